@@ -0,0 +1,307 @@
+//! Unpacking of the zip, tar, tar.gz and 7z archives given as input.
+//!
+//! Entries are written one at a time under their names cleaned of leading slashes and
+//! `.` components. Entries whose names climb out of the folder with `..`, links and
+//! special files are skipped with a warning rather than followed. Archives found inside
+//! are unpacked in place of the archive file, a few levels deep, and the entry count and
+//! unpacked size are capped across all of them. Encrypted zip entries are decrypted
+//! with the password given for the input archive, nested archives included.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tempfile::TempDir;
+use zip::result::ZipError;
+
+/// Levels of archives inside an input archive that are unpacked; deeper ones stay packed.
+const MAX_DEPTH: usize = 4;
+/// Most entries an input archive may hold, the archives inside it included.
+const MAX_ENTRIES: usize = 100_000;
+/// Most an input archive may unpack to, the archives inside it included.
+const MAX_BYTES: u64 = 2 << 30;
+
+/// Error for an encrypted zip unpacked without a password.
+pub const PASSWORD_REQUIRED: &str = "Password required";
+/// Error for an encrypted zip unpacked with the wrong password.
+pub const WRONG_PASSWORD: &str = "Wrong password";
+
+/// Archive formats accepted as input, told apart by file name.
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+    SevenZip,
+}
+
+impl Kind {
+    const EXTENSIONS: [(&'static str, Self); 5] = [
+        (".zip", Self::Zip),
+        (".tar", Self::Tar),
+        (".tar.gz", Self::TarGz),
+        (".tgz", Self::TarGz),
+        (".7z", Self::SevenZip),
+    ];
+
+    pub fn of(path: &Path) -> Option<Self> {
+        Self::split(path).map(|(kind, _)| kind)
+    }
+
+    /// The archive format of the file at `path`, and its name without the extension.
+    fn split(path: &Path) -> Option<(Self, &str)> {
+        let name = path.file_name()?.to_str()?;
+        Self::EXTENSIONS.iter().find_map(|&(extension, kind)| {
+            let stem_len = name.len().checked_sub(extension.len())?;
+            let matches = stem_len > 0 && name.get(stem_len..)?.eq_ignore_ascii_case(extension);
+            matches.then(|| (kind, &name[..stem_len]))
+        })
+    }
+}
+
+pub struct Extracted {
+    pub dir: TempDir,
+    /// Entries left out and nested archives left packed, with the reason.
+    pub warnings: Vec<String>,
+}
+
+/// Unpacks the archive at `path` into a new temporary folder, along with the archives
+/// inside it. `password` decrypts encrypted zip entries.
+pub fn extract(path: &Path, kind: Kind, password: Option<&str>) -> Result<Extracted, String> {
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let mut unpacker = Unpacker {
+        password: password.map(str::to_string),
+        ..Unpacker::default()
+    };
+    let label = path.file_name().unwrap_or_default().to_string_lossy();
+    unpacker.unpack(path, kind, dir.path(), &label, 0)?;
+
+    while let Some(nested) = unpacker.nested.pop() {
+        let Some((kind, stem)) = Kind::split(&nested.path) else {
+            continue;
+        };
+        let dest = nested.path.with_file_name(stem);
+        let existed = dest.exists();
+        match unpacker.unpack(&nested.path, kind, &dest, &nested.label, nested.depth) {
+            Ok(()) => {
+                let _ = fs::remove_file(&nested.path);
+            }
+            Err(err) if unpacker.over_limit => return Err(err),
+            Err(err) => {
+                if !existed {
+                    let _ = fs::remove_dir_all(&dest);
+                }
+                unpacker
+                    .warnings
+                    .push(format!("Left {} packed: {err}", nested.label));
+            }
+        }
+    }
+
+    Ok(Extracted {
+        dir,
+        warnings: unpacker.warnings,
+    })
+}
+
+/// An archive written out while unpacking another one.
+struct Nested {
+    path: PathBuf,
+    /// Path of the archive through the archives holding it, for messages.
+    label: String,
+    depth: usize,
+}
+
+#[derive(Default)]
+struct Unpacker {
+    entries: usize,
+    bytes: u64,
+    /// Set once a limit is passed, which fails the whole extraction.
+    over_limit: bool,
+    warnings: Vec<String>,
+    nested: Vec<Nested>,
+    password: Option<String>,
+}
+
+impl Unpacker {
+    /// Writes the entries of the archive at `path` under `dest`. `label` names the
+    /// archive in messages and `depth` counts the archives holding it.
+    fn unpack(
+        &mut self,
+        path: &Path,
+        kind: Kind,
+        dest: &Path,
+        label: &str,
+        depth: usize,
+    ) -> Result<(), String> {
+        let target = Target { dest, label, depth };
+        match kind {
+            Kind::Zip => self.unpack_zip(path, &target),
+            Kind::Tar => self.unpack_tar(File::open(path).map_err(|err| err.to_string())?, &target),
+            Kind::TarGz => {
+                let file = File::open(path).map_err(|err| err.to_string())?;
+                self.unpack_tar(GzDecoder::new(file), &target)
+            }
+            Kind::SevenZip => self.unpack_7z(path, &target),
+        }
+    }
+
+    fn unpack_zip(&mut self, path: &Path, target: &Target) -> Result<(), String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|err| err.to_string())?;
+        for index in 0..archive.len() {
+            let entry = match &self.password {
+                Some(password) => archive.by_index_decrypt(index, password.as_bytes()),
+                None => archive.by_index(index),
+            };
+            let mut entry = entry.map_err(|err| match err {
+                ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+                    PASSWORD_REQUIRED.to_string()
+                }
+                ZipError::InvalidPassword => WRONG_PASSWORD.to_string(),
+                err => err.to_string(),
+            })?;
+            let name = entry.name().to_string();
+            if entry.is_symlink() {
+                self.skip(target, &name, "links are not unpacked");
+            } else if entry.is_dir() {
+                self.add_dir(target, &name)?;
+            } else {
+                self.add_file(target, &name, &mut entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn unpack_tar(&mut self, reader: impl Read, target: &Target) -> Result<(), String> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries().map_err(|err| err.to_string())? {
+            let mut entry = entry.map_err(|err| err.to_string())?;
+            let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_dir() {
+                self.add_dir(target, &name)?;
+            } else if entry_type.is_file() {
+                self.add_file(target, &name, &mut entry)?;
+            } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+                self.skip(target, &name, "links are not unpacked");
+            } else {
+                self.skip(target, &name, "it is not a regular file");
+            }
+        }
+        Ok(())
+    }
+
+    fn unpack_7z(&mut self, path: &Path, target: &Target) -> Result<(), String> {
+        let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+            .map_err(|err| err.to_string())?;
+        // The reader's errors print as debug output, so ours are passed on separately.
+        let mut failure = None;
+        let read = reader.for_each_entries(|entry, data| {
+            let name = entry.name().to_string();
+            let added = if entry.is_directory() {
+                self.add_dir(target, &name)
+            } else {
+                self.add_file(target, &name, data)
+            };
+            if let Err(err) = added {
+                failure = Some(err);
+                return Err(sevenz_rust::Error::other("extraction stopped"));
+            }
+            // Entries share one stream, so a skipped entry still has to be read past.
+            io::copy(data, &mut io::sink())?;
+            Ok(true)
+        });
+        match failure {
+            Some(err) => Err(err),
+            None => read.map_err(|err| err.to_string()),
+        }
+    }
+
+    fn add_dir(&mut self, target: &Target, name: &str) -> Result<(), String> {
+        self.count_entry(target)?;
+        match sanitize(name) {
+            Some(relative) => {
+                fs::create_dir_all(target.dest.join(relative)).map_err(|err| err.to_string())
+            }
+            None => {
+                self.skip(target, name, "its path leads outside the archive");
+                Ok(())
+            }
+        }
+    }
+
+    fn add_file(&mut self, target: &Target, name: &str, data: &mut dyn Read) -> Result<(), String> {
+        self.count_entry(target)?;
+        let Some(relative) = sanitize(name).filter(|path| !path.as_os_str().is_empty()) else {
+            self.skip(target, name, "its path leads outside the archive");
+            return Ok(());
+        };
+        let path = target.dest.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let mut file = File::create(&path).map_err(|err| err.to_string())?;
+        let allowance = MAX_BYTES - self.bytes;
+        self.bytes +=
+            io::copy(&mut data.take(allowance + 1), &mut file).map_err(|err| err.to_string())?;
+        if self.bytes > MAX_BYTES {
+            self.over_limit = true;
+            return Err(format!(
+                "{} unpacks to more than {} MB",
+                target.label,
+                MAX_BYTES >> 20
+            ));
+        }
+
+        if Kind::of(&path).is_some() && target.depth < MAX_DEPTH {
+            self.nested.push(Nested {
+                path,
+                label: format!("{}/{}", target.label, relative.display()),
+                depth: target.depth + 1,
+            });
+        }
+        Ok(())
+    }
+
+    fn count_entry(&mut self, target: &Target) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            self.over_limit = true;
+            return Err(format!(
+                "{} holds more than {MAX_ENTRIES} entries",
+                target.label
+            ));
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, target: &Target, name: &str, reason: &str) {
+        self.warnings
+            .push(format!("Skipped {name} in {}: {reason}", target.label));
+    }
+}
+
+/// Where the entries of the archive being unpacked go.
+struct Target<'a> {
+    dest: &'a Path,
+    label: &'a str,
+    depth: usize,
+}
+
+/// `name` as a path relative to the folder the archive is unpacked into, or `None` when
+/// it would lead outside that folder.
+fn sanitize(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            // Drive letters and alternate data streams.
+            part if part.contains(':') => return None,
+            part => path.push(part),
+        }
+    }
+    Some(path)
+}
@@ -0,0 +1,109 @@
+//! Attribute blocks written straight after an image, as in
+//! `![alt](img.png){width=60% align=center}`.
+//!
+//! Sizes are a percentage of the text area, millimetres (`mm`, `cm`, `in`) or CSS pixels
+//! (`px`, the default unit). Pandoc's `.class` and `#id` entries and unknown keys are
+//! skipped, so blocks written for other tools still parse.
+
+const MM_PER_INCH: f32 = 25.4;
+const CSS_PX_PER_INCH: f32 = 96.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImageAttributes {
+    pub width: Option<Length>,
+    pub height: Option<Length>,
+    pub align: Align,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    /// Percentage of the text area's width or height.
+    Percent(f32),
+    Mm(f32),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Length {
+    pub fn to_mm(self, full_mm: f32) -> f32 {
+        match self {
+            Length::Percent(percent) => full_mm * percent / 100.0,
+            Length::Mm(mm) => mm,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Length> {
+        let split = value
+            .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let number: f32 = number.parse().ok()?;
+        let length = match unit.to_ascii_lowercase().as_str() {
+            "%" => Length::Percent(number),
+            "mm" => Length::Mm(number),
+            "cm" => Length::Mm(number * 10.0),
+            "in" => Length::Mm(number * MM_PER_INCH),
+            "" | "px" => Length::Mm(number * MM_PER_INCH / CSS_PX_PER_INCH),
+            _ => return None,
+        };
+        (number > 0.0).then_some(length)
+    }
+}
+
+impl ImageAttributes {
+    /// Scale that gives an image `width_mm` by `height_mm` in size the requested size, in
+    /// a text area `area_width_mm` by `area_height_mm`. A lone width or height keeps the
+    /// aspect ratio; with both, the image fits inside them.
+    pub fn scale(
+        &self,
+        width_mm: f32,
+        height_mm: f32,
+        area_width_mm: f32,
+        area_height_mm: f32,
+    ) -> Option<f32> {
+        let width = self
+            .width
+            .map(|width| width.to_mm(area_width_mm) / width_mm);
+        let height = self
+            .height
+            .map(|height| height.to_mm(area_height_mm) / height_mm);
+        match (width, height) {
+            (Some(width), Some(height)) => Some(width.min(height)),
+            (width, height) => width.or(height),
+        }
+    }
+}
+
+/// Attributes at the start of `text`, and the text after the block. `None` when `text`
+/// does not start with a block.
+pub fn parse(text: &str) -> Option<(ImageAttributes, &str)> {
+    let body = text.strip_prefix('{')?;
+    let end = body.find('}')?;
+    let mut attributes = ImageAttributes::default();
+    for entry in body[..end].split_whitespace() {
+        if entry.starts_with('.') || entry.starts_with('#') {
+            continue;
+        }
+        let (key, value) = entry.split_once('=')?;
+        let value = value.trim_matches(|ch| ch == '"' || ch == '\'');
+        match key.to_ascii_lowercase().as_str() {
+            "width" => attributes.width = Length::parse(value),
+            "height" => attributes.height = Length::parse(value),
+            "align" => {
+                attributes.align = match value.to_ascii_lowercase().as_str() {
+                    "center" | "centre" => Align::Center,
+                    "right" => Align::Right,
+                    _ => Align::Left,
+                }
+            }
+            _ => {}
+        }
+    }
+    Some((attributes, &body[end + 1..]))
+}
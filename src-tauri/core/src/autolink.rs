@@ -0,0 +1,103 @@
+//! Detection of bare URLs and email addresses, following GFM's extended autolinks.
+
+/// Splits `text` into pieces, pairing each URL or email address with its link destination.
+pub fn segments(text: &str) -> Vec<(&str, Option<String>)> {
+    let mut pieces = Vec::new();
+    let mut plain_start = 0;
+    let mut word_start = None;
+
+    for (index, ch) in text.char_indices().chain([(text.len(), ' ')]) {
+        if !ch.is_whitespace() {
+            word_start.get_or_insert(index);
+            continue;
+        }
+        let Some(start) = word_start.take() else {
+            continue;
+        };
+        if let Some((link_start, link_end, dest)) = find_link(&text[start..index]) {
+            let (link_start, link_end) = (start + link_start, start + link_end);
+            if plain_start < link_start {
+                pieces.push((&text[plain_start..link_start], None));
+            }
+            pieces.push((&text[link_start..link_end], Some(dest)));
+            plain_start = link_end;
+        }
+    }
+    if plain_start < text.len() {
+        pieces.push((&text[plain_start..], None));
+    }
+
+    pieces
+}
+
+/// Finds a link inside one whitespace-delimited word, returning its byte range and destination.
+fn find_link(word: &str) -> Option<(usize, usize, String)> {
+    for prefix in ["https://", "http://", "www."] {
+        let Some(start) = word.find(prefix) else {
+            continue;
+        };
+        if !starts_after_delimiter(word, start) {
+            continue;
+        }
+        let end = start + trim_trailing(&word[start..]);
+        if end <= start + prefix.len()
+            || !word[start + prefix.len()..end].contains(|c: char| c.is_alphanumeric())
+        {
+            continue;
+        }
+        let url = &word[start..end];
+        let dest = if prefix == "www." {
+            format!("http://{url}")
+        } else {
+            url.to_string()
+        };
+        return Some((start, end, dest));
+    }
+
+    find_email(word)
+}
+
+fn find_email(word: &str) -> Option<(usize, usize, String)> {
+    let at = word.find('@')?;
+    let is_local = |ch: char| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '+' | '-');
+    let start = word[..at]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_local(*ch))
+        .last()
+        .map(|(index, _)| index)?;
+    let domain_len = word[at + 1..]
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_')))
+        .unwrap_or(word.len() - at - 1);
+    let domain = word[at + 1..at + 1 + domain_len].trim_end_matches('.');
+    let valid_domain = domain.contains('.')
+        && !domain.ends_with(['-', '_'])
+        && domain.split('.').all(|label| !label.is_empty());
+    if !valid_domain || !starts_after_delimiter(word, start) {
+        return None;
+    }
+
+    let end = at + 1 + domain.len();
+    Some((start, end, format!("mailto:{}", &word[start..end])))
+}
+
+/// GFM only recognises autolinks at the start of a word or after an opening delimiter.
+fn starts_after_delimiter(word: &str, start: usize) -> bool {
+    word[..start]
+        .chars()
+        .next_back()
+        .is_none_or(|ch| matches!(ch, '(' | '*' | '_' | '~' | '"' | '\'' | '<'))
+}
+
+/// Length of a URL candidate once trailing punctuation and unbalanced `)` are dropped.
+fn trim_trailing(candidate: &str) -> usize {
+    let mut end = candidate.find('<').unwrap_or(candidate.len());
+    loop {
+        let url = &candidate[..end];
+        match url.chars().next_back() {
+            Some('?' | '!' | '.' | ',' | ':' | '*' | '_' | '~' | '\'' | '"' | ';') => end -= 1,
+            Some(')') if url.matches(')').count() > url.matches('(').count() => end -= 1,
+            _ => return end,
+        }
+    }
+}
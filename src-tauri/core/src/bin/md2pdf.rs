@@ -0,0 +1,300 @@
+//! Command-line front end converting markdown files, folders, archives and URLs into one
+//! PDF without the app, for scripts and CI.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use markdown2pdf_core::{
+    convert_each_subfolder, default_output_path, free_output_path, merge_pdf_files,
+    open_in_viewer, render_markdown_pdf, render_plain_text, scan_inputs, ExistingOutput,
+    Orientation, PageSize, RenderOptions,
+};
+
+const USAGE: &str = "\
+Usage: md2pdf [OPTIONS] <INPUT>...
+
+Converts markdown files, folders, zip, tar or 7z archives and URLs into one PDF.
+With --merge, joins the PDF inputs into one instead, and with --per-folder, converts
+each subfolder of the one folder given into a PDF of its own.
+
+Options:
+  -o, --output <FILE>        PDF to write [default: markdown_export.pdf next to the input]
+      --name <TEMPLATE>      Name of the PDF next to the input, such as
+                             {title}-{date}.pdf or {folder}-export.pdf
+      --if-exists <ACTION>   When the PDF exists: ask (fail), overwrite or increment
+                             (write name (2).pdf) [default: overwrite]
+      --text                 Writes plain text, as a .txt, instead of a PDF
+      --open                 Opens the written file in the default viewer
+      --before <PDF>         Puts the pages of a PDF, such as a cover, before the
+                             document; may be given more than once
+      --after <PDF>          Puts the pages of a PDF after the document; may be given
+                             more than once
+      --merge                Merges the PDF inputs in the order given
+                             [default output: merged.pdf next to the first]
+      --per-folder           Writes one PDF per subfolder, named after it unless
+                             --name is given, into the folder -o names
+                             [default: the input folder]
+      --pages <RANGE>        Keeps only these pages of the document, such as 10-25
+                             or 1-3,8,12-
+      --section <TITLE>      Keeps only the pages of the level-1 or level-2 heading
+                             with this title; may be given more than once
+      --split-pages <N>      Splits the PDF into parts of at most N pages, written as
+                             name-1.pdf, name-2.pdf and so on
+      --split-mb <N>         Splits the PDF into parts of about N megabytes at most
+      --page-size <SIZE>     a3, a4, a5, letter or legal [default: a4]
+      --landscape            Lays the pages out in landscape
+      --theme <FILE>         TOML or JSON theme file
+      --options <FILE>       JSON file of render options, as the app sends them; the
+                             flags above take precedence
+      --password <PASSWORD>  Password of encrypted zip inputs
+  -h, --help                 Prints this help
+";
+
+struct Args {
+    inputs: Vec<String>,
+    output: Option<PathBuf>,
+    options: RenderOptions,
+    password: Option<String>,
+    text: bool,
+    open: bool,
+    merge: bool,
+    per_folder: bool,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("md2pdf: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), String> {
+    let Some(args) = parse_args(env::args().skip(1))? else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+    if args.merge {
+        return merge(args);
+    }
+    if args.per_folder {
+        return per_folder(args);
+    }
+
+    let passwords: HashMap<String, String> = match &args.password {
+        Some(password) => args
+            .inputs
+            .iter()
+            .map(|input| (input.clone(), password.clone()))
+            .collect(),
+        None => HashMap::new(),
+    };
+    // Downloaded input has no folder of its own, so its PDF goes in the working folder.
+    let download_dir = || env::current_dir().map_err(|err| err.to_string());
+    let scanned = scan_inputs(
+        args.inputs,
+        &Default::default(),
+        &Default::default(),
+        &passwords,
+        download_dir,
+    )?;
+    let input = &scanned.input;
+    for warning in &input.warnings {
+        eprintln!("md2pdf: warning: {warning}");
+    }
+    if input.markdown_files.is_empty() {
+        return Err("No markdown files found".to_string());
+    }
+
+    let output = match args.output {
+        Some(output) => output,
+        None if args.text => default_output_path(input, Some(&args.options))?.with_extension("txt"),
+        None => default_output_path(input, Some(&args.options))?,
+    };
+    let written = if args.text {
+        let (output, _) = free_output_path(output, args.options.existing_output)?;
+        render_plain_text(&input.markdown_files, &input.chapters, &output, &args.options)?;
+        vec![output]
+    } else {
+        let written = render_markdown_pdf(
+            &input.markdown_files,
+            &input.chapters,
+            &input.image_files,
+            &output,
+            args.options,
+        )?;
+        written.into_iter().map(|(path, _)| path).collect()
+    };
+    for path in &written {
+        println!("{}", path.display());
+    }
+    if let (true, Some(first)) = (args.open, written.first()) {
+        open_in_viewer(first)?;
+    }
+    Ok(())
+}
+
+fn merge(args: Args) -> Result<(), String> {
+    let output = match args.output {
+        Some(output) => output,
+        None => Path::new(&args.inputs[0]).with_file_name("merged.pdf"),
+    };
+    let (output, _) = free_output_path(output, args.options.existing_output)?;
+    merge_pdf_files(&args.inputs, &output)?;
+    println!("{}", output.display());
+    if args.open {
+        open_in_viewer(&output)?;
+    }
+    Ok(())
+}
+
+fn per_folder(args: Args) -> Result<(), String> {
+    let [folder] = args.inputs.as_slice() else {
+        return Err("--per-folder takes a single folder".to_string());
+    };
+    let folder = PathBuf::from(folder);
+    let output_dir = args.output.unwrap_or_else(|| folder.clone());
+    let result = convert_each_subfolder(
+        &folder,
+        &output_dir,
+        &Default::default(),
+        &Default::default(),
+        &args.options,
+    )?;
+    for converted in &result.converted {
+        for path in &converted.output_paths {
+            println!("{path}");
+        }
+    }
+    for name in &result.skipped {
+        eprintln!("md2pdf: skipped {name}: no markdown files");
+    }
+    for failure in &result.failed {
+        eprintln!("md2pdf: {failure}");
+    }
+    if !result.failed.is_empty() {
+        return Err(format!("{} folders could not be converted", result.failed.len()));
+    }
+    Ok(())
+}
+
+/// Reads the command line, or `None` when help was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut inputs = Vec::new();
+    let mut output = None;
+    let mut page_size = None;
+    let mut landscape = false;
+    let mut theme = None;
+    let mut options_file = None;
+    let mut password = None;
+    let mut name = None;
+    let mut if_exists = None;
+    let mut text = false;
+    let mut open = false;
+    let mut merge = false;
+    let mut per_folder = false;
+    let mut before = Vec::new();
+    let mut pages = None;
+    let mut sections = Vec::new();
+    let mut split_pages = None;
+    let mut split_mb = None;
+    let mut after = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{name} needs a value\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+            "--page-size" => page_size = Some(value(&arg)?),
+            "--landscape" => landscape = true,
+            "--theme" => theme = Some(value(&arg)?),
+            "--options" => options_file = Some(value(&arg)?),
+            "--password" => password = Some(value(&arg)?),
+            "--name" => name = Some(value(&arg)?),
+            "--if-exists" => if_exists = Some(value(&arg)?),
+            "--text" => text = true,
+            "--open" => open = true,
+            "--before" => before.push(value(&arg)?),
+            "--after" => after.push(value(&arg)?),
+            "--merge" => merge = true,
+            "--per-folder" => per_folder = true,
+            "--pages" => pages = Some(value(&arg)?),
+            "--section" => sections.push(value(&arg)?),
+            "--split-pages" => split_pages = Some(value(&arg)?),
+            "--split-mb" => split_mb = Some(value(&arg)?),
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("Unknown option {flag}\n\n{USAGE}"));
+            }
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        return Err(format!("No input given\n\n{USAGE}"));
+    }
+
+    let mut options: RenderOptions = match options_file {
+        Some(path) => {
+            let contents = fs::read_to_string(&path).map_err(|err| format!("{path}: {err}"))?;
+            serde_json::from_str(&contents).map_err(|err| format!("{path}: {err}"))?
+        }
+        None => RenderOptions::default(),
+    };
+    if let Some(size) = page_size {
+        options.page_size = serde_json::from_value::<PageSize>(size.to_lowercase().into())
+            .map_err(|_| format!("Unknown page size {size}"))?;
+    }
+    if landscape {
+        options.orientation = Orientation::Landscape;
+    }
+    if theme.is_some() {
+        options.theme = theme;
+    }
+    if name.is_some() {
+        options.output_name = name;
+    }
+    options.pdfs_before.extend(before);
+    options.pdfs_after.extend(after);
+    if pages.is_some() {
+        options.page_range = pages;
+    }
+    options.sections.extend(sections);
+    if split_pages.is_some() || split_mb.is_some() {
+        let mut split = options.split.unwrap_or_default();
+        if let Some(pages) = split_pages {
+            split.max_pages =
+                Some(pages.parse().map_err(|_| format!("Invalid --split-pages count {pages}"))?);
+        }
+        if let Some(megabytes) = split_mb {
+            split.max_megabytes = Some(
+                megabytes
+                    .parse()
+                    .map_err(|_| format!("Invalid --split-mb size {megabytes}"))?,
+            );
+        }
+        options.split = Some(split);
+    }
+    if let Some(action) = if_exists {
+        options.existing_output =
+            serde_json::from_value::<ExistingOutput>(action.to_lowercase().into())
+                .map_err(|_| format!("Unknown --if-exists action {action}"))?;
+    }
+
+    Ok(Some(Args {
+        inputs,
+        output,
+        options,
+        password,
+        text,
+        open,
+        merge,
+        per_folder,
+    }))
+}
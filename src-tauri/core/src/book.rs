@@ -0,0 +1,92 @@
+//! mdBook books: the chapters a `SUMMARY.md` lists, in book order.
+//!
+//! The summary is looked for in the source folder named by `book.toml` (`src` unless
+//! `[book] src` says otherwise), or else next to the files. Each link in it is a chapter,
+//! nested under the chapter of the list item holding its list. Draft chapters, which have
+//! no file, and part titles are left out.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub path: String,
+    pub title: String,
+    /// Chapters above this one in the book, `0` for top-level chapters.
+    pub depth: u32,
+    /// Titles of the sections this chapter is the first page of, outermost first, for
+    /// sites whose navigation groups pages under sections without a page of their own.
+    #[serde(default)]
+    pub sections: Vec<String>,
+}
+
+/// The `SUMMARY.md` of the book in the folder `root`, if it holds one.
+pub fn summary_path(root: &Path) -> Result<Option<PathBuf>, String> {
+    let manifest = root.join("book.toml");
+    if manifest.is_file() {
+        let contents = fs::read_to_string(&manifest).map_err(|err| err.to_string())?;
+        let table: toml::Table = toml::from_str(&contents)
+            .map_err(|err| format!("Invalid {}: {err}", manifest.display()))?;
+        let src = table
+            .get("book")
+            .and_then(|book| book.get("src"))
+            .and_then(|src| src.as_str())
+            .unwrap_or("src");
+        let summary = root.join(src).join("SUMMARY.md");
+        if summary.is_file() {
+            return Ok(Some(summary));
+        }
+    }
+    let summary = root.join("SUMMARY.md");
+    Ok(summary.is_file().then_some(summary))
+}
+
+/// The chapters listed in the summary at `path`, with their paths resolved against its
+/// folder. A file listed twice is kept where it first appears.
+pub fn chapters(path: &Path) -> Result<Vec<Chapter>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut lists = 0u32;
+    let mut link: Option<(String, String)> = None;
+
+    for event in Parser::new(&contents) {
+        match event {
+            Event::Start(Tag::List(_)) => lists += 1,
+            Event::End(TagEnd::List(_)) => lists = lists.saturating_sub(1),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                link = Some((dest_url.to_string(), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = link.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                let Some((dest, title)) = link.take() else {
+                    continue;
+                };
+                let file = dest.split('#').next().unwrap_or_default();
+                if file.is_empty() {
+                    continue;
+                }
+                let path = base.join(file).to_string_lossy().into_owned();
+                if chapters.iter().any(|chapter| chapter.path == path) {
+                    continue;
+                }
+                chapters.push(Chapter {
+                    path,
+                    title: title.trim().to_string(),
+                    depth: lists.saturating_sub(1),
+                    sections: Vec::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(chapters)
+}
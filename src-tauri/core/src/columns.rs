@@ -0,0 +1,91 @@
+//! The `:::columns N` ... `:::` directive for multi-column sections.
+//!
+//! Directives are found line by line before markdown parsing, so each section is parsed
+//! on its own; reference-style link definitions only apply within their own section.
+
+pub enum Block<'a> {
+    Markdown(&'a str),
+    Columns { count: usize, body: &'a str },
+}
+
+const MAX_COLUMNS: usize = 4;
+
+/// Splits a file into plain markdown and column sections. Directives inside fenced code
+/// blocks are ignored, and an unclosed directive runs to the end of the file.
+pub fn split(contents: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut section_start = 0;
+    let mut open: Option<(usize, usize)> = None;
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for line in contents.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) && trimmed.trim_start_matches(marker).is_empty() {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        match open {
+            None => {
+                if let Some(count) = opening_count(trimmed) {
+                    if section_start < line_start {
+                        blocks.push(Block::Markdown(&contents[section_start..line_start]));
+                    }
+                    open = Some((count, offset));
+                }
+            }
+            Some((count, body_start)) => {
+                if is_closing(trimmed) {
+                    blocks.push(Block::Columns {
+                        count,
+                        body: &contents[body_start..line_start],
+                    });
+                    open = None;
+                    section_start = offset;
+                }
+            }
+        }
+    }
+
+    match open {
+        Some((count, body_start)) => blocks.push(Block::Columns {
+            count,
+            body: &contents[body_start..],
+        }),
+        None if section_start < contents.len() => {
+            blocks.push(Block::Markdown(&contents[section_start..]))
+        }
+        None => {}
+    }
+
+    blocks
+}
+
+/// Reads `:::columns 2` (or `::: columns 3`), returning the column count.
+fn opening_count(line: &str) -> Option<usize> {
+    let rest = line
+        .strip_prefix(":::")?
+        .trim_start_matches(':')
+        .trim_start();
+    let count = rest.strip_prefix("columns")?.trim();
+    let count = if count.is_empty() {
+        2
+    } else {
+        count.parse().ok()?
+    };
+    Some(count.clamp(1, MAX_COLUMNS))
+}
+
+fn is_closing(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|ch| ch == ':')
+}
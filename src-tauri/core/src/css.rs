@@ -0,0 +1,274 @@
+//! A small CSS subset mapped onto the document style, so a stylesheet written for HTML
+//! previews can be reused.
+//!
+//! Only plain element selectors are understood: `body`, `p`, `h1` to `h6`, `a`, `code`,
+//! `pre`, `blockquote` and `table`. Their `font-size`, `color`, `margin` and `text-align`
+//! declarations are applied where the renderer has a matching setting; `body` also
+//! accepts `font-family` and `line-height`, and `a` accepts `text-decoration`. Headings
+//! share one colour and spacing, so the last heading rule wins. Anything else is ignored.
+
+use crate::style::{FontFamily, Style, TextAlign};
+
+/// Applies the supported rules of `stylesheet` to `style`, in source order.
+pub fn apply(stylesheet: &str, style: &mut Style) {
+    let stylesheet = strip_comments(stylesheet);
+    let mut rest = stylesheet.as_str();
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        let selectors = &rest[..open];
+        let body = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+
+        for selector in selectors.split(',').map(str::trim) {
+            let selector = selector.to_ascii_lowercase();
+            for declaration in body.split(';') {
+                let Some((property, value)) = declaration.split_once(':') else {
+                    continue;
+                };
+                let property = property.trim().to_ascii_lowercase();
+                let value = value.trim().trim_end_matches("!important").trim();
+                apply_declaration(style, &selector, &property, value);
+            }
+        }
+    }
+}
+
+fn apply_declaration(style: &mut Style, selector: &str, property: &str, value: &str) {
+    let heading = heading_level(selector);
+    match (selector, property) {
+        (_, "font-size") => {
+            let Some(size) = length_pt(value, style.body_size) else {
+                return;
+            };
+            match selector {
+                "body" | "p" => style.body_size = size,
+                "code" | "pre" => style.code_size = size,
+                "table" => style.table_size = size,
+                "blockquote" => style.quote_size = size,
+                _ => {
+                    if let Some(level) = heading {
+                        set_heading_size(style, level, size);
+                    }
+                }
+            }
+        }
+        (_, "color") => {
+            let Some(color) = color(value) else {
+                return;
+            };
+            match selector {
+                "body" | "p" => style.text_color = color,
+                "code" | "pre" => style.code_color = color,
+                "blockquote" => style.quote_color = color,
+                "a" => style.link_color = color,
+                _ if heading.is_some() => style.heading_color = color,
+                _ => {}
+            }
+        }
+        ("code", "background-color" | "background") => {
+            if let Some(color) = color(value) {
+                style.code_background = color;
+            }
+        }
+        ("a", "text-decoration" | "text-decoration-line") => {
+            style.underline_links = value.to_ascii_lowercase().contains("underline");
+        }
+        ("table", "border-color") => {
+            if let Some(color) = color(value) {
+                style.table_border_color = color;
+            }
+        }
+        (_, "text-align") => {
+            let align = match value.to_ascii_lowercase().as_str() {
+                "left" | "start" => TextAlign::Left,
+                "center" => TextAlign::Center,
+                "right" | "end" => TextAlign::Right,
+                "justify" => TextAlign::Justify,
+                _ => return,
+            };
+            match selector {
+                "body" | "p" => style.text_align = align,
+                _ if heading.is_some() => style.heading_align = align,
+                _ => {}
+            }
+        }
+        (_, "margin" | "margin-top" | "margin-bottom" | "margin-left") => {
+            let [top, _, bottom, left] = if property == "margin" {
+                match margin_shorthand(value, style.body_size) {
+                    Some(sides) => sides.map(Some),
+                    None => return,
+                }
+            } else {
+                let length = length_pt(value, style.body_size);
+                match property {
+                    "margin-top" => [length, None, None, None],
+                    "margin-bottom" => [None, None, length, None],
+                    _ => [None, None, None, length],
+                }
+            };
+            match selector {
+                "p" => {
+                    if let Some(top) = top {
+                        style.paragraph_spacing_before = top;
+                    }
+                    if let Some(bottom) = bottom {
+                        style.block_spacing = bottom;
+                    }
+                }
+                "blockquote" => {
+                    if let Some(left) = left {
+                        style.quote_indent = left;
+                    }
+                }
+                _ if heading.is_some() => {
+                    if let Some(bottom) = bottom {
+                        style.heading_spacing = bottom;
+                    }
+                }
+                _ => {}
+            }
+        }
+        ("body", "line-height") => {
+            if let Ok(factor) = value.parse::<f32>() {
+                style.line_spacing = factor;
+            } else if let Some(height) = length_pt(value, style.body_size) {
+                style.line_spacing = height / style.body_size;
+            }
+        }
+        ("body", "font-family") => {
+            let value = value.to_ascii_lowercase();
+            let serif = value
+                .split(',')
+                .map(|name| name.trim().trim_matches(['"', '\'']));
+            for name in serif {
+                match name {
+                    "serif" | "times" | "times new roman" | "georgia" => {
+                        style.font_family = FontFamily::Times;
+                        return;
+                    }
+                    "sans-serif" | "helvetica" | "arial" => {
+                        style.font_family = FontFamily::Helvetica;
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn heading_level(selector: &str) -> Option<u32> {
+    let level = selector.strip_prefix('h')?.parse::<u32>().ok()?;
+    (1..=6).contains(&level).then_some(level)
+}
+
+fn set_heading_size(style: &mut Style, level: u32, size: f32) {
+    let index = (level - 1) as usize;
+    while style.heading_sizes.len() <= index {
+        let last = style
+            .heading_sizes
+            .last()
+            .copied()
+            .unwrap_or(style.body_size);
+        style.heading_sizes.push(last);
+    }
+    style.heading_sizes[index] = size;
+}
+
+/// Top, right, bottom and left from a one to four value `margin`, in points.
+fn margin_shorthand(value: &str, font_size: f32) -> Option<[f32; 4]> {
+    let values = value
+        .split_whitespace()
+        .map(|part| length_pt(part, font_size))
+        .collect::<Option<Vec<_>>>()?;
+    match values[..] {
+        [all] => Some([all; 4]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
+/// Converts a CSS length to points; `em` and `%` are relative to `font_size`.
+fn length_pt(value: &str, font_size: f32) -> Option<f32> {
+    let value = value.trim().to_ascii_lowercase();
+    let split = value
+        .find(|ch: char| !(ch.is_ascii_digit() || ch == '.' || ch == '-'))
+        .unwrap_or(value.len());
+    let number: f32 = value[..split].parse().ok()?;
+    let points = match &value[split..] {
+        "pt" => number,
+        "px" => number * 0.75,
+        "" if number == 0.0 => 0.0,
+        "mm" => number * 72.0 / 25.4,
+        "cm" => number * 72.0 / 2.54,
+        "in" => number * 72.0,
+        "em" | "rem" => number * font_size,
+        "%" => number * font_size / 100.0,
+        _ => return None,
+    };
+    Some(points.max(0.0))
+}
+
+fn color(value: &str) -> Option<[f32; 3]> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        let digits: Vec<u8> = hex
+            .chars()
+            .map(|ch| ch.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()?;
+        let channels = match digits[..] {
+            [r, g, b] => [r * 17, g * 17, b * 17],
+            [r1, r2, g1, g2, b1, b2] => [r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2],
+            _ => return None,
+        };
+        return Some(channels.map(|channel| f32::from(channel) / 255.0));
+    }
+    if let Some(arguments) = value
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels = arguments
+            .split(',')
+            .map(|part| part.trim().parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let [r, g, b] = channels[..] else {
+            return None;
+        };
+        return Some([r, g, b].map(|channel| (channel / 255.0).clamp(0.0, 1.0)));
+    }
+    let named = match value.as_str() {
+        "black" => [0, 0, 0],
+        "white" => [255, 255, 255],
+        "gray" | "grey" => [128, 128, 128],
+        "silver" => [192, 192, 192],
+        "red" => [255, 0, 0],
+        "maroon" => [128, 0, 0],
+        "green" => [0, 128, 0],
+        "olive" => [128, 128, 0],
+        "blue" => [0, 0, 255],
+        "navy" => [0, 0, 128],
+        "purple" => [128, 0, 128],
+        "teal" => [0, 128, 128],
+        _ => return None,
+    };
+    Some(named.map(|channel: u8| f32::from(channel) / 255.0))
+}
+
+fn strip_comments(stylesheet: &str) -> String {
+    let mut stripped = String::with_capacity(stylesheet.len());
+    let mut rest = stylesheet;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
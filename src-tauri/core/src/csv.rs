@@ -0,0 +1,117 @@
+//! Standalone CSV files, rendered as a table.
+//!
+//! The file is read as RFC 4180 CSV: fields may be quoted, with `""` for a quote and line
+//! breaks kept inside quotes. Semicolon- and tab-separated files, as spreadsheets export
+//! them in some locales, are recognised by their first line. The first row is the header.
+
+/// Separators tried, the one found most often in the first line winning.
+const DELIMITERS: [char; 3] = [',', ';', '\t'];
+/// Characters escaped in cells so they are not read as markdown.
+const ESCAPED: &str = "\\`*_[]<>|~&#";
+
+/// The CSV file `contents` as a markdown table, or an empty string when it has no fields.
+pub fn to_markdown(contents: &str) -> String {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let rows = parse(contents, delimiter(contents));
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let line = |cells: &[String]| {
+        let mut line = String::from("|");
+        for column in 0..columns {
+            let cell = cells.get(column).map_or(String::new(), |cell| escape(cell));
+            line.push_str(&format!(" {cell} |"));
+        }
+        line
+    };
+    let mut table = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+    table.extend(rows[1..].iter().map(|row| line(row)));
+    let mut markdown = table.join("\n");
+    markdown.push('\n');
+    markdown
+}
+
+/// The separator of the first line of `contents`.
+fn delimiter(contents: &str) -> char {
+    let mut counts = [0usize; DELIMITERS.len()];
+    let mut quoted = false;
+    for ch in contents.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            '\n' | '\r' if !quoted => break,
+            ch if !quoted => {
+                if let Some(index) = DELIMITERS.iter().position(|delimiter| *delimiter == ch) {
+                    counts[index] += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    // Commas win ties, and files of a single column.
+    let mut best = 0;
+    for (index, &count) in counts.iter().enumerate() {
+        if count > counts[best] {
+            best = index;
+        }
+    }
+    DELIMITERS[best]
+}
+
+/// The rows of fields of `contents`, leaving out blank lines.
+fn parse(contents: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if quoted {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                ch => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => quoted = true,
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => end_row(&mut rows, &mut row, &mut field),
+            ch if ch == delimiter => row.push(std::mem::take(&mut field)),
+            ch => field.push(ch),
+        }
+    }
+    end_row(&mut rows, &mut row, &mut field);
+    rows
+}
+
+fn end_row(rows: &mut Vec<Vec<String>>, row: &mut Vec<String>, field: &mut String) {
+    if row.is_empty() && field.is_empty() {
+        return;
+    }
+    row.push(std::mem::take(field));
+    rows.push(std::mem::take(row));
+}
+
+/// `cell` as the text of a markdown table cell, line breaks kept as `<br>`.
+fn escape(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    for (index, line) in cell.trim().lines().enumerate() {
+        if index > 0 {
+            escaped.push_str("<br>");
+        }
+        for ch in line.trim().chars() {
+            if ESCAPED.contains(ch) {
+                escaped.push('\\');
+            }
+            escaped.push(ch);
+        }
+    }
+    escaped
+}
@@ -0,0 +1,97 @@
+//! Azure DevOps wikis: pages in the order their folders' `.order` files give.
+//!
+//! Each page is a file, `Page-Title.md`, and its subpages are in the folder beside it
+//! named like the page, `Page-Title/`. A folder's `.order` lists its page names one per
+//! line; pages it leaves out follow in natural order. File names stand for titles with
+//! spaces written as `-` and other characters, `-` among them, percent-encoded.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::book::Chapter;
+
+/// Whether the folder `root` is the top of a wiki.
+pub fn is_wiki(root: &Path) -> bool {
+    root.join(".order").is_file()
+}
+
+/// The pages among `found` under the wiki at `root`, each followed by its subpages.
+pub fn chapters(root: &Path, found: &[PathBuf]) -> Result<Vec<Chapter>, String> {
+    let mut chapters = Vec::new();
+    visit(root, 0, found, &mut chapters)?;
+    Ok(chapters)
+}
+
+fn visit(
+    folder: &Path,
+    depth: u32,
+    found: &[PathBuf],
+    chapters: &mut Vec<Chapter>,
+) -> Result<(), String> {
+    // Pages and subpage folders of this folder, by page name.
+    let mut pages: Vec<(String, &PathBuf)> = Vec::new();
+    let mut folders = BTreeSet::new();
+    for file in found {
+        let Ok(relative) = file.strip_prefix(folder) else {
+            continue;
+        };
+        let mut components = relative.components();
+        let first = components.next();
+        let first = first.map(|part| part.as_os_str().to_string_lossy().into_owned());
+        match (first, components.next()) {
+            (Some(_), None) => {
+                let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+                pages.push((stem.into_owned(), file));
+            }
+            (Some(name), Some(_)) => {
+                folders.insert(name);
+            }
+            _ => {}
+        }
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    for name in pages.iter().map(|(name, _)| name).chain(&folders) {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names.sort_by(|a, b| crate::natural_cmp(a, b));
+    let order = folder.join(".order");
+    if order.is_file() {
+        let listed = fs::read_to_string(&order).map_err(|err| err.to_string())?;
+        let mut ordered: Vec<String> = Vec::new();
+        for name in listed.lines().map(|line| line.trim().to_string()) {
+            if names.contains(&name) && !ordered.contains(&name) {
+                ordered.push(name);
+            }
+        }
+        names.retain(|name| !ordered.contains(name));
+        ordered.append(&mut names);
+        names = ordered;
+    }
+
+    for name in names {
+        let mut has_page = false;
+        for (_, path) in pages.iter().filter(|(page, _)| *page == name) {
+            chapters.push(Chapter {
+                path: path.to_string_lossy().into_owned(),
+                title: title(&name),
+                depth,
+                sections: Vec::new(),
+            });
+            has_page = true;
+        }
+        if folders.contains(&name) {
+            let depth = if has_page { depth + 1 } else { depth };
+            visit(&folder.join(&name), depth, found, chapters)?;
+        }
+    }
+    Ok(())
+}
+
+/// The title the page file name `name` stands for.
+fn title(name: &str) -> String {
+    crate::notion::decode(&name.replace('-', " "))
+}
@@ -0,0 +1,183 @@
+//! Emoji shortcode expansion and the optional TrueType font emoji are drawn with.
+//!
+//! The builtin PDF fonts have no emoji glyphs, so emoji are split into their own spans and
+//! set in an embedded font: the one named in the render options, or the first common
+//! system emoji font found. Without one they are set in the fallback fonts, and with none
+//! of those either they are written as their shortcodes.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use printpdf::PdfDocumentReference;
+
+use crate::truetype::EmbeddedFont;
+
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/noto/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/google-noto-emoji/NotoEmoji-Regular.ttf",
+    "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+    "/usr/share/fonts/TTF/Symbola.ttf",
+    "C:\\Windows\\Fonts\\seguiemj.ttf",
+    "C:\\Windows\\Fonts\\seguisym.ttf",
+    "/System/Library/Fonts/Apple Symbols.ttf",
+    "/Library/Fonts/NotoEmoji-Regular.ttf",
+];
+
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("arrow_down", "⬇"),
+    ("arrow_left", "⬅"),
+    ("arrow_right", "➡"),
+    ("arrow_up", "⬆"),
+    ("bangbang", "‼"),
+    ("bell", "🔔"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📅"),
+    ("check", "✔"),
+    ("clap", "👏"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("gear", "⚙"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("heart", "❤"),
+    ("heavy_check_mark", "✔"),
+    ("hourglass", "⌛"),
+    ("information_source", "ℹ"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("no_entry", "⛔"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("pencil2", "✏"),
+    ("point_right", "👉"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("see_no_evil", "🙈"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("trophy", "🏆"),
+    ("warning", "⚠"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+/// Embeds the font at `path`, or the first installed candidate when no path is given.
+/// An explicit path that cannot be loaded is an error; missing candidates are not.
+pub fn load_font(
+    doc: &PdfDocumentReference,
+    path: Option<&str>,
+) -> Result<Option<EmbeddedFont>, String> {
+    if let Some(path) = path {
+        return EmbeddedFont::embed(doc, Path::new(path)).map(Some);
+    }
+    Ok(FONT_CANDIDATES
+        .iter()
+        .map(Path::new)
+        .filter(|path| path.is_file())
+        .find_map(|path| EmbeddedFont::embed(doc, path).ok()))
+}
+
+/// Replaces known `:shortcode:` names with their emoji, leaving unknown ones untouched.
+pub fn expand_shortcodes(text: &str) -> Cow<'_, str> {
+    if !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '+' | '-')))
+            .unwrap_or(after.len());
+        let emoji = after[name_len..]
+            .starts_with(':')
+            .then(|| lookup(&after[..name_len]))
+            .flatten();
+        match emoji {
+            Some(emoji) => {
+                expanded.push_str(emoji);
+                rest = &after[name_len + 1..];
+            }
+            None => {
+                expanded.push(':');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
+
+/// `text` with each emoji that has a shortcode written as `:shortcode:`, the longest name
+/// when it has several.
+pub fn shortcodes(text: &str) -> String {
+    let mut written = String::with_capacity(text.len());
+    for ch in text.chars() {
+        let name = SHORTCODES
+            .iter()
+            .filter(|(_, emoji)| emoji.chars().eq([ch]))
+            .map(|(name, _)| *name)
+            .max_by_key(|name| name.len());
+        match name {
+            Some(name) => {
+                written.push(':');
+                written.push_str(name);
+                written.push(':');
+            }
+            None => written.push(ch),
+        }
+    }
+    written
+}
+
+fn lookup(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by(|(code, _)| code.cmp(&name))
+        .ok()
+        .map(|index| SHORTCODES[index].1)
+}
+
+/// Pictographic characters outside what the builtin fonts' WinAnsi encoding can show.
+pub fn is_emoji(ch: char) -> bool {
+    matches!(
+        u32::from(ch),
+        0x203C | 0x2049 | 0x2139 | 0x2190..=0x21FF | 0x2300..=0x23FF | 0x25A0..=0x27BF
+            | 0x2900..=0x297F | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF
+    )
+}
+
+/// Variation selectors and joiners that only affect colour emoji presentation.
+pub fn is_presentation_mark(ch: char) -> bool {
+    matches!(ch, '\u{FE0E}' | '\u{FE0F}' | '\u{200D}')
+}
@@ -0,0 +1,70 @@
+//! Decoding of markdown files that are not UTF-8.
+//!
+//! A byte order mark names the encoding outright. Without one, text with a zero byte
+//! beside most ASCII characters is taken as UTF-16, checked first as ASCII text in UTF-16
+//! is also valid UTF-8, then valid UTF-8 as is, and anything else as a single-byte Windows
+//! code page: Cyrillic (1251) when most letters are outside ASCII, as in Russian text, and
+//! Western (1252, a superset of Latin-1) otherwise.
+
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1251, WINDOWS_1252};
+
+/// Bytes looked at when guessing an encoding.
+const SAMPLE_LEN: usize = 4096;
+
+/// `bytes` as text, decoded from the encoding they appear to be in.
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return text;
+    }
+    let sample = &bytes[..bytes.len().min(SAMPLE_LEN)];
+    let encoding = match utf16(sample) {
+        Some(encoding) => encoding,
+        None => match std::str::from_utf8(bytes) {
+            Ok(text) => return Cow::Borrowed(text),
+            Err(_) => single_byte(sample),
+        },
+    };
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    text
+}
+
+/// UTF-16 when `sample` looks like it; markdown itself never has zero bytes.
+fn utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zeros_at = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&byte| byte == 0)
+            .count()
+    };
+    // ASCII characters in UTF-16 have their zero byte second (LE) or first (BE).
+    if zeros_at(1) * 3 > pairs && zeros_at(0) * 10 < pairs {
+        return Some(UTF_16LE);
+    }
+    if zeros_at(0) * 3 > pairs && zeros_at(1) * 10 < pairs {
+        return Some(UTF_16BE);
+    }
+    None
+}
+
+fn single_byte(sample: &[u8]) -> &'static Encoding {
+    let ascii_letters = sample
+        .iter()
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .count();
+    // 0xC0 to 0xFF are letters in both code pages: Cyrillic in 1251, accented in 1252.
+    let high_letters = sample.iter().filter(|&&byte| byte >= 0xC0).count();
+    if high_letters > ascii_letters {
+        WINDOWS_1251
+    } else {
+        WINDOWS_1252
+    }
+}
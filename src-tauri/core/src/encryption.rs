@@ -0,0 +1,271 @@
+//! Password protection with the PDF standard security handler, revision 6 (AES-256).
+//!
+//! Viewers enforce the permissions, so they keep honest readers from printing or copying
+//! but are no protection against someone determined. The open password is what actually
+//! keeps the content private.
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128, Aes256};
+use printpdf::lopdf::{Dictionary, Document, Object, StringFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Permission bits that must always be set: bits 7, 8 and 13 to 32.
+const RESERVED_PERMISSIONS: u32 = 0xFFFF_F0C0;
+const PRINT: u32 = 1 << 2;
+const MODIFY: u32 = 1 << 3;
+const COPY: u32 = 1 << 4;
+const ANNOTATE: u32 = 1 << 5;
+const FILL_FORMS: u32 = 1 << 8;
+const ACCESSIBILITY: u32 = 1 << 9;
+const ASSEMBLE: u32 = 1 << 10;
+const PRINT_HIGH_QUALITY: u32 = 1 << 11;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Encryption {
+    /// Needed to open the document; empty lets anyone open it.
+    pub user_password: String,
+    /// Needed to lift the restrictions; a random one nobody knows is used when empty.
+    pub owner_password: String,
+    pub allow_printing: bool,
+    /// Copying text and images out of the document. Accessibility tools may always
+    /// extract text.
+    pub allow_copying: bool,
+}
+
+impl Default for Encryption {
+    fn default() -> Self {
+        Self {
+            user_password: String::new(),
+            owner_password: String::new(),
+            allow_printing: true,
+            allow_copying: true,
+        }
+    }
+}
+
+impl Encryption {
+    fn permissions(&self) -> u32 {
+        let mut permissions =
+            RESERVED_PERMISSIONS | MODIFY | ANNOTATE | FILL_FORMS | ACCESSIBILITY | ASSEMBLE;
+        if self.allow_printing {
+            permissions |= PRINT | PRINT_HIGH_QUALITY;
+        }
+        if self.allow_copying {
+            permissions |= COPY;
+        }
+        permissions
+    }
+}
+
+/// Encrypts every string and stream of `doc` and installs the matching encryption
+/// dictionary.
+pub fn encrypt(doc: &mut Document, settings: &Encryption) -> Result<(), String> {
+    let file_key: [u8; 32] = random()?;
+    let user_password = password_bytes(&settings.user_password);
+    let owner_password = if settings.owner_password.is_empty() {
+        random::<32>()?.to_vec()
+    } else {
+        password_bytes(&settings.owner_password)
+    };
+
+    // Algorithm 8: the user password's validation and key salts follow its hash.
+    let [user_validation_salt, user_key_salt]: [[u8; 8]; 2] = [random()?, random()?];
+    let mut user = hash(&user_password, &user_validation_salt, &[]).to_vec();
+    user.extend_from_slice(&user_validation_salt);
+    user.extend_from_slice(&user_key_salt);
+    let user_key = hash(&user_password, &user_key_salt, &[]);
+    let user_encrypted_key = aes256_cbc_no_padding(&user_key, &file_key);
+
+    // Algorithm 9: the same for the owner password, bound to the user entry.
+    let [owner_validation_salt, owner_key_salt]: [[u8; 8]; 2] = [random()?, random()?];
+    let mut owner = hash(&owner_password, &owner_validation_salt, &user).to_vec();
+    owner.extend_from_slice(&owner_validation_salt);
+    owner.extend_from_slice(&owner_key_salt);
+    let owner_key = hash(&owner_password, &owner_key_salt, &user);
+    let owner_encrypted_key = aes256_cbc_no_padding(&owner_key, &file_key);
+
+    // Algorithm 10: the permissions, sealed with the file key so they cannot be edited.
+    let permissions = settings.permissions();
+    let mut perms = [0u8; 16];
+    perms[..4].copy_from_slice(&permissions.to_le_bytes());
+    perms[4..8].fill(0xFF);
+    perms[8] = b'T';
+    perms[9..12].copy_from_slice(b"adb");
+    perms[12..].copy_from_slice(&random::<4>()?);
+    let perms = aes256_cbc_no_padding(&file_key, &perms);
+
+    for object in doc.objects.values_mut() {
+        encrypt_object(object, &file_key)?;
+    }
+
+    let mut filter = Dictionary::new();
+    filter.set("Type", Object::Name(b"CryptFilter".to_vec()));
+    filter.set("CFM", Object::Name(b"AESV3".to_vec()));
+    filter.set("AuthEvent", Object::Name(b"DocOpen".to_vec()));
+    filter.set("Length", 32);
+    let mut filters = Dictionary::new();
+    filters.set("StdCF", filter);
+
+    let mut dict = Dictionary::new();
+    dict.set("Filter", Object::Name(b"Standard".to_vec()));
+    dict.set("V", 5);
+    dict.set("R", 6);
+    dict.set("Length", 256);
+    dict.set("CF", filters);
+    dict.set("StmF", Object::Name(b"StdCF".to_vec()));
+    dict.set("StrF", Object::Name(b"StdCF".to_vec()));
+    dict.set("O", hex_string(owner));
+    dict.set("U", hex_string(user));
+    dict.set("OE", hex_string(owner_encrypted_key));
+    dict.set("UE", hex_string(user_encrypted_key));
+    dict.set("Perms", hex_string(perms));
+    dict.set("P", i64::from(permissions as i32));
+    let encrypt_id = doc.add_object(dict);
+    doc.trailer.set("Encrypt", Object::Reference(encrypt_id));
+
+    if doc.trailer.get(b"ID").is_err() {
+        let id = hex_string(random::<16>()?.to_vec());
+        doc.trailer.set("ID", vec![id.clone(), id]);
+    }
+
+    // AES-256 is an extension to PDF 1.7.
+    doc.version = "1.7".to_string();
+    let mut adobe = Dictionary::new();
+    adobe.set("BaseVersion", Object::Name(b"1.7".to_vec()));
+    adobe.set("ExtensionLevel", 8);
+    let mut extensions = Dictionary::new();
+    extensions.set("ADBE", adobe);
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    catalog.set("Extensions", extensions);
+    Ok(())
+}
+
+fn encrypt_object(object: &mut Object, key: &[u8; 32]) -> Result<(), String> {
+    match object {
+        Object::String(bytes, _) => *bytes = aes256_cbc(key, bytes)?,
+        Object::Array(items) => {
+            for item in items {
+                encrypt_object(item, key)?;
+            }
+        }
+        Object::Dictionary(dict) => {
+            for (_, value) in dict.iter_mut() {
+                encrypt_object(value, key)?;
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in stream.dict.iter_mut() {
+                encrypt_object(value, key)?;
+            }
+            let content = aes256_cbc(key, &stream.content)?;
+            stream.set_content(content);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Passwords are UTF-8, truncated to 127 bytes.
+fn password_bytes(password: &str) -> Vec<u8> {
+    let mut end = password.len().min(127);
+    while !password.is_char_boundary(end) {
+        end -= 1;
+    }
+    password.as_bytes()[..end].to_vec()
+}
+
+/// Algorithm 2.B: the revision 6 password hash.
+fn hash(password: &[u8], salt: &[u8], user: &[u8]) -> [u8; 32] {
+    let mut key: Vec<u8> = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(user)
+        .finalize()
+        .to_vec();
+    let mut round = 0u32;
+    loop {
+        let mut encrypted = Vec::with_capacity(64 * (password.len() + key.len() + user.len()));
+        for _ in 0..64 {
+            encrypted.extend_from_slice(password);
+            encrypted.extend_from_slice(&key);
+            encrypted.extend_from_slice(user);
+        }
+        let iv = key[16..32].try_into().expect("key holds 32 bytes");
+        cbc(
+            &Aes128::new(GenericArray::from_slice(&key[..16])),
+            iv,
+            &mut encrypted,
+        );
+
+        let remainder = encrypted[..16]
+            .iter()
+            .map(|&byte| u32::from(byte))
+            .sum::<u32>()
+            % 3;
+        key = match remainder {
+            0 => Sha256::digest(&encrypted).to_vec(),
+            1 => Sha384::digest(&encrypted).to_vec(),
+            _ => Sha512::digest(&encrypted).to_vec(),
+        };
+        round += 1;
+        let last = u32::from(*encrypted.last().expect("block is never empty"));
+        if round >= 64 && last <= round - 32 {
+            break;
+        }
+    }
+    key[..32]
+        .try_into()
+        .expect("digests hold at least 32 bytes")
+}
+
+/// AES-256-CBC with a random IV written in front and PKCS#7 padding, as used for
+/// strings and streams.
+fn aes256_cbc(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
+    let iv: [u8; 16] = random()?;
+    let padding = 16 - data.len() % 16;
+    let mut output = iv.to_vec();
+    output.extend_from_slice(data);
+    output.resize(16 + data.len() + padding, padding as u8);
+    cbc(
+        &Aes256::new(GenericArray::from_slice(key)),
+        iv,
+        &mut output[16..],
+    );
+    Ok(output)
+}
+
+/// AES-256-CBC with a zero IV and no padding, for the key and permission entries.
+fn aes256_cbc_no_padding(key: &[u8; 32], data: &[u8]) -> Vec<u8> {
+    let mut output = data.to_vec();
+    cbc(
+        &Aes256::new(GenericArray::from_slice(key)),
+        [0; 16],
+        &mut output,
+    );
+    output
+}
+
+/// Encrypts `data`, a whole number of blocks, in place in CBC mode.
+fn cbc(cipher: &impl BlockEncrypt, iv: [u8; 16], data: &mut [u8]) {
+    let mut previous = iv;
+    for chunk in data.chunks_exact_mut(16) {
+        for (byte, prior) in chunk.iter_mut().zip(previous) {
+            *byte ^= prior;
+        }
+        cipher.encrypt_block(GenericArray::from_mut_slice(chunk));
+        previous.copy_from_slice(chunk);
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).map_err(|err| err.to_string())?;
+    Ok(bytes)
+}
+
+fn hex_string(bytes: Vec<u8>) -> Object {
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
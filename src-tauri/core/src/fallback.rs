@@ -0,0 +1,215 @@
+//! Font fallback for text the builtin fonts cannot show.
+//!
+//! The builtin PDF fonts only cover the WinAnsi character set, so Greek, Cyrillic, CJK
+//! and other scripts come out as garbage. Characters outside it are set in embedded
+//! TrueType fonts instead: the ones named in the render options, then an installed
+//! font for each script the document uses. Glyphs are drawn one by one without
+//! shaping, which suits alphabetic and CJK scripts but not joined scripts like Arabic.
+
+use std::path::{Path, PathBuf};
+
+use crate::style::FontFamily;
+
+const FONT_DIRS: &[&str] = &[
+    "/usr/share/fonts/truetype/noto",
+    "/usr/share/fonts/noto",
+    "/usr/share/fonts/google-noto",
+    "/usr/share/fonts/truetype/dejavu",
+    "/usr/share/fonts/truetype/liberation",
+    "/usr/share/fonts/liberation-sans",
+    "/usr/share/fonts/liberation-serif",
+    "/usr/share/fonts/liberation-mono",
+    "/usr/share/fonts/dejavu",
+    "/usr/share/fonts/TTF",
+    "/usr/share/fonts/truetype/droid",
+    "C:\\Windows\\Fonts",
+    "/System/Library/Fonts/Supplemental",
+    "/Library/Fonts",
+];
+
+/// Broad-coverage fonts used for Greek, Cyrillic, accented Latin and anything else.
+const GENERAL_FONTS: &[&str] = &[
+    "NotoSans-Regular.ttf",
+    "DejaVuSans.ttf",
+    "arial.ttf",
+    "Arial Unicode.ttf",
+];
+
+/// Regular, bold, italic and bold italic files of families that can stand in for the
+/// builtin fonts, metric-compatible ones first.
+const SANS_FAMILIES: &[[&str; 4]] = &[
+    [
+        "LiberationSans-Regular.ttf",
+        "LiberationSans-Bold.ttf",
+        "LiberationSans-Italic.ttf",
+        "LiberationSans-BoldItalic.ttf",
+    ],
+    ["arial.ttf", "arialbd.ttf", "ariali.ttf", "arialbi.ttf"],
+    [
+        "Arial.ttf",
+        "Arial Bold.ttf",
+        "Arial Italic.ttf",
+        "Arial Bold Italic.ttf",
+    ],
+    [
+        "DejaVuSans.ttf",
+        "DejaVuSans-Bold.ttf",
+        "DejaVuSans-Oblique.ttf",
+        "DejaVuSans-BoldOblique.ttf",
+    ],
+];
+
+const SERIF_FAMILIES: &[[&str; 4]] = &[
+    [
+        "LiberationSerif-Regular.ttf",
+        "LiberationSerif-Bold.ttf",
+        "LiberationSerif-Italic.ttf",
+        "LiberationSerif-BoldItalic.ttf",
+    ],
+    ["times.ttf", "timesbd.ttf", "timesi.ttf", "timesbi.ttf"],
+    [
+        "Times New Roman.ttf",
+        "Times New Roman Bold.ttf",
+        "Times New Roman Italic.ttf",
+        "Times New Roman Bold Italic.ttf",
+    ],
+    [
+        "DejaVuSerif.ttf",
+        "DejaVuSerif-Bold.ttf",
+        "DejaVuSerif-Italic.ttf",
+        "DejaVuSerif-BoldItalic.ttf",
+    ],
+];
+
+const MONO_FONTS: &[&str] = &[
+    "LiberationMono-Regular.ttf",
+    "cour.ttf",
+    "Courier New.ttf",
+    "DejaVuSansMono.ttf",
+];
+
+/// Installed TrueType files replacing the builtin fonts when every font has to be
+/// embedded.
+pub struct EmbeddableFaces {
+    /// Regular, bold, italic and bold italic.
+    pub text: [PathBuf; 4],
+    pub mono: PathBuf,
+    /// Broad-coverage font for the Greek letters and operators otherwise set in Symbol.
+    pub symbol: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Cjk,
+    Other,
+}
+
+impl Script {
+    fn of(ch: char) -> Script {
+        match u32::from(ch) {
+            0x0590..=0x05FF => Script::Hebrew,
+            0x0600..=0x06FF | 0x0750..=0x077F => Script::Arabic,
+            0x0900..=0x097F => Script::Devanagari,
+            0x0E00..=0x0E7F => Script::Thai,
+            0x1100..=0x11FF
+            | 0x2E80..=0x9FFF
+            | 0xAC00..=0xD7AF
+            | 0xF900..=0xFAFF
+            | 0xFF00..=0xFFEF
+            | 0x20000..=0x2FFFF => Script::Cjk,
+            _ => Script::Other,
+        }
+    }
+
+    fn fonts(self) -> &'static [&'static str] {
+        match self {
+            Script::Hebrew => &["NotoSansHebrew-Regular.ttf"],
+            Script::Arabic => &["NotoSansArabic-Regular.ttf", "NotoNaskhArabic-Regular.ttf"],
+            Script::Devanagari => &["NotoSansDevanagari-Regular.ttf", "mangal.ttf"],
+            Script::Thai => &["NotoSansThai-Regular.ttf", "tahoma.ttf"],
+            Script::Cjk => &[
+                "DroidSansFallbackFull.ttf",
+                "DroidSansFallback.ttf",
+                "simhei.ttf",
+                "Arial Unicode.ttf",
+            ],
+            Script::Other => GENERAL_FONTS,
+        }
+    }
+}
+
+/// Whether the builtin fonts' WinAnsi encoding has `ch`.
+pub fn is_win_ansi(ch: char) -> bool {
+    matches!(
+        ch,
+        '\0'..='\u{7F}'
+            | '\u{A0}'..='\u{FF}'
+            | '€' | '‚' | 'ƒ' | '„' | '…' | '†' | '‡' | 'ˆ' | '‰' | 'Š' | '‹' | 'Œ' | 'Ž'
+            | '‘' | '’' | '“' | '”' | '•' | '–' | '—' | '˜' | '™' | 'š' | '›' | 'œ' | 'ž'
+            | 'Ÿ'
+    )
+}
+
+/// Fallback fonts for `text`: the `preferred` ones, then the first installed font for
+/// each script found among the characters the builtin fonts lack, then a broad-coverage
+/// font for whatever those leave out.
+pub fn font_paths(text: &str, preferred: &[String]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = preferred.iter().map(PathBuf::from).collect();
+    let mut scripts: Vec<Script> = Vec::new();
+    for ch in text.chars().filter(|ch| !is_win_ansi(*ch)) {
+        let script = Script::of(ch);
+        if !scripts.contains(&script) {
+            scripts.push(script);
+        }
+    }
+    if scripts.is_empty() {
+        return paths;
+    }
+    if !scripts.contains(&Script::Other) {
+        scripts.push(Script::Other);
+    }
+
+    for script in scripts {
+        if let Some(path) = find_installed(script.fonts()) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+pub fn embeddable_faces(family: FontFamily) -> Result<EmbeddableFaces, String> {
+    let families = match family {
+        FontFamily::Helvetica => SANS_FAMILIES,
+        FontFamily::Times => SERIF_FAMILIES,
+    };
+    let text = families.iter().find_map(|names| {
+        let [regular, bold, italic, bold_italic] = names.map(|name| find_installed(&[name]));
+        Some([regular?, bold?, italic?, bold_italic?])
+    });
+    match (
+        text,
+        find_installed(MONO_FONTS),
+        find_installed(GENERAL_FONTS),
+    ) {
+        (Some(text), Some(mono), Some(symbol)) => Ok(EmbeddableFaces { text, mono, symbol }),
+        _ => Err(
+            "Embedding every font needs installed TrueType fonts such as Liberation or DejaVu"
+                .to_string(),
+        ),
+    }
+}
+
+fn find_installed(names: &[&str]) -> Option<PathBuf> {
+    names.iter().find_map(|name| {
+        FONT_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(name))
+            .find(|path| path.is_file())
+    })
+}
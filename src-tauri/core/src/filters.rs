@@ -0,0 +1,150 @@
+//! Include and exclude patterns that pick the files an input folder contributes.
+//!
+//! Patterns are globs matched against paths relative to the input folder, with `/`
+//! between folders: `*` stays within one folder and `**` spans any number of them. A
+//! pattern without a `/`, such as `CHANGELOG.md`, matches in every folder.
+//!
+//! Hidden files and folders, whose names start with a dot like `.git` or `.obsidian`, are
+//! skipped along with everything inside them unless asked for.
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetFilters {
+    /// Markdown files to convert, such as `docs/**/*.md`; every markdown file when empty.
+    pub include: Vec<String>,
+    /// Files to leave out, markdown and images alike, such as `node_modules/**`.
+    pub exclude: Vec<String>,
+    /// Leaves out hidden files and folders.
+    pub skip_hidden: bool,
+    /// Collects `.txt` files along with the markdown files, shown as preformatted text.
+    /// The include and exclude patterns apply to them as to markdown files.
+    pub include_text: bool,
+    /// Collects `.html` and `.htm` pages along with the markdown files, converted to
+    /// markdown first. The include and exclude patterns apply to them as to markdown files.
+    pub include_html: bool,
+    /// Collects `.csv` files along with the markdown files, each shown as a table under
+    /// its file name. The include and exclude patterns apply to them as to markdown files.
+    pub include_csv: bool,
+    /// Collects `.rst` files along with the markdown files, converted to markdown first.
+    /// The include and exclude patterns apply to them as to markdown files.
+    pub include_rst: bool,
+}
+
+impl Default for AssetFilters {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            skip_hidden: true,
+            include_text: false,
+            include_html: false,
+            include_csv: false,
+            include_rst: false,
+        }
+    }
+}
+
+/// Compiled `AssetFilters`.
+pub struct Filter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    skip_hidden: bool,
+    include_text: bool,
+    include_html: bool,
+    include_csv: bool,
+    include_rst: bool,
+}
+
+impl Filter {
+    pub fn new(filters: &AssetFilters) -> Result<Self, String> {
+        let include = if filters.include.is_empty() {
+            None
+        } else {
+            Some(glob_set(&filters.include)?)
+        };
+        Ok(Self {
+            include,
+            exclude: glob_set(&filters.exclude)?,
+            skip_hidden: filters.skip_hidden,
+            include_text: filters.include_text,
+            include_html: filters.include_html,
+            include_csv: filters.include_csv,
+            include_rst: filters.include_rst,
+        })
+    }
+
+    /// Whether the file or folder called `name` is left out, with all it holds, before
+    /// any pattern is tried.
+    pub fn skips_entry(&self, name: &OsStr) -> bool {
+        self.skip_hidden && name.to_string_lossy().starts_with('.')
+    }
+
+    /// Whether `.txt` files are collected.
+    pub fn collects_text(&self) -> bool {
+        self.include_text
+    }
+
+    /// Whether `.html` and `.htm` pages are collected.
+    pub fn collects_html(&self) -> bool {
+        self.include_html
+    }
+
+    /// Whether `.csv` files are collected.
+    pub fn collects_csv(&self) -> bool {
+        self.include_csv
+    }
+
+    /// Whether `.rst` files are collected.
+    pub fn collects_rst(&self) -> bool {
+        self.include_rst
+    }
+
+    /// Whether the markdown or text file at `relative` is converted.
+    pub fn keeps_markdown(&self, relative: &Path) -> bool {
+        let path = slashed(relative);
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(&path))
+            && !self.exclude.is_match(&path)
+    }
+
+    /// Whether the image at `relative` is collected.
+    pub fn keeps_image(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(slashed(relative))
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_start_matches("./");
+        if pattern.is_empty() {
+            continue;
+        }
+        let anchored = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{pattern}")
+        };
+        let glob = GlobBuilder::new(&anchored)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| format!("Invalid pattern {pattern}: {err}"))?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|err| err.to_string())
+}
+
+/// `path` with `/` between its components on every platform.
+fn slashed(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,113 @@
+//! Splits YAML front matter, or TOML front matter between `+++` lines as Hugo writes it,
+//! off the top of a markdown file.
+//!
+//! Only flat `key: value` (or `key = value`) pairs are read; nested mappings, tables,
+//! lists and block scalars are skipped so unusual front matter never breaks a conversion.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+    /// ISO 639-1 code of the language the file is written in.
+    pub language: Option<String>,
+    /// Cover page image, relative to the markdown file.
+    pub logo: Option<String>,
+    /// Place of the file among the files of its folder; lower comes first.
+    pub order: Option<f64>,
+    /// Set by `draft: true` or `published: false`, for pages a site leaves unpublished.
+    pub draft: bool,
+}
+
+/// Returns the parsed front matter and the markdown body that follows it. Files without
+/// a leading `---` block come back unchanged with empty front matter.
+pub fn split(contents: &str) -> (FrontMatter, &str) {
+    let text = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let (rest, fence, separator) = if let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    {
+        (rest, "---", ':')
+    } else if let Some(rest) = text
+        .strip_prefix("+++\n")
+        .or_else(|| text.strip_prefix("+++\r\n"))
+    {
+        (rest, "+++", '=')
+    } else {
+        return (FrontMatter::default(), contents);
+    };
+
+    let mut front_matter = FrontMatter::default();
+    let mut offset = 0;
+    // Keys of TOML tables such as `[params]` are not the page's own.
+    let mut in_table = false;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let trimmed = line.trim_end();
+        if trimmed == fence || (fence == "---" && trimmed == "...") {
+            return (front_matter, &rest[offset..]);
+        }
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        if separator == '=' && trimmed.starts_with('[') {
+            in_table = true;
+        }
+        if in_table {
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(separator) else {
+            continue;
+        };
+        let value = unquote(value.trim());
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim().to_ascii_lowercase().as_str() {
+            "title" => front_matter.title = Some(value),
+            "subtitle" => front_matter.subtitle = Some(value),
+            "author" => front_matter.author = Some(value),
+            "date" => front_matter.date = Some(value),
+            "logo" => front_matter.logo = Some(value),
+            "subject" | "description" => front_matter.subject = Some(value),
+            "keywords" | "tags" => front_matter.keywords = list(&value),
+            "lang" | "language" => front_matter.language = Some(value),
+            "order" | "weight" => front_matter.order = value.parse().ok(),
+            "draft" => front_matter.draft = value == "true",
+            "published" => front_matter.draft = value == "false",
+            _ => {}
+        }
+    }
+
+    // An unterminated block is ordinary markdown (a thematic break), not front matter.
+    (FrontMatter::default(), contents)
+}
+
+/// Reads an inline `[a, b]` list or a plain comma-separated one.
+fn list(value: &str) -> Vec<String> {
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(value);
+    inner
+        .split(',')
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> String {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
@@ -0,0 +1,174 @@
+//! A tokenizer for the small, safe subset of HTML that markdown authors embed inline.
+//!
+//! Only formatting tags with a direct renderer equivalent are recognised; every other
+//! tag and all comments are dropped while their text content is kept.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InlineTag {
+    Bold,
+    Italic,
+    Strikethrough,
+    Superscript,
+    Subscript,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlToken {
+    Text(String),
+    LineBreak,
+    Open(InlineTag),
+    Close(InlineTag),
+    Image { src: String },
+}
+
+pub fn tokenize(html: &str) -> Vec<HtmlToken> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        if rest.starts_with('<') {
+            if let Some(end) = rest.find('>') {
+                if let Some(token) = parse_tag(&rest[1..end]) {
+                    tokens.push(token);
+                }
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, ch)| *ch == '<')
+            .map_or(rest.len(), |(index, _)| index);
+        let text = decode_entities(&rest[..end]);
+        match tokens.last_mut() {
+            Some(HtmlToken::Text(previous)) => previous.push_str(&text),
+            _ => tokens.push(HtmlToken::Text(text)),
+        }
+        rest = &rest[end..];
+    }
+
+    tokens
+}
+
+fn parse_tag(tag: &str) -> Option<HtmlToken> {
+    let tag = tag.trim().trim_end_matches('/').trim_end();
+    let (closing, tag) = match tag.strip_prefix('/') {
+        Some(tag) => (true, tag.trim_start()),
+        None => (false, tag),
+    };
+    let name_end = tag
+        .find(|ch: char| ch.is_whitespace())
+        .unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+    let attributes = &tag[name_end..];
+
+    let inline = match name.as_str() {
+        "br" => return Some(HtmlToken::LineBreak),
+        "img" if !closing => {
+            return attribute(attributes, "src").map(|src| HtmlToken::Image { src });
+        }
+        "b" | "strong" => InlineTag::Bold,
+        "i" | "em" => InlineTag::Italic,
+        "s" | "del" | "strike" => InlineTag::Strikethrough,
+        "sup" => InlineTag::Superscript,
+        "sub" => InlineTag::Subscript,
+        _ => return None,
+    };
+
+    Some(if closing {
+        HtmlToken::Close(inline)
+    } else {
+        HtmlToken::Open(inline)
+    })
+}
+
+/// Reads a quoted or bare attribute value, matching the name case-insensitively.
+pub fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let lower = attributes.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find(name) {
+        let start = search_from + found;
+        search_from = start + name.len();
+        let preceded_by_space = lower[..start]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        let value = attributes[search_from..].trim_start();
+        let Some(value) = value.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+
+        let raw = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value.split_whitespace().next().unwrap_or(""),
+        };
+        return Some(decode_entities(raw));
+    }
+    None
+}
+
+/// Replaces character references such as `&amp;`, `&#233;` and `&#xE9;` with the
+/// characters they stand for. Unknown names are left as written.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let name = rest.find(';').filter(|&end| end <= 10).map(|end| &rest[..end]);
+        match name.and_then(|name| Some((name, character(name)?))) {
+            Some((name, ch)) => {
+                decoded.push(ch);
+                rest = &rest[name.len() + 1..];
+            }
+            None => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn character(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "mdash" => '—',
+        "ndash" => '–',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "middot" => '·',
+        "bull" => '•',
+        "deg" => '°',
+        "times" => '×',
+        "euro" => '€',
+        _ => return None,
+    })
+}
@@ -0,0 +1,533 @@
+//! Conversion of HTML pages, such as those exported from wikis, into markdown so they are
+//! laid out like the markdown files beside them.
+//!
+//! Headings, paragraphs, emphasis, links, images, lists, block quotes, preformatted code
+//! and tables are carried over, and the page `<title>` becomes the front matter title.
+//! Other tags are dropped with their text kept; the document head, scripts and styles are
+//! dropped along with their contents. Unclosed list items, cells and paragraphs, as HTML
+//! allows, end where the next one starts or their parent ends.
+
+use crate::html::{attribute, decode_entities};
+
+/// Elements left out with everything inside them.
+const SKIPPED: [&str; 8] = [
+    "head", "title", "script", "style", "template", "noscript", "svg", "iframe",
+];
+/// Elements that start and end a block, like a paragraph.
+const BLOCKS: [&str; 20] = [
+    "p", "div", "section", "article", "main", "header", "footer", "nav", "aside", "figure",
+    "figcaption", "caption", "dl", "dt", "dd", "address", "details", "summary", "center",
+    "form",
+];
+/// Characters escaped in text so they are not read as markdown.
+const ESCAPED: &str = "\\`*_[]<>|~&";
+
+/// The markdown equivalent of the HTML page `html`.
+pub fn to_markdown(html: &str) -> String {
+    // Lowercasing ASCII keeps byte offsets, so the two strings can be searched in step.
+    let lower = html.to_ascii_lowercase();
+    let mut converter = Converter::default();
+    let mut index = 0;
+
+    while index < html.len() {
+        let rest = &html[index..];
+        if rest.starts_with("<!--") {
+            index = lower[index..]
+                .find("-->")
+                .map_or(html.len(), |end| index + end + 3);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            index = rest.find('>').map_or(html.len(), |end| index + end + 1);
+            continue;
+        }
+        let is_tag = rest.starts_with('<')
+            && rest[1..].starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '/');
+        if let Some(end) = rest.find('>').filter(|_| is_tag) {
+            let tag = Tag::parse(&rest[1..end]);
+            index += end + 1;
+            if !tag.closing && SKIPPED.contains(&tag.name.as_str()) {
+                let close = format!("</{}", tag.name);
+                index = lower[index..]
+                    .find(&close)
+                    .and_then(|start| {
+                        let start = index + start;
+                        lower[start..].find('>').map(|end| start + end + 1)
+                    })
+                    .unwrap_or(html.len());
+            } else {
+                converter.tag(&tag);
+            }
+            continue;
+        }
+        let end = rest[1..].find('<').map_or(rest.len(), |end| end + 1);
+        converter.text(&rest[..end]);
+        index += end;
+    }
+
+    let body = converter.finish();
+    match title(html, &lower) {
+        Some(title) => format!("---\ntitle: {title}\n---\n\n{body}"),
+        None => body,
+    }
+}
+
+/// The text of the page's `<title>`, if it has a non-empty one.
+fn title(html: &str, lower: &str) -> Option<String> {
+    let start = lower.find("<title")?;
+    let text_start = start + lower[start..].find('>')? + 1;
+    let text_end = text_start + lower[text_start..].find("</title")?;
+    let text = decode_entities(&html[text_start..text_end]);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: &'a str,
+}
+
+impl<'a> Tag<'a> {
+    /// Reads the inside of `<...>`.
+    fn parse(tag: &'a str) -> Self {
+        let tag = tag.trim().trim_end_matches('/').trim_end();
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag.trim_start()),
+            None => (false, tag),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        Self {
+            name: tag[..name_end].to_ascii_lowercase(),
+            closing,
+            attributes: &tag[name_end..],
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<String> {
+        attribute(self.attributes, name)
+    }
+}
+
+/// Elements whose markdown is written once they end, from the text gathered inside them.
+enum Kind {
+    Root,
+    Link(Option<String>),
+    Quote,
+    Item,
+    Cell,
+    Pre(Option<String>),
+}
+
+struct Frame {
+    kind: Kind,
+    text: String,
+}
+
+struct List {
+    ordered: bool,
+    /// Number of the next item of an ordered list.
+    next: u64,
+}
+
+struct Converter {
+    frames: Vec<Frame>,
+    lists: Vec<List>,
+    /// Rows of cells of the tables being read, innermost last.
+    tables: Vec<Vec<Vec<String>>>,
+    /// Depth of inline `<code>` elements, whose text is not escaped.
+    code: usize,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self {
+            frames: vec![Frame {
+                kind: Kind::Root,
+                text: String::new(),
+            }],
+            lists: Vec::new(),
+            tables: Vec::new(),
+            code: 0,
+        }
+    }
+}
+
+impl Converter {
+    fn out(&mut self) -> &mut String {
+        &mut self.frames.last_mut().expect("the root frame is never closed").text
+    }
+
+    fn in_pre(&self) -> bool {
+        self.frames
+            .iter()
+            .any(|frame| matches!(frame.kind, Kind::Pre(_)))
+    }
+
+    fn open_items(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|frame| matches!(frame.kind, Kind::Item))
+            .count()
+    }
+
+    /// Ends the current block with a blank line.
+    fn block(&mut self) {
+        let out = self.out();
+        out.truncate(out.trim_end_matches(' ').len());
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+    }
+
+    /// Ends the current line.
+    fn line(&mut self) {
+        let out = self.out();
+        out.truncate(out.trim_end_matches(' ').len());
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        let decoded = decode_entities(raw);
+        if self.in_pre() {
+            self.out().push_str(&decoded);
+            return;
+        }
+        let code = self.code > 0;
+        let out = self.out();
+        let mut space = false;
+        let mut numbering = false;
+        for ch in decoded.chars() {
+            if ch.is_whitespace() {
+                space = true;
+                continue;
+            }
+            if space && !out.is_empty() && !out.ends_with([' ', '\n']) {
+                out.push(' ');
+            }
+            space = false;
+            if code {
+                out.push(ch);
+                continue;
+            }
+            let line_start = out.is_empty() || out.ends_with('\n');
+            // Text such as `# x`, `- x` or `1. x` at the start of a line would become
+            // a heading or list item.
+            if line_start && ch.is_ascii_digit() {
+                numbering = true;
+            } else if numbering && matches!(ch, '.' | ')') {
+                out.push('\\');
+                numbering = false;
+            } else if !ch.is_ascii_digit() {
+                numbering = false;
+            }
+            if ESCAPED.contains(ch) || (line_start && matches!(ch, '#' | '-' | '+' | '=')) {
+                out.push('\\');
+            }
+            out.push(ch);
+        }
+        if space && !out.is_empty() && !out.ends_with([' ', '\n']) {
+            out.push(' ');
+        }
+    }
+
+    /// Writes an emphasis marker, keeping a closing one against the text it ends.
+    fn marker(&mut self, marker: &str, closing: bool) {
+        let out = self.out();
+        if closing && out.ends_with(' ') {
+            out.pop();
+            out.push_str(marker);
+            out.push(' ');
+        } else {
+            out.push_str(marker);
+        }
+    }
+
+    fn tag(&mut self, tag: &Tag) {
+        let name = tag.name.as_str();
+        if self.in_pre() {
+            match (name, tag.closing) {
+                ("pre", true) => self.close_until(|kind| matches!(kind, Kind::Pre(_))),
+                ("br", _) => self.out().push('\n'),
+                ("code", false) => {
+                    let language = tag.attribute("class").and_then(|class| {
+                        class.split_whitespace().find_map(|class| {
+                            let language = class
+                                .strip_prefix("language-")
+                                .or_else(|| class.strip_prefix("lang-"))?;
+                            Some(language.to_string())
+                        })
+                    });
+                    if let Some(Frame {
+                        kind: Kind::Pre(current @ None),
+                        ..
+                    }) = self.frames.last_mut()
+                    {
+                        *current = language;
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match (name, tag.closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", closing) => {
+                self.block();
+                if !closing {
+                    let level = usize::from(name.as_bytes()[1] - b'0');
+                    let hashes = "#".repeat(level);
+                    self.out().push_str(&hashes);
+                    self.out().push(' ');
+                }
+            }
+            (name, _) if BLOCKS.contains(&name) => self.block(),
+            ("br", _) => self.out().push_str("\\\n"),
+            ("hr", _) => {
+                self.block();
+                self.out().push_str("---");
+                self.block();
+            }
+            ("b" | "strong", closing) => self.marker("**", closing),
+            ("i" | "em", closing) => self.marker("*", closing),
+            ("s" | "del" | "strike", closing) => self.marker("~~", closing),
+            ("sup" | "sub", closing) => {
+                let slash = if closing { "/" } else { "" };
+                self.out().push_str(&format!("<{slash}{name}>"));
+            }
+            ("code" | "kbd" | "samp" | "tt", closing) => {
+                if closing {
+                    self.code = self.code.saturating_sub(1);
+                } else {
+                    self.code += 1;
+                }
+                self.marker("`", closing);
+            }
+            ("a", false) => self.open(Kind::Link(tag.attribute("href"))),
+            ("a", true) => self.close_until(|kind| matches!(kind, Kind::Link(_))),
+            ("img", false) => {
+                if let Some(src) = tag.attribute("src") {
+                    let alt = tag.attribute("alt").unwrap_or_default();
+                    let alt = alt.replace('[', "\\[").replace(']', "\\]");
+                    let image = format!("![{alt}]({})", destination(&src));
+                    self.out().push_str(&image);
+                }
+            }
+            ("ul" | "ol", false) => {
+                if self.open_items() > 0 {
+                    self.line();
+                } else {
+                    self.block();
+                }
+                let start = tag.attribute("start").and_then(|start| start.parse().ok());
+                self.lists.push(List {
+                    ordered: name == "ol",
+                    next: start.unwrap_or(1),
+                });
+            }
+            ("ul" | "ol", true) => {
+                while self.open_items() >= self.lists.len().max(1) {
+                    self.close_until(|kind| matches!(kind, Kind::Item));
+                }
+                if self.lists.pop().is_some() {
+                    if self.open_items() > 0 {
+                        self.line();
+                    } else {
+                        self.block();
+                    }
+                }
+            }
+            ("li", false) => {
+                if self.open_items() >= self.lists.len().max(1) {
+                    self.close_until(|kind| matches!(kind, Kind::Item));
+                }
+                self.open(Kind::Item);
+            }
+            ("li", true) => self.close_until(|kind| matches!(kind, Kind::Item)),
+            ("blockquote", false) => {
+                self.block();
+                self.open(Kind::Quote);
+            }
+            ("blockquote", true) => self.close_until(|kind| matches!(kind, Kind::Quote)),
+            ("pre", false) => {
+                self.block();
+                self.open(Kind::Pre(None));
+            }
+            ("table", false) => {
+                self.block();
+                self.tables.push(Vec::new());
+            }
+            ("table", true) => {
+                self.close_cell();
+                if let Some(rows) = self.tables.pop() {
+                    self.write_table(rows);
+                }
+            }
+            ("tr", closing) => {
+                self.close_cell();
+                if let (false, Some(rows)) = (closing, self.tables.last_mut()) {
+                    rows.push(Vec::new());
+                }
+            }
+            ("td" | "th", false) => {
+                self.close_cell();
+                self.open(Kind::Cell);
+            }
+            ("td" | "th", true) => self.close_cell(),
+            _ => {}
+        }
+    }
+
+    fn open(&mut self, kind: Kind) {
+        self.frames.push(Frame {
+            kind,
+            text: String::new(),
+        });
+    }
+
+    fn close_cell(&mut self) {
+        self.close_until(|kind| matches!(kind, Kind::Cell));
+    }
+
+    /// Ends the innermost element `is_target` matches, along with those left open inside
+    /// it. Does nothing when none is open.
+    fn close_until(&mut self, is_target: fn(&Kind) -> bool) {
+        if !self.frames.iter().any(|frame| is_target(&frame.kind)) {
+            return;
+        }
+        while let Some(frame) = self.frames.pop() {
+            let done = is_target(&frame.kind);
+            self.write(frame);
+            if done {
+                break;
+            }
+        }
+    }
+
+    /// Writes the markdown of the ended element `frame` into its parent.
+    fn write(&mut self, frame: Frame) {
+        let text = frame.text.trim();
+        match frame.kind {
+            Kind::Root => self.out().push_str(text),
+            Kind::Link(href) => {
+                let href = href.filter(|href| {
+                    !href.is_empty() && !href.to_ascii_lowercase().starts_with("javascript:")
+                });
+                let out = self.out();
+                match href {
+                    Some(href) if !text.is_empty() => {
+                        out.push_str(&format!("[{text}]({})", destination(&href)));
+                    }
+                    _ => out.push_str(text),
+                }
+                if frame.text.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            Kind::Quote => {
+                let quoted: Vec<String> = text
+                    .lines()
+                    .map(|line| format!("> {line}").trim_end().to_string())
+                    .collect();
+                self.block();
+                self.out().push_str(&quoted.join("\n"));
+                self.block();
+            }
+            Kind::Item => {
+                let marker = match self.lists.last_mut() {
+                    Some(list) if list.ordered => {
+                        list.next += 1;
+                        format!("{}. ", list.next - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                let indent = " ".repeat(marker.len());
+                let mut item = marker;
+                for (index, line) in text.lines().enumerate() {
+                    if index > 0 {
+                        item.push('\n');
+                        if !line.is_empty() {
+                            item.push_str(&indent);
+                        }
+                    }
+                    item.push_str(line);
+                }
+                self.line();
+                self.out().push_str(&item);
+                self.line();
+            }
+            Kind::Cell => {
+                let cell = text.replace("\\\n", "<br>");
+                let cell = cell.split_whitespace().collect::<Vec<_>>().join(" ");
+                match self.tables.last_mut() {
+                    Some(rows) => {
+                        if rows.is_empty() {
+                            rows.push(Vec::new());
+                        }
+                        rows.last_mut().expect("a row was just added").push(cell);
+                    }
+                    None => self.out().push_str(&cell),
+                }
+            }
+            Kind::Pre(language) => {
+                let code = frame.text.strip_prefix('\n').unwrap_or(&frame.text);
+                let code = code.trim_end();
+                let mut fence = "```".to_string();
+                while code.contains(fence.as_str()) {
+                    fence.push('`');
+                }
+                let language = language.unwrap_or_default();
+                let block = format!("{fence}{language}\n{code}\n{fence}");
+                self.block();
+                self.out().push_str(&block);
+                self.block();
+            }
+        }
+    }
+
+    fn write_table(&mut self, rows: Vec<Vec<String>>) {
+        let rows: Vec<Vec<String>> = rows.into_iter().filter(|row| !row.is_empty()).collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let line = |cells: &[String]| {
+            let mut line = String::from("|");
+            for column in 0..columns {
+                let cell = cells.get(column).map_or("", String::as_str);
+                line.push_str(&format!(" {cell} |"));
+            }
+            line
+        };
+        let mut table = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+        table.extend(rows[1..].iter().map(|row| line(row)));
+        self.block();
+        self.out().push_str(&table.join("\n"));
+        self.block();
+    }
+
+    /// Ends every element left open and returns the markdown.
+    fn finish(mut self) -> String {
+        while self.frames.len() > 1 {
+            let frame = self.frames.pop().expect("more than the root frame is open");
+            self.write(frame);
+        }
+        while let Some(rows) = self.tables.pop() {
+            self.write_table(rows);
+        }
+        let mut markdown = self.out().trim().to_string();
+        markdown.push('\n');
+        markdown
+    }
+}
+
+/// `url` as a markdown link destination, wrapped in `<>` when it holds spaces or brackets.
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
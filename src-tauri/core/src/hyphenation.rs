@@ -0,0 +1,47 @@
+//! Hyphenation of words that would otherwise run past the end of a line.
+//!
+//! hypher's patterns cover about thirty languages, picked by their ISO 639-1 code. Only
+//! the letters of a word are split; leading and trailing punctuation stays attached to
+//! the first and last syllable, and words with digits or inner punctuation are left
+//! whole.
+
+pub use hypher::Lang;
+
+/// Syllables on either side of a break must be at least this many characters long.
+const MIN_SYLLABLE: usize = 2;
+
+/// Language for a code such as `en`, `de-CH` or `pt_BR`; the region is ignored.
+pub fn language(code: &str) -> Option<Lang> {
+    let mut letters = code.trim().chars().map(|ch| ch.to_ascii_lowercase());
+    let first = letters.next().filter(char::is_ascii_lowercase)?;
+    let second = letters.next().filter(char::is_ascii_lowercase)?;
+    if letters.next().is_some_and(|ch| ch != '-' && ch != '_') {
+        return None;
+    }
+    Lang::from_iso([first as u8, second as u8])
+}
+
+/// Byte offsets in `word` where it may be broken with a hyphen, in increasing order.
+pub fn break_points(word: &str, lang: Lang) -> Vec<usize> {
+    let start = word.len()
+        - word
+            .trim_start_matches(|ch: char| !ch.is_alphabetic())
+            .len();
+    let core = word[start..].trim_end_matches(|ch: char| !ch.is_alphabetic());
+    if !core.chars().all(char::is_alphabetic) {
+        return Vec::new();
+    }
+
+    let mut points = Vec::new();
+    let mut offset = start;
+    let syllables: Vec<&str> = hypher::hyphenate(core, lang).collect();
+    for syllable in &syllables[..syllables.len().saturating_sub(1)] {
+        offset += syllable.len();
+        points.push(offset);
+    }
+    points.retain(|&point| {
+        word[start..point].chars().count() >= MIN_SYLLABLE
+            && word[point..start + core.len()].chars().count() >= MIN_SYLLABLE
+    });
+    points
+}
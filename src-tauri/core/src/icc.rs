@@ -0,0 +1,110 @@
+//! A compact sRGB ICC profile, built in code rather than shipped as a binary asset, for
+//! the output intent PDF/A requires.
+
+/// D50-adapted sRGB primaries, as in the widely used IEC 61966-2.1 profile.
+const RED: [f64; 3] = [0.4360, 0.2225, 0.0139];
+const GREEN: [f64; 3] = [0.3851, 0.7169, 0.0971];
+const BLUE: [f64; 3] = [0.1431, 0.0606, 0.7141];
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+const D65: [f64; 3] = [0.9505, 1.0, 1.0891];
+const CURVE_POINTS: usize = 1024;
+
+pub const DESCRIPTION: &str = "sRGB IEC61966-2.1";
+
+/// Returns a version 2 display profile with the sRGB primaries and tone curve.
+pub fn srgb_profile() -> Vec<u8> {
+    let curve = tone_curve();
+    let tags: [(&[u8; 4], Vec<u8>); 8] = [
+        (b"desc", description()),
+        (b"cprt", text("No copyright, use freely")),
+        (b"wtpt", xyz(D65)),
+        (b"rXYZ", xyz(RED)),
+        (b"gXYZ", xyz(GREEN)),
+        (b"bXYZ", xyz(BLUE)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve),
+    ];
+    // The blue curve shares the green one's data.
+    let tag_count = tags.len() + 1;
+
+    let mut data = Vec::new();
+    let mut table = Vec::new();
+    let data_start = 128 + 4 + tag_count * 12;
+    let mut last = (0, 0);
+    for (signature, tag) in &tags {
+        let offset = data_start + data.len();
+        table.extend_from_slice(*signature);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        last = (offset, tag.len());
+        data.extend_from_slice(tag);
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+    }
+    table.extend_from_slice(b"bTRC");
+    table.extend_from_slice(&(last.0 as u32).to_be_bytes());
+    table.extend_from_slice(&(last.1 as u32).to_be_bytes());
+
+    let size = data_start + data.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]);
+    profile.extend_from_slice(&[2, 0x10, 0, 0]);
+    profile.extend_from_slice(b"mntrRGB XYZ ");
+    for part in [2000u16, 1, 1, 0, 0, 0] {
+        profile.extend_from_slice(&part.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 24]);
+    // Perceptual rendering intent.
+    profile.extend_from_slice(&[0; 4]);
+    profile.extend_from_slice(&xyz(D50)[8..]);
+    profile.extend_from_slice(&[0; 48]);
+    profile.extend_from_slice(&(tag_count as u32).to_be_bytes());
+    profile.extend_from_slice(&table);
+    profile.extend_from_slice(&data);
+    profile
+}
+
+fn description() -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(DESCRIPTION.len() as u32 + 1).to_be_bytes());
+    tag.extend_from_slice(DESCRIPTION.as_bytes());
+    tag.push(0);
+    // Empty Unicode and ScriptCode descriptions.
+    tag.extend_from_slice(&[0; 8]);
+    tag.extend_from_slice(&[0; 3]);
+    tag.extend_from_slice(&[0; 67]);
+    tag
+}
+
+fn text(value: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend_from_slice(value.as_bytes());
+    tag.push(0);
+    tag
+}
+
+fn xyz(values: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for value in values {
+        tag.extend_from_slice(&((value * 65536.0).round() as i32).to_be_bytes());
+    }
+    tag
+}
+
+fn tone_curve() -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    tag.extend_from_slice(&(CURVE_POINTS as u32).to_be_bytes());
+    for index in 0..CURVE_POINTS {
+        let encoded = index as f64 / (CURVE_POINTS - 1) as f64;
+        let linear = if encoded <= 0.04045 {
+            encoded / 12.92
+        } else {
+            ((encoded + 0.055) / 1.055).powf(2.4)
+        };
+        tag.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+    tag
+}
@@ -0,0 +1,338 @@
+//! Loading pictures for embedding.
+//!
+//! Baseline and progressive JPEG files in grey or RGB are embedded as they are, since
+//! PDF readers decode them natively; anything else is decoded and stored as RGB pixels,
+//! which the post-processing compresses. The alpha channel of a transparent picture is
+//! kept apart, for the post-processing to attach as a soft mask.
+//!
+//! Pixels are stored the way the file has them; an EXIF orientation, as phone cameras
+//! write, is applied with a transformation when the picture is drawn. Animated GIF and
+//! WebP files show their first frame.
+//!
+//! Pictures are cached by the hash of their file, so an image used throughout a vault is
+//! only decoded once; the post-processing then keeps a single copy of it in the PDF.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::rc::Rc;
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPDecoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{
+    AnimationDecoder, DynamicImage, GenericImageView, GrayImage, ImageDecoder, ImageFormat,
+    ImageReader, RgbImage,
+};
+use printpdf::{ColorBits, ColorSpace, ImageFilter, ImageXObject, Px};
+use sha2::{Digest, Sha256};
+
+/// An image in the form it is written into the PDF.
+pub struct Picture {
+    /// Size of the picture the right way up.
+    pub width_px: u32,
+    pub height_px: u32,
+    /// Whether the file is an animation, of which only the first frame is kept.
+    pub animated: bool,
+    color_space: ColorSpace,
+    data: Vec<u8>,
+    /// `ImageFilter::DCT` when `data` is a JPEG file.
+    filter: Option<ImageFilter>,
+    /// One opacity byte per pixel, for pictures that are not fully opaque.
+    alpha: Option<Vec<u8>>,
+    orientation: Orientation,
+}
+
+impl Picture {
+    pub fn xobject(&self, interpolate: bool) -> ImageXObject {
+        let (width_px, height_px) = self.stored_size();
+        ImageXObject {
+            width: Px(width_px as usize),
+            height: Px(height_px as usize),
+            color_space: self.color_space,
+            bits_per_component: ColorBits::Bit8,
+            interpolate,
+            image_data: self.data.clone(),
+            image_filter: self.filter,
+            clipping_bbox: None,
+            smask: None,
+        }
+    }
+
+    pub fn alpha(&self) -> Option<&[u8]> {
+        self.alpha.as_deref()
+    }
+
+    /// Width and height of the pixels as stored, before the orientation is applied.
+    pub fn stored_size(&self) -> (u32, u32) {
+        if swaps_sides(self.orientation) {
+            (self.height_px, self.width_px)
+        } else {
+            (self.width_px, self.height_px)
+        }
+    }
+
+    /// The picture shrunk to `factor` of its size and stored as a JPEG of `quality`, from
+    /// 1 to 100.
+    pub fn downsample(&self, factor: f32, quality: u8) -> Result<Picture, String> {
+        let (width_px, height_px) = self.stored_size();
+        let new_width = ((width_px as f32 * factor).round() as u32).max(1);
+        let new_height = ((height_px as f32 * factor).round() as u32).max(1);
+        let pixels = self
+            .pixels()?
+            .resize_exact(new_width, new_height, FilterType::Triangle);
+        let pixels = match self.color_space {
+            ColorSpace::Greyscale => DynamicImage::ImageLuma8(pixels.to_luma8()),
+            _ => DynamicImage::ImageRgb8(pixels.to_rgb8()),
+        };
+        let mut data = Vec::new();
+        JpegEncoder::new_with_quality(&mut data, quality)
+            .encode_image(&pixels)
+            .map_err(|err| err.to_string())?;
+
+        let alpha = self.mask()?.map(|mask| {
+            image::imageops::resize(&mask, new_width, new_height, FilterType::Triangle).into_raw()
+        });
+        let (width_px, height_px) = if swaps_sides(self.orientation) {
+            (new_height, new_width)
+        } else {
+            (new_width, new_height)
+        };
+        Ok(Picture {
+            width_px,
+            height_px,
+            color_space: self.color_space,
+            data,
+            filter: Some(ImageFilter::DCT),
+            alpha,
+            orientation: self.orientation,
+            animated: self.animated,
+        })
+    }
+
+    /// `height_px` rows of the upright picture starting `top_px` rows down.
+    pub fn crop_rows(&self, top_px: u32, height_px: u32) -> Result<Picture, String> {
+        let mut pixels = self.pixels()?;
+        pixels.apply_orientation(self.orientation);
+        let pixels = pixels.crop_imm(0, top_px, self.width_px, height_px);
+        let data = match self.color_space {
+            ColorSpace::Greyscale => pixels.to_luma8().into_raw(),
+            _ => pixels.to_rgb8().into_raw(),
+        };
+        let alpha = self.mask()?.map(|mask| {
+            let mut mask = DynamicImage::ImageLuma8(mask);
+            mask.apply_orientation(self.orientation);
+            mask.crop_imm(0, top_px, self.width_px, height_px)
+                .into_luma8()
+                .into_raw()
+        });
+        Ok(Picture {
+            width_px: pixels.width(),
+            height_px: pixels.height(),
+            color_space: self.color_space,
+            data,
+            filter: None,
+            alpha,
+            orientation: Orientation::NoTransforms,
+            animated: self.animated,
+        })
+    }
+
+    /// The stored pixels, decoded.
+    fn pixels(&self) -> Result<DynamicImage, String> {
+        let (width_px, height_px) = self.stored_size();
+        match self.filter {
+            Some(ImageFilter::DCT) => {
+                image::load_from_memory(&self.data).map_err(|err| err.to_string())
+            }
+            _ => match self.color_space {
+                ColorSpace::Greyscale => {
+                    GrayImage::from_raw(width_px, height_px, self.data.clone())
+                        .map(DynamicImage::ImageLuma8)
+                }
+                _ => RgbImage::from_raw(width_px, height_px, self.data.clone())
+                    .map(DynamicImage::ImageRgb8),
+            }
+            .ok_or_else(|| "Image data does not match its size".to_string()),
+        }
+    }
+
+    /// The stored alpha channel as a greyscale image.
+    fn mask(&self) -> Result<Option<GrayImage>, String> {
+        let (width_px, height_px) = self.stored_size();
+        match &self.alpha {
+            Some(alpha) => GrayImage::from_raw(width_px, height_px, alpha.clone())
+                .map(Some)
+                .ok_or_else(|| "Image mask does not match its size".to_string()),
+            None => Ok(None),
+        }
+    }
+
+    /// PDF matrix turning the stored pixels, drawn `width` by `height` points from the
+    /// origin, the right way up with the lower-left corner still at the origin.
+    pub fn orientation_matrix(&self, width: f32, height: f32) -> [f32; 6] {
+        match self.orientation {
+            Orientation::NoTransforms => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            Orientation::FlipHorizontal => [-1.0, 0.0, 0.0, 1.0, width, 0.0],
+            Orientation::Rotate180 => [-1.0, 0.0, 0.0, -1.0, width, height],
+            Orientation::FlipVertical => [1.0, 0.0, 0.0, -1.0, 0.0, height],
+            Orientation::Rotate90 => [0.0, -1.0, 1.0, 0.0, 0.0, width],
+            Orientation::Rotate270 => [0.0, 1.0, -1.0, 0.0, height, 0.0],
+            Orientation::Rotate90FlipH => [0.0, -1.0, -1.0, 0.0, height, width],
+            Orientation::Rotate270FlipH => [0.0, 1.0, 1.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+/// Pictures loaded so far, by the SHA-256 hash of their file.
+#[derive(Default)]
+pub struct Cache {
+    pictures: HashMap<[u8; 32], Rc<Picture>>,
+}
+
+impl Cache {
+    pub fn load(&mut self, path: &Path) -> Result<Rc<Picture>, String> {
+        let bytes = fs::read(path).map_err(|err| err.to_string())?;
+        let hash: [u8; 32] = Sha256::digest(&bytes).into();
+        if let Some(picture) = self.pictures.get(&hash) {
+            return Ok(Rc::clone(picture));
+        }
+        let picture = Rc::new(from_bytes(bytes)?);
+        self.pictures.insert(hash, Rc::clone(&picture));
+        Ok(picture)
+    }
+}
+
+fn from_bytes(bytes: Vec<u8>) -> Result<Picture, String> {
+    let orientation = orientation(&bytes);
+    let animated = is_animated(&bytes);
+    let mut picture = decode(bytes)?;
+    if swaps_sides(orientation) {
+        std::mem::swap(&mut picture.width_px, &mut picture.height_px);
+    }
+    picture.orientation = orientation;
+    picture.animated = animated;
+    Ok(picture)
+}
+
+fn decode(bytes: Vec<u8>) -> Result<Picture, String> {
+    if let Some((width_px, height_px, components)) = jpeg_header(&bytes) {
+        let color_space = match components {
+            1 => Some(ColorSpace::Greyscale),
+            3 => Some(ColorSpace::Rgb),
+            // CMYK files are often stored inverted, which readers cannot tell.
+            _ => None,
+        };
+        if let Some(color_space) = color_space {
+            return Ok(Picture {
+                width_px,
+                height_px,
+                color_space,
+                data: bytes,
+                filter: Some(ImageFilter::DCT),
+                alpha: None,
+                orientation: Orientation::NoTransforms,
+                animated: false,
+            });
+        }
+    }
+
+    let image = image::load_from_memory(&bytes).map_err(|err| err.to_string())?;
+    let (width_px, height_px) = image.dimensions();
+    // printpdf's own soft mask support writes a malformed mask, so the mask is kept apart.
+    let alpha = image
+        .color()
+        .has_alpha()
+        .then(|| {
+            image
+                .to_luma_alpha8()
+                .pixels()
+                .map(|pixel| pixel[1])
+                .collect::<Vec<u8>>()
+        })
+        .filter(|alpha| alpha.iter().any(|&value| value < u8::MAX));
+    Ok(Picture {
+        width_px,
+        height_px,
+        color_space: ColorSpace::Rgb,
+        data: image.to_rgb8().into_raw(),
+        filter: None,
+        alpha,
+        orientation: Orientation::NoTransforms,
+        animated: false,
+    })
+}
+
+/// The EXIF orientation of `bytes`; files without one are shown as stored.
+fn orientation(bytes: &[u8]) -> Orientation {
+    ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .and_then(|mut decoder| decoder.orientation().ok())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+/// Whether `bytes` is a GIF or WebP file with more than one frame.
+fn is_animated(bytes: &[u8]) -> bool {
+    match image::guess_format(bytes) {
+        Ok(ImageFormat::Gif) => GifDecoder::new(Cursor::new(bytes))
+            .is_ok_and(|decoder| decoder.into_frames().take(2).count() > 1),
+        Ok(ImageFormat::WebP) => {
+            WebPDecoder::new(Cursor::new(bytes)).is_ok_and(|decoder| decoder.has_animation())
+        }
+        _ => false,
+    }
+}
+
+/// Whether `orientation` turns the picture on its side.
+fn swaps_sides(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+/// Width, height and component count of an 8-bit baseline or progressive JPEG, the
+/// kinds PDF's DCT filter can decode.
+fn jpeg_header(bytes: &[u8]) -> Option<(u32, u32, u8)> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xFF {
+            // Fill byte before a marker.
+            pos += 1;
+            continue;
+        }
+        let length = usize::from(u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]));
+        match marker {
+            0xC0..=0xC2 => {
+                let segment = bytes.get(pos + 4..pos + 2 + length)?;
+                let [precision, h1, h0, w1, w0, components, ..] = *segment else {
+                    return None;
+                };
+                let height = u32::from(u16::from_be_bytes([h1, h0]));
+                let width = u32::from(u16::from_be_bytes([w1, w0]));
+                return (precision == 8 && width > 0 && height > 0)
+                    .then_some((width, height, components));
+            }
+            // Lossless, hierarchical and arithmetic-coded frames, or image data before
+            // any frame header.
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF | 0xD9 | 0xDA => return None,
+            _ => pos += 2 + length,
+        }
+    }
+    None
+}
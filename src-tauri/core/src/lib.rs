@@ -0,0 +1,5329 @@
+//! Conversion of markdown files, folders, archives and URLs into a single PDF, shared by
+//! the desktop app and the `md2pdf` command.
+
+mod archive;
+mod attributes;
+mod autolink;
+pub mod book;
+mod columns;
+mod css;
+mod csv;
+mod devops;
+mod emoji;
+mod encoding;
+mod encryption;
+mod fallback;
+pub mod filters;
+mod frontmatter;
+mod html;
+mod htmlpage;
+mod hyphenation;
+mod icc;
+mod images;
+pub mod limits;
+mod linearize;
+mod math;
+mod merge;
+mod mermaid;
+mod metrics;
+mod mkdocs;
+mod notion;
+mod obsidian;
+mod pagerange;
+mod plaintext;
+mod postprocess;
+pub mod preview;
+mod remote;
+mod rst;
+mod scripts;
+mod site;
+mod split;
+pub mod style;
+mod tagging;
+mod truetype;
+mod wikilink;
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use printpdf::lopdf::content::Operation;
+use printpdf::lopdf::{Dictionary, Object};
+use printpdf::path::{PaintMode, WindingOrder};
+use printpdf::{
+    Actions, BorderArray, BuiltinFont, Color, ColorArray, CurTransMat, Greyscale,
+    HighlightingMode, Image, ImageTransform, Line, LinkAnnotation, Mm, PdfDocument,
+    PdfDocumentReference, PdfLayerReference, Point, Polygon, Pt, Rect, Rgb, TextMatrix,
+};
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+use attributes::ImageAttributes;
+use html::{HtmlToken, InlineTag};
+use style::TextAlign;
+
+/// The markdown files and images collected from the input, rendered as one document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub markdown_files: Vec<String>,
+    pub image_files: Vec<String>,
+    pub root: String,
+    /// Archive entries and downloaded images left out of the input, with the reason.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Chapters of the mdBook books and MkDocs sites in the input, naming and nesting their
+    /// files.
+    #[serde(default)]
+    pub chapters: Vec<book::Chapter>,
+    /// Session of the desktop app the input was collected in, which files dropped later are
+    /// added to; `0` when it was collected outside the app.
+    #[serde(default)]
+    pub session_id: u64,
+}
+
+impl Document {
+    /// Adds the files of `other`, collected later, after those already here. The PDF goes
+    /// in the folder both inputs share.
+    pub fn extend(&mut self, other: Document) {
+        for file in other.markdown_files {
+            if !self.markdown_files.contains(&file) {
+                self.markdown_files.push(file);
+            }
+        }
+        for file in other.image_files {
+            if !self.image_files.contains(&file) {
+                self.image_files.push(file);
+            }
+        }
+        for chapter in other.chapters {
+            if !self.chapters.iter().any(|known| known.path == chapter.path) {
+                self.chapters.push(chapter);
+            }
+        }
+        self.warnings.extend(other.warnings);
+        let roots = [PathBuf::from(&self.root), PathBuf::from(&other.root)];
+        if let Some(root) = common_root(&roots).filter(|path| path.parent().is_some()) {
+            self.root = root.to_string_lossy().to_string();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertResult {
+    pub output_path: String,
+    /// Every file written, in order: the parts of a split PDF, starting with
+    /// `output_path`, or else `output_path` alone.
+    pub output_paths: Vec<String>,
+    /// What became of a file already at the path asked for, `Overwritten` when any part of
+    /// a split PDF replaced one.
+    pub written: OutputWrite,
+    /// What became of a file already at each of `output_paths`.
+    pub output_writes: Vec<OutputWrite>,
+}
+
+impl ConvertResult {
+    pub fn new(files: &[(PathBuf, OutputWrite)]) -> Self {
+        let paths: Vec<String> = files
+            .iter()
+            .map(|(path, _)| path.to_string_lossy().to_string())
+            .collect();
+        let writes: Vec<OutputWrite> = files.iter().map(|&(_, written)| written).collect();
+        let written = if writes.contains(&OutputWrite::Overwritten) {
+            OutputWrite::Overwritten
+        } else {
+            writes.first().copied().unwrap_or(OutputWrite::Created)
+        };
+        Self {
+            output_path: paths.first().cloned().unwrap_or_default(),
+            output_paths: paths,
+            written,
+            output_writes: writes,
+        }
+    }
+}
+
+/// How far `render_with_progress` has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    /// Index of the file being read or rendered, from 0.
+    pub file_index: usize,
+    pub file_count: usize,
+    /// Rough share of the work done, from 0 to 100.
+    pub percent: u8,
+    pub stage: RenderStage,
+}
+
+impl Progress {
+    fn new(stage: RenderStage, file_index: usize, file_count: usize) -> Self {
+        // Reading is quick next to laying out pages, which takes most of the time.
+        let (start, end) = match stage {
+            RenderStage::Reading => (0, 10),
+            RenderStage::Rendering => (10, 90),
+            RenderStage::Finishing => (90, 90),
+            RenderStage::Writing => (95, 95),
+        };
+        let done = (end - start) * file_index.min(file_count) / file_count.max(1);
+        Self {
+            file_index,
+            file_count,
+            percent: (start + done) as u8,
+            stage,
+        }
+    }
+}
+
+/// What the rendering is busy with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderStage {
+    /// Reading and converting the source files.
+    Reading,
+    /// Laying out the pages of each file.
+    Rendering,
+    /// Filling in page numbers, headers and footers, and the document's structure.
+    Finishing,
+    /// Writing the PDF or its parts.
+    Writing,
+}
+
+/// PDFs written by `convert_each_subfolder`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchResult {
+    /// One result per subfolder converted, in folder order.
+    pub converted: Vec<ConvertResult>,
+    /// Subfolders without markdown files.
+    pub skipped: Vec<String>,
+    /// Subfolders that could not be converted, each with the reason.
+    pub failed: Vec<String>,
+}
+
+/// What happens when the PDF's path already holds a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingOutput {
+    /// Fails naming the file, so the caller can ask and retry.
+    Ask,
+    /// Replaces the file.
+    #[default]
+    Overwrite,
+    /// Writes `name (2).pdf` instead, or the first higher number that is free.
+    Increment,
+}
+
+/// How the PDF was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputWrite {
+    /// No file was at the path.
+    Created,
+    /// The file at the path was replaced.
+    Overwritten,
+    /// The path held a file, so a numbered name was used.
+    Incremented,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// TrueType font used for emoji; common system emoji fonts are tried when unset.
+    pub emoji_font: Option<String>,
+    /// TrueType fonts tried in order for characters the builtin fonts lack, such as
+    /// Cyrillic or CJK, ahead of the installed fonts found for the scripts in use.
+    pub fallback_fonts: Vec<String>,
+    /// Prefixes headings with hierarchical numbers such as `1.2.3`.
+    pub number_headings: bool,
+    /// Vault folder searched first for `![[image]]` embeds, such as `attachments`.
+    pub attachments_folder: Option<String>,
+    /// Numbers code block lines in a gutter.
+    pub code_line_numbers: bool,
+    /// Prints a fenced code block's language above it.
+    pub code_language_labels: bool,
+    /// Deepest heading level listed in the PDF bookmarks, under one entry per file;
+    /// `0` leaves the bookmarks out.
+    pub outline_depth: u32,
+    /// Starts the document with a contents page listing every file and heading.
+    pub table_of_contents: bool,
+    /// Starts each file on a new page instead of straight after the previous one.
+    pub page_break_between_files: bool,
+    /// Starts each file or each level-1 heading on a right-hand (odd) page, leaving the
+    /// page before it blank when needed, for books printed on both sides.
+    pub start_on_odd_page: Option<OddPageStart>,
+    /// Swaps the left and right margins on even pages, so the left margin is always the
+    /// inner one by the binding when printed on both sides.
+    pub mirror_margins: bool,
+    /// Breaks long words at line ends with a hyphen.
+    pub hyphenation: bool,
+    /// ISO 639-1 code of the language words are hyphenated in, such as `en` or `de`;
+    /// English when unset. A file's front matter `lang` takes precedence.
+    pub language: Option<String>,
+    pub page_templates: PageTemplates,
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    pub margins: Margins,
+    pub style: style::Style,
+    /// TOML or JSON theme file replacing `style`.
+    pub theme: Option<String>,
+    /// CSS file whose supported rules are applied on top of the style or theme.
+    pub stylesheet: Option<String>,
+    /// Starts the document with a title page.
+    pub cover_page: Option<CoverPage>,
+    pub metadata: DocumentMetadata,
+    /// Writes a PDF/A-2b archival file: every font embedded, XMP metadata and an sRGB
+    /// output intent.
+    pub pdf_a: bool,
+    /// Protects the document with passwords and restricts printing or copying.
+    pub encryption: Option<encryption::Encryption>,
+    /// Text or image stamped across every page.
+    pub watermark: Option<Watermark>,
+    /// Shrinks images drawn at a higher resolution than needed, for a smaller file.
+    pub image_quality: Option<ImageQuality>,
+    /// Slices images taller than the height limit across pages at the text width
+    /// instead of shrinking them to fit.
+    pub split_tall_images: bool,
+    /// Sets tables and images too wide for the portrait text area on a landscape page,
+    /// turning back to portrait after them, instead of shrinking them to fit.
+    pub landscape_wide_content: bool,
+    /// Adds "(animated)" after the caption of animated GIF and WebP images, which only
+    /// show their first frame.
+    pub mark_animated_images: bool,
+    /// Embeds the markdown files in the PDF as attachments, so the sources travel with it.
+    pub attach_sources: bool,
+    /// Tags headings, paragraphs, lists, tables and figures and records the document
+    /// language, so screen readers can navigate the PDF.
+    pub tagged: bool,
+    /// Writes a linearized ("fast web view") file whose first page shows while the rest
+    /// is still downloading, for documents served over HTTP. Not available with
+    /// `encryption`.
+    pub linearize: bool,
+    /// Reads the input as a Notion markdown export: percent-encoded links and images are
+    /// decoded, files are found when their names differ from the links only by Notion's
+    /// page ids, and the ids are left out of the file names shown.
+    pub notion_export: bool,
+    /// Reads the input as an Obsidian vault: `attachments_folder` defaults to the folder
+    /// the vault's settings save attachments in, and images and links not found beside
+    /// the file are looked up across the vault by name.
+    pub obsidian_vault: bool,
+    /// Reads the input as the sources of a Hugo or Jekyll site.
+    pub site_content: Option<SiteContent>,
+    /// Template of the PDF's file name when no output path is given, such as
+    /// `{title}-{date}.pdf`; see `output_file_name`.
+    pub output_name: Option<String>,
+    /// What happens when the output path already holds a file.
+    pub existing_output: ExistingOutput,
+    /// PDFs whose pages go before the rendered document, such as a ready-made cover.
+    pub pdfs_before: Vec<String>,
+    /// PDFs whose pages go after the rendered document, such as legal boilerplate.
+    pub pdfs_after: Vec<String>,
+    /// Pages of the rendered document kept in the PDF, such as `10-25` or `1-3, 8, 12-`;
+    /// the rest are left out once it is laid out, so page numbers, the contents page and
+    /// links stay as they are in the whole document.
+    pub page_range: Option<String>,
+    /// Titles of the level-1 and level-2 headings whose pages are kept, as the running
+    /// section shows them; with `page_range`, only the pages in both are kept.
+    pub sections: Vec<String>,
+    /// Splits the PDF into numbered parts, `name-1.pdf`, `name-2.pdf` and so on.
+    pub split: Option<SplitOutput>,
+}
+
+/// Page margins in millimetres.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Margins {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Self {
+            top: 15.0,
+            bottom: 15.0,
+            left: 15.0,
+            right: 15.0,
+        }
+    }
+}
+
+impl Margins {
+    /// Rejects negative margins and margins that leave too little of the page for text.
+    fn validate(&self, page_width_mm: f32, page_height_mm: f32) -> Result<(), String> {
+        let sides = [
+            ("Top", self.top),
+            ("Bottom", self.bottom),
+            ("Left", self.left),
+            ("Right", self.right),
+        ];
+        for (side, margin) in sides {
+            if !margin.is_finite() || margin < 0.0 {
+                return Err(format!("{side} margin must be zero or more millimetres"));
+            }
+        }
+        if page_width_mm - self.left - self.right < MIN_TEXT_AREA_MM {
+            return Err(format!(
+                "Left and right margins must leave at least {MIN_TEXT_AREA_MM} mm of the page width"
+            ));
+        }
+        if page_height_mm - self.top - self.bottom < MIN_TEXT_AREA_MM {
+            return Err(format!(
+                "Top and bottom margins must leave at least {MIN_TEXT_AREA_MM} mm of the page height"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    A3,
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    /// Portrait page width and height in millimetres.
+    fn dimensions_mm(self) -> (f32, f32) {
+        match self {
+            PageSize::A3 => (297.0, 420.0),
+            PageSize::A4 => (210.0, 297.0),
+            PageSize::A5 => (148.0, 210.0),
+            PageSize::Letter => (215.9, 279.4),
+            PageSize::Legal => (215.9, 355.6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    /// Width and height in millimetres of pages of `size`.
+    fn page_size_mm(self, size: PageSize) -> (f32, f32) {
+        let (width, height) = size.dimensions_mm();
+        match self {
+            Orientation::Portrait => (width, height),
+            Orientation::Landscape => (height, width),
+        }
+    }
+}
+
+/// Blocks that begin on a right-hand page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OddPageStart {
+    Files,
+    /// Level-1 headings in the markdown; file titles do not count.
+    Headings,
+}
+
+/// Lines stamped centred in the top and bottom margins of every page once layout is done.
+/// `{title}`, `{file}`, `{section}`, `{date}`, `{page}` and `{pages}` are replaced by the
+/// document title, the file at the top of the page, the running section, the front matter
+/// date or today's date, the page number and the page count. The running section is the
+/// first level-1 or level-2 heading on the page, or else the last one before it in the
+/// same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageTemplates {
+    pub header: Option<String>,
+    pub footer: Option<String>,
+}
+
+/// Contents of the title page. Unset fields are taken from the first file's front matter
+/// that has them; the date falls back to today.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoverPage {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    /// Image drawn above the title.
+    pub logo: Option<String>,
+}
+
+/// Properties written into the PDF. Unset fields are taken from the first file's front
+/// matter that has them; the title then falls back to the first file's name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+    /// Defaults to the application name and version.
+    pub creator: Option<String>,
+}
+
+/// Translucent stamp drawn over the middle of every page once layout is done: rotated
+/// text such as `DRAFT` or `CONFIDENTIAL`, an image, or both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Watermark {
+    pub text: Option<String>,
+    /// Font size of the text, in points.
+    pub font_size: f32,
+    /// RGB colour of the text, each channel in `0.0..=1.0`.
+    pub color: [f32; 3],
+    /// Counter-clockwise rotation of the text, in degrees.
+    pub angle: f32,
+    /// Opacity of the text and image, from `0.0` (invisible) to `1.0`.
+    pub opacity: f32,
+    /// Image centred on the page, shrunk to fit within the margins.
+    pub image: Option<String>,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            text: None,
+            font_size: 72.0,
+            color: [0.6, 0.6, 0.6],
+            angle: 45.0,
+            opacity: 0.3,
+            image: None,
+        }
+    }
+}
+
+impl Watermark {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err("Watermark opacity must be between 0 and 1".to_string());
+        }
+        if !self.font_size.is_finite() || self.font_size <= 0.0 {
+            return Err("Watermark font size must be more than zero points".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How static-site sources are read. Pages without a front matter title are titled after
+/// their file name, and Jekyll posts without a date are dated by theirs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteContent {
+    /// Leaves out pages whose front matter sets `draft: true` or `published: false`.
+    pub skip_drafts: bool,
+    /// What becomes of Hugo shortcodes and Liquid tags.
+    pub shortcodes: site::Shortcodes,
+}
+
+/// Limit on the resolution images are embedded at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageQuality {
+    /// Images drawn at more pixels per inch are downsampled to this resolution.
+    pub max_dpi: f32,
+    /// JPEG quality from 1 to 100 the downsampled images are stored at.
+    pub jpeg_quality: u8,
+}
+
+impl Default for ImageQuality {
+    fn default() -> Self {
+        Self {
+            max_dpi: 150.0,
+            jpeg_quality: 80,
+        }
+    }
+}
+
+impl ImageQuality {
+    fn validate(&self) -> Result<(), String> {
+        if !self.max_dpi.is_finite() || self.max_dpi < MIN_IMAGE_DPI {
+            return Err(format!("Maximum image resolution must be at least {MIN_IMAGE_DPI} dpi"));
+        }
+        if !(1..=100).contains(&self.jpeg_quality) {
+            return Err("JPEG quality must be between 1 and 100".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Largest parts a split PDF is written in; a part ends at whichever limit it reaches
+/// first. Parts keep the page numbers of the whole document, the pages added from other
+/// PDFs go in the first and last parts, and a single page larger than the size limit
+/// makes a part of its own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplitOutput {
+    pub max_pages: Option<usize>,
+    /// Approximate, estimated from the pages' compressed content, fonts and images.
+    pub max_megabytes: Option<f32>,
+}
+
+impl SplitOutput {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_pages.is_none() && self.max_megabytes.is_none() {
+            return Err("Splitting needs a page count or a size to split at".to_string());
+        }
+        if self.max_pages == Some(0) {
+            return Err("Parts must have at least one page".to_string());
+        }
+        if self
+            .max_megabytes
+            .is_some_and(|megabytes| !megabytes.is_finite() || megabytes <= 0.0)
+        {
+            return Err("Part size must be more than zero megabytes".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for PageTemplates {
+    fn default() -> Self {
+        Self {
+            header: None,
+            footer: Some("Page {page} of {pages}".to_string()),
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            emoji_font: None,
+            fallback_fonts: Vec::new(),
+            number_headings: false,
+            attachments_folder: None,
+            code_line_numbers: false,
+            code_language_labels: false,
+            outline_depth: 3,
+            table_of_contents: false,
+            page_break_between_files: true,
+            start_on_odd_page: None,
+            mirror_margins: false,
+            hyphenation: false,
+            language: None,
+            page_templates: PageTemplates::default(),
+            page_size: PageSize::A4,
+            orientation: Orientation::Portrait,
+            margins: Margins::default(),
+            style: style::Style::default(),
+            theme: None,
+            stylesheet: None,
+            cover_page: None,
+            metadata: DocumentMetadata::default(),
+            pdf_a: false,
+            encryption: None,
+            watermark: None,
+            image_quality: None,
+            split_tall_images: false,
+            landscape_wide_content: false,
+            mark_animated_images: false,
+            attach_sources: false,
+            tagged: false,
+            linearize: false,
+            notion_export: false,
+            obsidian_vault: false,
+            site_content: None,
+            output_name: None,
+            existing_output: ExistingOutput::default(),
+            pdfs_before: Vec::new(),
+            pdfs_after: Vec::new(),
+            page_range: None,
+            sections: Vec::new(),
+            split: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Rejects options that cannot be rendered: margins that do not fit the page, a style
+    /// out of range, and combinations a PDF cannot have or this crate cannot write.
+    pub fn validate(&self) -> Result<(), String> {
+        let (page_width_mm, page_height_mm) = self.orientation.page_size_mm(self.page_size);
+        self.margins.validate(page_width_mm, page_height_mm)?;
+        self.style.validate()?;
+        if self.pdf_a && self.encryption.is_some() {
+            return Err("PDF/A documents cannot be encrypted".to_string());
+        }
+        // Encryption keys each object by its number, and linearizing renumbers them.
+        if self.linearize && self.encryption.is_some() {
+            return Err("Encrypted documents cannot be linearized by this app".to_string());
+        }
+        if self.pdf_a && self.attach_sources {
+            return Err("PDF/A-2b documents cannot carry markdown attachments".to_string());
+        }
+        // Pages merged from other PDFs keep their own fonts and colours, which the PDF/A
+        // claim in the metadata would then cover.
+        if self.pdf_a && !(self.pdfs_before.is_empty() && self.pdfs_after.is_empty()) {
+            return Err("PDF/A-2b documents cannot include pages of other PDFs".to_string());
+        }
+        if let Some(watermark) = &self.watermark {
+            watermark.validate()?;
+        }
+        if let Some(quality) = &self.image_quality {
+            quality.validate()?;
+        }
+        if let Some(split) = &self.split {
+            split.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Input collected by `scan_inputs`.
+pub struct ScannedInput {
+    pub input: Document,
+    /// Unpacked archives and downloads, which the input's files are in until dropped.
+    pub temp_dirs: Vec<TempDir>,
+}
+
+/// Why `scan_inputs` failed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanError {
+    pub message: String,
+    /// The limit passed and the counts reached, when the input was too large.
+    pub too_large: Option<limits::TooLarge>,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<String> for ScanError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            too_large: None,
+        }
+    }
+}
+
+impl From<limits::TooLarge> for ScanError {
+    fn from(too_large: limits::TooLarge) -> Self {
+        Self {
+            message: too_large.to_string(),
+            too_large: Some(too_large),
+        }
+    }
+}
+
+impl From<ScanError> for String {
+    fn from(err: ScanError) -> Self {
+        err.message
+    }
+}
+
+/// Collects the markdown files and images of `input_paths`, which are files, folders,
+/// archives or URLs, that `filters` keeps, failing once `limits` are passed. Encrypted
+/// zips are opened with their password in `passwords`, by input path. The PDF of URL
+/// inputs goes in `download_dir`.
+pub fn scan_inputs(
+    input_paths: Vec<String>,
+    filters: &filters::AssetFilters,
+    limits: &limits::ScanLimits,
+    passwords: &HashMap<String, String>,
+    download_dir: impl Fn() -> Result<PathBuf, String>,
+) -> Result<ScannedInput, ScanError> {
+    if input_paths.is_empty() {
+        return Err("No input paths provided".to_string().into());
+    }
+    let filter = filters::Filter::new(filters)?;
+    limits.validate()?;
+
+    let mut temp_dirs = Vec::new();
+    let mut scan_roots: Vec<PathBuf> = Vec::new();
+    let mut output_roots: Vec<PathBuf> = Vec::new();
+    let mut warnings = Vec::new();
+
+    for input_path in input_paths {
+        if remote::is_url(&input_path) {
+            let fetched = remote::fetch(&input_path)?;
+            scan_roots.push(fetched.root);
+            output_roots.push(download_dir()?);
+            temp_dirs.push(fetched.dir);
+            warnings.extend(fetched.warnings);
+            continue;
+        }
+
+        let path = PathBuf::from(&input_path);
+        if !path.exists() {
+            return Err(format!("Input path does not exist: {}", path.to_string_lossy()).into());
+        }
+
+        let archive = path.is_file().then(|| archive::Kind::of(&path)).flatten();
+        if let Some(kind) = archive {
+            let password = passwords.get(&input_path).map(String::as_str);
+            // The frontend asks for the password when it sees these errors.
+            let extracted = archive::extract(&path, kind, password).map_err(|err| {
+                match err.as_str() {
+                    archive::PASSWORD_REQUIRED | archive::WRONG_PASSWORD => {
+                        format!("{err} for {input_path}")
+                    }
+                    _ => err,
+                }
+            })?;
+            scan_roots.push(extracted.dir.path().to_path_buf());
+            output_roots.push(path.parent().unwrap_or(Path::new(".")).to_path_buf());
+            temp_dirs.push(extracted.dir);
+            warnings.extend(extracted.warnings);
+        } else if path.is_file() {
+            scan_roots.push(path.clone());
+            output_roots.push(path.parent().unwrap_or(Path::new(".")).to_path_buf());
+        } else {
+            scan_roots.push(path.clone());
+            output_roots.push(path.clone());
+        }
+    }
+
+    let assets = collect_assets(&scan_roots, &filter, limits)?;
+    let output_root = common_root(&output_roots)
+        .filter(|path| path.parent().is_some())
+        .unwrap_or_else(|| output_roots[0].clone());
+
+    Ok(ScannedInput {
+        input: Document {
+            markdown_files: assets.markdown_files,
+            image_files: assets.image_files,
+            root: output_root.to_string_lossy().to_string(),
+            warnings,
+            chapters: assets.chapters,
+            session_id: 0,
+        },
+        temp_dirs,
+    })
+}
+
+/// `files` in `order` without the `excluded` ones, the files missing from `order` following
+/// in their current order.
+pub fn arrange_files(
+    files: &[String],
+    order: &[String],
+    excluded: &[String],
+) -> Result<Vec<String>, String> {
+    for file in order.iter().chain(excluded) {
+        if !files.contains(file) {
+            return Err(format!("{file} is not part of the input"));
+        }
+    }
+    let mut arranged: Vec<String> = Vec::with_capacity(files.len());
+    for file in order.iter().chain(files) {
+        if !excluded.contains(file) && !arranged.contains(file) {
+            arranged.push(file.clone());
+        }
+    }
+    if arranged.is_empty() {
+        return Err("Select at least one markdown file to convert".to_string());
+    }
+    Ok(arranged)
+}
+
+/// Renders each immediate subfolder of `folder` into its own PDF in `output_dir`, named by
+/// `output_name` or else after the subfolder. Hidden folders are left out, and a subfolder
+/// that cannot be converted does not stop the others.
+pub fn convert_each_subfolder(
+    folder: &Path,
+    output_dir: &Path,
+    filters: &filters::AssetFilters,
+    limits: &limits::ScanLimits,
+    options: &RenderOptions,
+) -> Result<BatchResult, String> {
+    if !folder.is_dir() {
+        return Err(format!("Not a folder: {}", folder.display()));
+    }
+    if !output_dir.is_dir() {
+        return Err(format!("Output folder does not exist: {}", output_dir.display()));
+    }
+    let mut subfolders: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|err| err.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            path.is_dir() && !hidden
+        })
+        .collect();
+    if subfolders.is_empty() {
+        return Err(format!("No subfolders in {}", folder.display()));
+    }
+    subfolders.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+    let mut options = options.clone();
+    if options.output_name.is_none() {
+        options.output_name = Some("{folder}.pdf".to_string());
+    }
+    let mut result = BatchResult::default();
+    for subfolder in subfolders {
+        let name = subfolder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match convert_subfolder(&subfolder, output_dir, filters, limits, &options, &result) {
+            Ok(Some(converted)) => result.converted.push(converted),
+            Ok(None) => result.skipped.push(name),
+            Err(err) => result.failed.push(format!("{name}: {err}")),
+        }
+    }
+    Ok(result)
+}
+
+/// Renders `subfolder` into `output_dir`, or `None` when it holds no markdown. `done`
+/// holds the PDFs already written for other subfolders, which are not written over.
+fn convert_subfolder(
+    subfolder: &Path,
+    output_dir: &Path,
+    filters: &filters::AssetFilters,
+    limits: &limits::ScanLimits,
+    options: &RenderOptions,
+    done: &BatchResult,
+) -> Result<Option<ConvertResult>, String> {
+    let scanned = scan_inputs(
+        vec![subfolder.to_string_lossy().into_owned()],
+        filters,
+        limits,
+        &HashMap::new(),
+        || Ok(output_dir.to_path_buf()),
+    )?;
+    let input = scanned.input;
+    if input.markdown_files.is_empty() {
+        return Ok(None);
+    }
+    let name = default_output_path(&input, Some(options))?;
+    let path = output_dir.join(name.file_name().unwrap_or_default());
+    // Numbered or refused names never replace another folder's PDF; overwriting would.
+    if options.existing_output == ExistingOutput::Overwrite {
+        let taken = path.to_string_lossy();
+        let converted = done.converted.iter().flat_map(|converted| &converted.output_paths);
+        if converted.into_iter().any(|written| *written == taken) {
+            return Err(format!(
+                "{} is already the PDF of another folder; put {{folder}} in the file name",
+                path.display()
+            ));
+        }
+    }
+    let written = render(&input, &path, options.clone())?;
+    Ok(Some(ConvertResult::new(&written)))
+}
+
+/// Writes the pages of the PDFs `inputs`, in order, to the PDF at `output_path`.
+pub fn merge_pdf_files(inputs: &[String], output_path: &Path) -> Result<(), String> {
+    if inputs.len() < 2 {
+        return Err("Choose at least two PDFs to merge".to_string());
+    }
+    let documents = inputs
+        .iter()
+        .map(|input| merge::load(Path::new(input)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let bytes = merge::merge(documents)?;
+    fs::write(output_path, bytes).map_err(|err| err.to_string())
+}
+
+/// Files found in the input.
+struct Assets {
+    markdown_files: Vec<String>,
+    image_files: Vec<String>,
+    /// Chapters of the mdBook books and MkDocs sites among the markdown files.
+    chapters: Vec<book::Chapter>,
+}
+
+/// Opens the PDF or text export at `path` in the system's default viewer, without waiting
+/// for it to close. Other files are refused, so this cannot be used to launch programs.
+pub fn open_in_viewer(path: &Path) -> Result<(), String> {
+    let is_export = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("pdf") || extension.eq_ignore_ascii_case("txt")
+        });
+    if !is_export {
+        return Err(format!("Only PDF and text exports can be opened: {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("{} no longer exists", path.display()));
+    }
+    open::that_detached(path).map_err(|err| format!("Could not open {}: {err}", path.display()))
+}
+
+/// Opens the folder holding the file at `path` in Explorer, Finder or the desktop's file
+/// manager with the file selected. File managers on Linux that do not take the
+/// `org.freedesktop.FileManager1` request open the folder without a selection.
+pub fn reveal_in_file_manager(path: &Path) -> Result<(), String> {
+    let path = fs::canonicalize(path)
+        .map_err(|err| format!("{} no longer exists: {err}", path.display()))?;
+    let failed = |err: std::io::Error| format!("Could not reveal {}: {err}", path.display());
+    if cfg!(target_os = "windows") {
+        // Explorer exits with an error code even when it opened, so it is not waited for.
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(&path);
+        Command::new("explorer").arg(select).spawn().map_err(failed)?;
+        return Ok(());
+    }
+    if cfg!(target_os = "macos") {
+        Command::new("open").arg("-R").arg(&path).spawn().map_err(failed)?;
+        return Ok(());
+    }
+
+    let uri = url::Url::from_file_path(&path)
+        .map_err(|_| format!("Could not reveal {}", path.display()))?;
+    let shown = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{uri}"))
+        .arg("string:")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !shown {
+        let folder = path.parent().unwrap_or(&path);
+        open::that_detached(folder).map_err(failed)?;
+    }
+    Ok(())
+}
+
+/// The path the PDF meant for `path` is written to when `existing` says what to do with a
+/// file already there, and how it is written.
+pub fn free_output_path(
+    path: PathBuf,
+    existing: ExistingOutput,
+) -> Result<(PathBuf, OutputWrite), String> {
+    if !path.exists() {
+        return Ok((path, OutputWrite::Created));
+    }
+    match existing {
+        ExistingOutput::Ask => Err(format!("Output file already exists: {}", path.display())),
+        ExistingOutput::Overwrite => Ok((path, OutputWrite::Overwritten)),
+        ExistingOutput::Increment => {
+            let mut number = 2;
+            loop {
+                let numbered = numbered_path(&path, number);
+                if !numbered.exists() {
+                    return Ok((numbered, OutputWrite::Incremented));
+                }
+                number += 1;
+            }
+        }
+    }
+}
+
+/// The paths the `count` parts of the PDF meant for `path` are written to, and how each
+/// is written. Parts are numbered together, so when `existing` says to increment and any
+/// part's path is taken, the whole set moves to `name (2)-1.pdf` and so on.
+fn free_part_paths(
+    path: &Path,
+    count: usize,
+    existing: ExistingOutput,
+) -> Result<Vec<(PathBuf, OutputWrite)>, String> {
+    let part_paths = |path: &Path| -> Vec<PathBuf> {
+        (1..=count).map(|number| part_path(path, number)).collect()
+    };
+    let paths = part_paths(path);
+    let Some(taken) = paths.iter().find(|path| path.exists()) else {
+        return Ok(paths.into_iter().map(|path| (path, OutputWrite::Created)).collect());
+    };
+    match existing {
+        ExistingOutput::Ask => Err(format!("Output file already exists: {}", taken.display())),
+        ExistingOutput::Overwrite => Ok(paths
+            .into_iter()
+            .map(|path| {
+                let written = if path.exists() {
+                    OutputWrite::Overwritten
+                } else {
+                    OutputWrite::Created
+                };
+                (path, written)
+            })
+            .collect()),
+        ExistingOutput::Increment => {
+            let mut number = 2;
+            loop {
+                let paths = part_paths(&numbered_path(path, number));
+                if paths.iter().all(|path| !path.exists()) {
+                    let written = OutputWrite::Incremented;
+                    return Ok(paths.into_iter().map(|path| (path, written)).collect());
+                }
+                number += 1;
+            }
+        }
+    }
+}
+
+/// `path` with ` (number)` added to its file name before the extension.
+fn numbered_path(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem} ({number}).{}", extension.to_string_lossy()),
+        None => format!("{stem} ({number})"),
+    };
+    path.with_file_name(name)
+}
+
+/// The PDF path of `input` when none is chosen: in the input's folder, named by the
+/// `output_name` template of `options` or else `markdown_export.pdf`.
+pub fn default_output_path(
+    input: &Document,
+    options: Option<&RenderOptions>,
+) -> Result<PathBuf, String> {
+    let root = PathBuf::from(&input.root);
+    let name = match options.and_then(|options| options.output_name.as_deref()) {
+        Some(template) => output_file_name(template, &input.markdown_files, &root)?,
+        None => OUTPUT_FILE_NAME.to_string(),
+    };
+    Ok(root.join(name))
+}
+
+/// The PDF file name `template` gives for `files`, collected in the folder `root`.
+/// `{title}` is the first front matter title, or the folder's name when no file has one,
+/// `{folder}` the folder's name and `{date}` today's date. Characters file names cannot
+/// hold become `-`, and `.pdf` is added when missing.
+fn output_file_name(template: &str, files: &[String], root: &Path) -> Result<String, String> {
+    let folder = root
+        .file_name()
+        .map_or("markdown_export".to_string(), |name| name.to_string_lossy().into_owned());
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in output name {template}"))?;
+        let value = match &rest[start + 1..start + end] {
+            "title" => files
+                .iter()
+                .find_map(|file| {
+                    let bytes = fs::read(file).ok()?;
+                    let contents = encoding::decode(&bytes);
+                    split_source(Path::new(file), &contents).0.title
+                })
+                .unwrap_or_else(|| folder.clone()),
+            "folder" => folder.clone(),
+            "date" => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            other => return Err(format!("Unknown placeholder {{{other}}} in output name")),
+        };
+        name.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    let mut name: String = name
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            ch if ch.is_control() => '-',
+            ch => ch,
+        })
+        .collect();
+    name = name.trim().trim_matches('.').to_string();
+    if name.is_empty() {
+        return Err(format!("Output name {template} gives an empty file name"));
+    }
+    if !name.to_lowercase().ends_with(".pdf") {
+        name.push_str(".pdf");
+    }
+    Ok(name)
+}
+
+/// The output path `path` the user chose, given `extension` when it has none, once its
+/// folder is known to exist and take new files.
+pub fn chosen_output_path(path: &str, extension: &str) -> Result<PathBuf, String> {
+    let mut path = PathBuf::from(path);
+    if !path.is_absolute() || path.file_name().is_none() {
+        return Err(format!("Output path must be an absolute file path: {}", path.display()));
+    }
+    if path.extension().is_none() {
+        path.set_extension(extension);
+    }
+    if path.is_dir() {
+        return Err(format!("Output path is a folder: {}", path.display()));
+    }
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    if !dir.is_dir() {
+        return Err(format!("Output folder does not exist: {}", dir.display()));
+    }
+    // Creating a file is the only dependable test; permission bits miss ACLs and
+    // read-only mounts.
+    tempfile::NamedTempFile::new_in(dir)
+        .map_err(|err| format!("Output folder is not writable: {}: {err}", dir.display()))?;
+    Ok(path)
+}
+
+/// Markdown files and images under `roots` that `filter` keeps. Files given directly are
+/// matched by their name. An mdBook book or MkDocs site contributes only the chapters its
+/// summary or nav lists, in their order, and an Azure DevOps wiki its pages in the order
+/// of its `.order` files.
+fn collect_assets(
+    roots: &[PathBuf],
+    filter: &filters::Filter,
+    limits: &limits::ScanLimits,
+) -> Result<Assets, ScanError> {
+    let mut markdown_files = Vec::new();
+    let mut image_files = Vec::new();
+    let mut chapters = Vec::new();
+    let mut tally = limits::Tally::new(*limits);
+    let size = |path: &Path| fs::metadata(path).map_or(0, |metadata| metadata.len());
+    let is_document = |path: &Path| {
+        is_markdown(path)
+            || (filter.collects_text() && is_text(path))
+            || (filter.collects_html() && is_html(path))
+            || (filter.collects_csv() && is_csv(path))
+            || (filter.collects_rst() && is_rst(path))
+    };
+
+    for root in roots {
+        if root.is_file() {
+            let name = Path::new(root.file_name().unwrap_or_default());
+            if is_document(root) && filter.keeps_markdown(name) {
+                tally.add_markdown(size(root))?;
+                markdown_files.push(root.to_string_lossy().to_string());
+            }
+            continue;
+        }
+        let mut found = Vec::new();
+        let walk = WalkDir::new(root)
+            .max_depth(limits.max_depth)
+            .sort_by_file_name()
+            .into_iter()
+            // The root itself may be a hidden temporary folder holding an unpacked archive.
+            .filter_entry(|entry| entry.depth() == 0 || !filter.skips_entry(entry.file_name()));
+        for entry in walk.filter_map(|entry| entry.ok()) {
+            // The walk stops at the deepest level, so a folder there would be left out.
+            if entry.file_type().is_dir() && entry.depth() == limits.max_depth {
+                return Err(tally.too_deep().into());
+            }
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            if is_document(path) && filter.keeps_markdown(relative) {
+                tally.add_markdown(size(path))?;
+                found.push(path.to_string_lossy().to_string());
+            } else if is_image(path) && filter.keeps_image(relative) {
+                tally.add_image(size(path))?;
+                image_files.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        let listed = match book::summary_path(root)? {
+            Some(summary) => Some(book::chapters(&summary)?),
+            None => match mkdocs::config_path(root) {
+                Some(config) => mkdocs::chapters(&config)?,
+                None if devops::is_wiki(root) => {
+                    let found: Vec<PathBuf> = found.iter().map(PathBuf::from).collect();
+                    Some(devops::chapters(root, &found)?)
+                }
+                None => None,
+            },
+        };
+        let Some(listed) = listed else {
+            markdown_files.extend(sort_markdown(found, root)?);
+            continue;
+        };
+        let found: Vec<PathBuf> =
+            found.iter().map(|file| normalize_path(Path::new(file))).collect();
+        for chapter in listed {
+            if found.contains(&normalize_path(Path::new(&chapter.path))) {
+                markdown_files.push(chapter.path.clone());
+                chapters.push(chapter);
+            }
+        }
+    }
+
+    Ok(Assets {
+        markdown_files,
+        image_files,
+        chapters,
+    })
+}
+
+/// Puts `files` in the order their front matter `order` or `weight` gives, files without
+/// one last, and otherwise in natural order of their paths under `root`, so `2-intro.md`
+/// comes before `10-api.md`.
+fn sort_markdown(files: Vec<String>, root: &Path) -> Result<Vec<String>, String> {
+    let mut keyed = files
+        .into_iter()
+        .map(|file| {
+            let bytes = fs::read(&file).map_err(|err| err.to_string())?;
+            let (front_matter, _) = frontmatter::split(&encoding::decode(&bytes));
+            Ok((front_matter.order, file))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    keyed.sort_by(|(a_order, a), (b_order, b)| {
+        let by_order = match (a_order, b_order) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        let components = |file: &String| {
+            let path = Path::new(file);
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        by_order.then_with(|| {
+            let (a, b) = (components(a), components(b));
+            a.iter()
+                .zip(&b)
+                .map(|(a, b)| natural_cmp(a, b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len()))
+        })
+    });
+    Ok(keyed.into_iter().map(|(_, file)| file).collect())
+}
+
+/// Compares names with runs of digits taken as numbers and letters regardless of case.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let (Some(&a_char), Some(&b_char)) = (a_chars.peek(), b_chars.peek()) else {
+            return a_chars.peek().is_some().cmp(&b_chars.peek().is_some()).then(a.cmp(b));
+        };
+        let ordering = if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                let mut run = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    run.push(digit);
+                }
+                run.trim_start_matches('0').to_string()
+            };
+            let (a_run, b_run) = (digits(&mut a_chars), digits(&mut b_chars));
+            a_run.len().cmp(&b_run.len()).then(a_run.cmp(&b_run))
+        } else {
+            a_chars.next();
+            b_chars.next();
+            a_char.to_lowercase().cmp(b_char.to_lowercase())
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Whether the file at `path` is plain text, rendered as preformatted text.
+fn is_text(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+}
+
+/// Whether the file at `path` is a CSV file, rendered as a table.
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Whether the file at `path` is reStructuredText, converted to markdown before rendering.
+fn is_rst(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rst"))
+}
+
+/// Whether the file at `path` is an HTML page, converted to markdown before rendering.
+fn is_html(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("md") | Some("markdown")
+    )
+}
+
+fn is_image(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("png")
+            | Some("jpg")
+            | Some("jpeg")
+            | Some("gif")
+            | Some("webp")
+            | Some("bmp")
+    )
+}
+
+fn common_root(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut iter = paths.iter();
+    let first = iter.next()?.components().collect::<Vec<_>>();
+    let mut common_len = first.len();
+
+    for path in iter {
+        let components = path.components().collect::<Vec<_>>();
+        common_len = common_len.min(components.len());
+        for i in 0..common_len {
+            if components[i] != first[i] {
+                common_len = i;
+                break;
+            }
+        }
+    }
+
+    if common_len == 0 {
+        None
+    } else {
+        let mut common = PathBuf::new();
+        for component in &first[..common_len] {
+            common.push(component.as_os_str());
+        }
+        Some(common)
+    }
+}
+
+/// Name of the PDF written next to the input.
+pub const OUTPUT_FILE_NAME: &str = "markdown_export.pdf";
+const DOCUMENT_NAME: &str = "Markdown Export";
+const CREATOR: &str = concat!("Markdown to PDF ", env!("CARGO_PKG_VERSION"));
+const MM_PER_PT: f32 = 25.4 / 72.0;
+/// Smallest width and height margins may leave for text.
+const MIN_TEXT_AREA_MM: f32 = 50.0;
+/// Lowest resolution images may be downsampled to.
+const MIN_IMAGE_DPI: f32 = 36.0;
+const MAX_IMAGE_HEIGHT_MM: f32 = 120.0;
+/// Shortest slice of a split image drawn at the bottom of a page.
+const MIN_IMAGE_SLICE_MM: f32 = 20.0;
+const COVER_LOGO_HEIGHT_MM: f32 = 40.0;
+const TABLE_CELL_PADDING_MM: f32 = 1.5;
+const DEFINITION_INDENT_MM: f32 = 8.0;
+const ALERT_INDENT_MM: f32 = 5.0;
+const TOC_INDENT_MM: f32 = 5.0;
+const COLUMN_GAP_MM: f32 = 6.0;
+const COLUMN_BALANCE_SLACK_MM: f32 = 2.0;
+/// Body lines that must fit under a heading for it to stay on the current page.
+const KEEP_WITH_NEXT_LINES: f32 = 2.0;
+/// Starting height for layout passes that only measure, tall enough to never break.
+const MEASURE_TOP_MM: f32 = 100_000.0;
+
+const MARKDOWN_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_MATH)
+    .union(Options::ENABLE_DEFINITION_LIST)
+    .union(Options::ENABLE_GFM);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum Script {
+    #[default]
+    Normal,
+    Super,
+    Sub,
+}
+
+impl Script {
+    fn scale(self) -> f32 {
+        match self {
+            Script::Normal => 1.0,
+            Script::Super | Script::Sub => 0.7,
+        }
+    }
+
+    /// Baseline shift as a fraction of the surrounding font size.
+    fn rise(self) -> f32 {
+        match self {
+            Script::Normal => 0.0,
+            Script::Super => 0.35,
+            Script::Sub => -0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct SpanStyle {
+    bold: bool,
+    italic: bool,
+    strikethrough: bool,
+    code: bool,
+    /// Set in the Symbol font, used for Greek letters and math operators.
+    symbol: bool,
+    /// Set in the embedded emoji font when one is available.
+    emoji: bool,
+    /// Outside what the builtin fonts can encode; set in the fallback fonts.
+    fallback: bool,
+    script: Script,
+    /// Index into `Renderer::links` when the span is part of a hyperlink.
+    link: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    style: SpanStyle,
+}
+
+/// A link destination: a whole markdown file, or one of its headings by slug.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Anchor {
+    file: PathBuf,
+    slug: Option<String>,
+}
+
+enum LinkTarget {
+    Uri(String),
+    Internal(Anchor),
+}
+
+struct PendingLink {
+    page: usize,
+    rect_mm: [f32; 4],
+    anchor: Anchor,
+}
+
+/// Where layout stood as a file began at the top of a page, for a later layout to resume
+/// from when only that file or ones after it changed.
+#[derive(Clone)]
+struct Checkpoint {
+    /// Index of the file among those laid out, drafts left out.
+    file: usize,
+    /// Index of the page the file begins on.
+    page: usize,
+    cursor_y: f32,
+    heading_counters: Vec<(u32, u32)>,
+}
+
+/// What a live preview keeps of its last layout, so the next one can resume on the page
+/// the first edited file begins on instead of laying out the whole document again.
+#[derive(Default)]
+pub(crate) struct Layout {
+    /// The finished PDF.
+    bytes: Vec<u8>,
+    /// Each file laid out, drafts left out, with its markdown.
+    sources: Vec<(PathBuf, String)>,
+    /// A checkpoint for each file that began on a page of its own, in order. Empty when
+    /// an edit can change the pages before the file it is in.
+    checkpoints: Vec<Checkpoint>,
+    /// Fallback fonts, front matter and date the pages were drawn with, which an edit to
+    /// any file can change.
+    drawn_with: Option<(Vec<PathBuf>, frontmatter::FrontMatter, String)>,
+    /// Pages laid out, for telling whether a page count in the header or footer changed.
+    page_count: usize,
+}
+
+impl Layout {
+    /// The checkpoint to lay `sources` out again from: the last one at or before the first
+    /// file that changed, when the pages before it still hold.
+    fn resume_point(
+        &self,
+        sources: &[(PathBuf, String)],
+        drawn_with: &(Vec<PathBuf>, frontmatter::FrontMatter, String),
+    ) -> Option<Checkpoint> {
+        if self.drawn_with.as_ref() != Some(drawn_with) {
+            return None;
+        }
+        let edited = sources
+            .iter()
+            .zip(&self.sources)
+            .position(|(source, laid_out)| source != laid_out)
+            .unwrap_or(sources.len().min(self.sources.len()));
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.file <= edited && checkpoint.file < sources.len())
+            .cloned()
+    }
+
+    /// The first `count` pages of the last layout's PDF.
+    fn leading_pages(&self, count: usize) -> Result<printpdf::lopdf::Document, String> {
+        let mut doc =
+            printpdf::lopdf::Document::load_mem(&self.bytes).map_err(|err| err.to_string())?;
+        // lopdf numbers pages from 1.
+        let later: Vec<u32> = (count + 1..=doc.get_pages().len()).map(|page| page as u32).collect();
+        doc.delete_pages(&later);
+        doc.prune_objects();
+        Ok(doc)
+    }
+}
+
+/// Multi-column flow for a `:::columns` block.
+struct Columns {
+    count: usize,
+    index: usize,
+    /// Top of the columns on the current page.
+    top_mm: f32,
+    /// Where every column but the last breaks.
+    floor_mm: f32,
+    /// Lowest point any column on the current page has reached.
+    lowest_mm: f32,
+}
+
+/// A heading listed in a table of contents.
+struct TocEntry {
+    level: u32,
+    text: String,
+    anchor: Anchor,
+}
+
+/// A page number drawn after layout, once the page its anchor lands on is known.
+struct PageReference {
+    page: printpdf::PdfPageIndex,
+    layer: printpdf::PdfLayerIndex,
+    right_mm: f32,
+    y_mm: f32,
+    font_size: f32,
+    /// Where the entry's text ends and the dot leader starts.
+    leader_mm: f32,
+    anchor: Anchor,
+}
+
+struct ListItem {
+    spans: Vec<Span>,
+    checked: Option<bool>,
+}
+
+/// Appends text to the last span when the style matches, otherwise starts a new span.
+fn push_span(spans: &mut Vec<Span>, text: &str, style: SpanStyle) {
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+/// Like `push_span`, but expands emoji shortcodes and gives emoji spans of their own.
+fn push_text(spans: &mut Vec<Span>, text: &str, style: SpanStyle) {
+    for ch in emoji::expand_shortcodes(text).chars() {
+        if emoji::is_presentation_mark(ch) {
+            continue;
+        }
+        // Emoji fall back too, for when no emoji font is found.
+        let style = SpanStyle {
+            emoji: emoji::is_emoji(ch),
+            fallback: !fallback::is_win_ansi(ch),
+            ..style
+        };
+        push_span(spans, ch.encode_utf8(&mut [0; 4]), style);
+    }
+}
+
+/// The spans inline content is collected into: the open list item's, or the paragraph's.
+fn inline_target<'a>(
+    list_item: &'a mut Option<ListItem>,
+    paragraph: &'a mut Vec<Span>,
+) -> &'a mut Vec<Span> {
+    match list_item.as_mut() {
+        Some(item) => &mut item.spans,
+        None => paragraph,
+    }
+}
+
+fn spans_text(spans: &[Span]) -> String {
+    spans.iter().map(|span| span.text.as_str()).collect()
+}
+
+struct Fonts {
+    regular: printpdf::IndirectFontRef,
+    bold: printpdf::IndirectFontRef,
+    italic: printpdf::IndirectFontRef,
+    bold_italic: printpdf::IndirectFontRef,
+    mono: printpdf::IndirectFontRef,
+    symbol: printpdf::IndirectFontRef,
+    emoji: Option<truetype::EmbeddedFont>,
+    fallback: Vec<truetype::EmbeddedFont>,
+    /// Set when the faces above are embedded TrueType fonts rather than builtin ones.
+    embedded: Option<EmbeddedFaces>,
+}
+
+/// TrueType stand-ins for the builtin fonts, kept for measuring their glyphs.
+struct EmbeddedFaces {
+    /// Regular, bold, italic and bold italic.
+    text: [truetype::EmbeddedFont; 4],
+    mono: truetype::EmbeddedFont,
+    /// Shows the Symbol font's characters once mapped to Unicode.
+    symbol: truetype::EmbeddedFont,
+}
+
+impl EmbeddedFaces {
+    fn embed(doc: &PdfDocumentReference, family: style::FontFamily) -> Result<Self, String> {
+        let paths = fallback::embeddable_faces(family)?;
+        let [regular, bold, italic, bold_italic] = &paths.text;
+        Ok(Self {
+            text: [
+                truetype::EmbeddedFont::embed(doc, regular)?,
+                truetype::EmbeddedFont::embed(doc, bold)?,
+                truetype::EmbeddedFont::embed(doc, italic)?,
+                truetype::EmbeddedFont::embed(doc, bold_italic)?,
+            ],
+            mono: truetype::EmbeddedFont::embed(doc, &paths.mono)?,
+            symbol: truetype::EmbeddedFont::embed(doc, &paths.symbol)?,
+        })
+    }
+
+    fn for_style(&self, style: SpanStyle) -> &truetype::EmbeddedFont {
+        if style.code {
+            return &self.mono;
+        }
+        if style.symbol {
+            return &self.symbol;
+        }
+        let [regular, bold, italic, bold_italic] = &self.text;
+        match (style.bold, style.italic) {
+            (true, true) => bold_italic,
+            (true, false) => bold,
+            (false, true) => italic,
+            (false, false) => regular,
+        }
+    }
+}
+
+impl Fonts {
+    fn for_style(&self, style: SpanStyle) -> &printpdf::IndirectFontRef {
+        if style.code {
+            return &self.mono;
+        }
+        if style.symbol {
+            return &self.symbol;
+        }
+        if let (true, Some(emoji)) = (style.emoji, &self.emoji) {
+            return &emoji.font;
+        }
+        match (style.bold, style.italic) {
+            (true, true) => &self.bold_italic,
+            (true, false) => &self.bold,
+            (false, true) => &self.italic,
+            (false, false) => &self.regular,
+        }
+    }
+
+    /// Whether text in `style` is set in the fallback fonts: characters the builtin fonts
+    /// lack, emoji among them unless there is an emoji font.
+    fn falls_back(&self, style: SpanStyle) -> bool {
+        style.fallback && !self.fallback.is_empty() && !(style.emoji && self.emoji.is_some())
+    }
+
+    /// Whether text in `style` is emoji no font can show, written as shortcodes instead.
+    fn spells_emoji(&self, style: SpanStyle) -> bool {
+        style.emoji && self.emoji.is_none() && self.fallback.is_empty()
+    }
+
+    /// Splits `text` into runs set in the first fallback font with a glyph for each
+    /// character. Characters no fallback font has stay with the first one.
+    fn fallback_runs<'a>(&'a self, text: &'a str) -> Vec<(&'a truetype::EmbeddedFont, &'a str)> {
+        let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+        for (offset, ch) in text.char_indices() {
+            let font = self
+                .fallback
+                .iter()
+                .position(|font| font.has_glyph(ch))
+                .unwrap_or(0);
+            let end = offset + ch.len_utf8();
+            match runs.last_mut() {
+                Some((last, _, run_end)) if *last == font => *run_end = end,
+                _ => runs.push((font, offset, end)),
+            }
+        }
+        runs.into_iter()
+            .filter_map(|(font, start, end)| Some((self.fallback.get(font)?, &text[start..end])))
+            .collect()
+    }
+}
+
+struct Renderer {
+    doc: PdfDocumentReference,
+    current_page: printpdf::PdfPageIndex,
+    current_layer: printpdf::PdfLayerIndex,
+    page_width_mm: f32,
+    page_height_mm: f32,
+    cursor_y: f32,
+    fonts: Fonts,
+    options: RenderOptions,
+    page_number: usize,
+    links: Vec<LinkTarget>,
+    current_file: PathBuf,
+    slug_counts: HashMap<String, usize>,
+    destinations: HashMap<Anchor, (usize, f32)>,
+    pending_links: Vec<PendingLink>,
+    uses_symbol_font: bool,
+    /// Level and count of each open heading, outermost first, continued across files.
+    heading_counters: Vec<(u32, u32)>,
+    /// Every file in the export, for resolving wiki links by note name.
+    markdown_files: Vec<PathBuf>,
+    /// Every collected image, for resolving `![[image]]` embeds by file name.
+    image_files: Vec<PathBuf>,
+    page_references: Vec<PageReference>,
+    columns: Option<Columns>,
+    outline: Vec<postprocess::OutlineItem>,
+    /// Every page in order, for the finalization sweep.
+    pages: Vec<Page>,
+    fallback_fonts: Vec<PathBuf>,
+    /// Colour body text is drawn in; headings switch it while they are drawn.
+    ink: [f32; 3],
+    /// Extra gap after each space of the line being drawn, widening justified lines.
+    word_spacing_mm: f32,
+    /// Language long words are hyphenated in, for the file being drawn.
+    hyphenation: Option<hyphenation::Lang>,
+    soft_masks: Vec<postprocess::SoftMask>,
+    /// Images decoded so far, reused when a file is drawn again.
+    pictures: images::Cache,
+    /// Structure elements opened so far, when the document is tagged.
+    tags: Option<tagging::Tags>,
+    /// Pages turned to landscape after printpdf added them in portrait.
+    turned_pages: Vec<usize>,
+    /// Title of the last level-1 or level-2 heading in the current file.
+    section: String,
+    /// Added to bookmark levels, nesting a book chapter's bookmarks under its parent's.
+    outline_offset: u32,
+    /// Lays out column blocks to measure them; built on first use and kept, as it embeds
+    /// the same fonts.
+    measurer: Option<Box<Renderer>>,
+    /// Pages of an earlier layout kept ahead of the first page, when layout resumed at a
+    /// `Checkpoint`.
+    first_page: usize,
+}
+
+struct Page {
+    page: printpdf::PdfPageIndex,
+    layer: printpdf::PdfLayerIndex,
+    /// Name of the file shown at the top of the page.
+    file: String,
+    /// Running section title shown in the page templates.
+    section: String,
+    /// Whether a level-1 or level-2 heading has been drawn on the page.
+    starts_section: bool,
+    /// Every running section the page shows part of, the one carried over from the page
+    /// before first.
+    sections: Vec<String>,
+    /// Images drawn so far; printpdf names a page's images X0, X1 and so on.
+    images: usize,
+    /// Left empty so the following page is a right-hand one; it gets no header or footer.
+    blank: bool,
+    width_mm: f32,
+    height_mm: f32,
+}
+
+impl Renderer {
+    /// `fallback_fonts` are embedded for text the builtin fonts cannot show.
+    fn new(options: RenderOptions, fallback_fonts: &[PathBuf]) -> Result<Self, String> {
+        options.validate()?;
+        let (page_width_mm, page_height_mm) = options.orientation.page_size_mm(options.page_size);
+        let (doc, page, layer) =
+            PdfDocument::new(DOCUMENT_NAME, Mm(page_width_mm), Mm(page_height_mm), "Layer 1");
+        let faces = match options.style.font_family {
+            style::FontFamily::Helvetica => [
+                BuiltinFont::Helvetica,
+                BuiltinFont::HelveticaBold,
+                BuiltinFont::HelveticaOblique,
+                BuiltinFont::HelveticaBoldOblique,
+            ],
+            style::FontFamily::Times => [
+                BuiltinFont::TimesRoman,
+                BuiltinFont::TimesBold,
+                BuiltinFont::TimesItalic,
+                BuiltinFont::TimesBoldItalic,
+            ],
+        };
+        // PDF/A forbids the builtin fonts, which are never embedded.
+        let embedded = if options.pdf_a {
+            Some(EmbeddedFaces::embed(&doc, options.style.font_family)?)
+        } else {
+            None
+        };
+        let ([regular, bold, italic, bold_italic], mono, symbol) = match &embedded {
+            Some(faces) => (
+                faces.text.each_ref().map(|face| face.font.clone()),
+                faces.mono.font.clone(),
+                faces.symbol.font.clone(),
+            ),
+            None => {
+                let builtin = |face| doc.add_builtin_font(face).map_err(|err| err.to_string());
+                let [regular, bold, italic, bold_italic] = faces;
+                (
+                    [
+                        builtin(regular)?,
+                        builtin(bold)?,
+                        builtin(italic)?,
+                        builtin(bold_italic)?,
+                    ],
+                    builtin(BuiltinFont::Courier)?,
+                    builtin(BuiltinFont::Symbol)?,
+                )
+            }
+        };
+        let emoji = emoji::load_font(&doc, options.emoji_font.as_deref())?;
+        let fallback = fallback_fonts
+            .iter()
+            .map(|path| truetype::EmbeddedFont::embed(&doc, path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ink = options.style.text_color;
+        let tags = options.tagged.then(|| {
+            let mut tags = tagging::Tags::default();
+            tags.open("Document", None);
+            tags
+        });
+
+        Ok(Self {
+            doc,
+            current_page: page,
+            current_layer: layer,
+            page_width_mm,
+            page_height_mm,
+            cursor_y: page_height_mm - options.margins.top,
+            fonts: Fonts {
+                regular,
+                bold,
+                italic,
+                bold_italic,
+                mono,
+                symbol,
+                emoji,
+                fallback,
+                embedded,
+            },
+            options,
+            page_number: 0,
+            links: Vec::new(),
+            current_file: PathBuf::new(),
+            slug_counts: HashMap::new(),
+            destinations: HashMap::new(),
+            pending_links: Vec::new(),
+            uses_symbol_font: false,
+            heading_counters: Vec::new(),
+            markdown_files: Vec::new(),
+            image_files: Vec::new(),
+            page_references: Vec::new(),
+            columns: None,
+            outline: Vec::new(),
+            fallback_fonts: fallback_fonts.to_vec(),
+            ink,
+            word_spacing_mm: 0.0,
+            hyphenation: None,
+            soft_masks: Vec::new(),
+            pictures: images::Cache::default(),
+            tags,
+            turned_pages: Vec::new(),
+            section: String::new(),
+            outline_offset: 0,
+            measurer: None,
+            first_page: 0,
+            pages: vec![Page {
+                page,
+                layer,
+                file: String::new(),
+                section: String::new(),
+                starts_section: false,
+                sections: Vec::new(),
+                images: 0,
+                blank: false,
+                width_mm: page_width_mm,
+                height_mm: page_height_mm,
+            }],
+        })
+    }
+
+    fn layer(&self) -> PdfLayerReference {
+        self.doc
+            .get_page(self.current_page)
+            .get_layer(self.current_layer)
+    }
+
+    fn add_page(&mut self) {
+        // A block running onto the next page continues in a new marked-content sequence.
+        let marking = self.tags.as_ref().is_some_and(tagging::Tags::is_marking);
+        if marking {
+            self.layer().add_operation(Operation::new("EMC", Vec::new()));
+        }
+        let (page, layer) = self
+            .doc
+            .add_page(Mm(self.page_width_mm), Mm(self.page_height_mm), "Layer 1");
+        self.current_page = page;
+        self.current_layer = layer;
+        self.pages.push(Page {
+            page,
+            layer,
+            file: display_name(&self.current_file, self.options.notion_export),
+            section: self.section.clone(),
+            starts_section: false,
+            sections: [self.section.clone()]
+                .into_iter()
+                .filter(|section| !section.is_empty())
+                .collect(),
+            images: 0,
+            blank: false,
+            width_mm: self.page_width_mm,
+            height_mm: self.page_height_mm,
+        });
+        self.page_number += 1;
+        self.cursor_y = self.page_height_mm - self.options.margins.top;
+        if marking {
+            self.mark_content();
+        }
+    }
+
+    /// Opens a structure element for the block about to be drawn. Elements that hold
+    /// content own everything drawn until the matching `end_tag`.
+    fn begin_tag(&mut self, role: &'static str, alt: Option<String>) {
+        if self.tags.as_mut().is_some_and(|tags| tags.open(role, alt)) {
+            self.mark_content();
+        }
+    }
+
+    fn end_tag(&mut self) {
+        if self.tags.as_mut().is_some_and(tagging::Tags::close) {
+            self.layer().add_operation(Operation::new("EMC", Vec::new()));
+        }
+    }
+
+    /// Starts a marked-content sequence for the innermost open element on the current page.
+    fn mark_content(&mut self) {
+        let page = self.pages.len() - 1;
+        let Some((role, mcid)) = self.tags.as_mut().and_then(|tags| tags.mark(page)) else {
+            return;
+        };
+        let mut properties = Dictionary::new();
+        properties.set("MCID", Object::Integer(mcid.into()));
+        self.layer().add_operation(Operation::new(
+            "BDC",
+            vec![Object::Name(role.as_bytes().to_vec()), Object::Dictionary(properties)],
+        ));
+    }
+
+    /// Marks what is drawn on `layer` until `end_artifact` as decoration that screen
+    /// readers skip, such as running headers and table borders.
+    fn begin_artifact(&self, layer: &PdfLayerReference) {
+        if self.tags.is_some() {
+            layer.add_operation(Operation::new("BMC", vec![Object::Name(b"Artifact".to_vec())]));
+        }
+    }
+
+    fn end_artifact(&self, layer: &PdfLayerReference) {
+        if self.tags.is_some() {
+            layer.add_operation(Operation::new("EMC", Vec::new()));
+        }
+    }
+
+    fn register_link(&mut self, dest: &str) -> usize {
+        let target = if self.options.notion_export && !has_scheme(dest) {
+            self.notion_link_target(dest)
+        } else if self.options.obsidian_vault && !has_scheme(dest) {
+            self.vault_link_target(dest)
+        } else {
+            link_target(&self.current_file, dest)
+        };
+        self.links.push(target);
+        self.links.len() - 1
+    }
+
+    /// Classifies the relative link `dest` of a Notion export, finding the exported file
+    /// it means when it does not name one exactly.
+    fn notion_link_target(&self, dest: &str) -> LinkTarget {
+        match link_target(&self.current_file, &notion::decode(dest)) {
+            LinkTarget::Uri(_) => LinkTarget::Uri(dest.to_string()),
+            LinkTarget::Internal(anchor) if !anchor.file.exists() => {
+                let file = notion::find(&anchor.file, &self.markdown_files)
+                    .cloned()
+                    .unwrap_or(anchor.file);
+                LinkTarget::Internal(Anchor {
+                    file,
+                    slug: anchor.slug,
+                })
+            }
+            target => target,
+        }
+    }
+
+    /// Classifies the relative link `dest` of a vault note, finding the note it names
+    /// elsewhere in the vault when it is not beside the file.
+    fn vault_link_target(&self, dest: &str) -> LinkTarget {
+        match link_target(&self.current_file, &notion::decode(dest)) {
+            LinkTarget::Uri(_) => LinkTarget::Uri(dest.to_string()),
+            LinkTarget::Internal(anchor) if !anchor.file.exists() => {
+                let (path_part, _) = dest.split_once('#').unwrap_or((dest, ""));
+                let note = notion::decode(path_part);
+                let note = Path::new(&note).with_extension("");
+                let file = self
+                    .resolve_note(&note.to_string_lossy())
+                    .unwrap_or(anchor.file);
+                LinkTarget::Internal(Anchor {
+                    file,
+                    slug: anchor.slug,
+                })
+            }
+            target => target,
+        }
+    }
+
+    /// Registers a wiki link, returning `None` when no exported file matches the note.
+    fn register_wiki_link(&mut self, link: &wikilink::WikiLink) -> Option<usize> {
+        let file = if link.note.is_empty() {
+            self.current_file.clone()
+        } else {
+            self.resolve_note(link.note)?
+        };
+        self.links.push(LinkTarget::Internal(Anchor {
+            file,
+            slug: link.heading.map(heading_slug),
+        }));
+        Some(self.links.len() - 1)
+    }
+
+    /// Finds the exported file for a note name or note path, matched case-insensitively
+    /// and preferring files in the current file's folder.
+    fn resolve_note(&self, note: &str) -> Option<PathBuf> {
+        let wanted = name_components(note);
+        let current_dir = self.current_file.parent();
+        self.markdown_files
+            .iter()
+            .filter(|path| lowercase_components(&path.with_extension("")).ends_with(&wanted))
+            .min_by_key(|path| path.parent() != current_dir)
+            .cloned()
+    }
+
+    /// Finds the collected image an embed names, preferring the configured attachments
+    /// folder and then the current file's folder.
+    fn resolve_attachment(&self, name: &str) -> Option<PathBuf> {
+        let wanted = name_components(name);
+        let folder = self
+            .options
+            .attachments_folder
+            .as_deref()
+            .map(name_components)
+            .unwrap_or_default();
+        let current_dir = self.current_file.parent();
+        self.image_files
+            .iter()
+            .filter(|path| lowercase_components(path).ends_with(&wanted))
+            .min_by_key(|path| {
+                let parent = path.parent().map(lowercase_components).unwrap_or_default();
+                let in_folder = !folder.is_empty() && parent.ends_with(&folder);
+                (!in_folder, path.parent() != current_dir)
+            })
+            .cloned()
+    }
+
+    /// Starts a new source file and records its top as a link destination.
+    fn begin_file(&mut self, path: &Path) {
+        self.keep_with_next(2);
+        self.current_file = normalize_path(path);
+        self.section.clear();
+        if self.cursor_y >= self.page_height_mm - self.options.margins.top {
+            if let Some(page) = self.pages.last_mut() {
+                page.file = display_name(path, self.options.notion_export);
+                page.section.clear();
+                page.sections.clear();
+            }
+        }
+        self.slug_counts.clear();
+        let anchor = Anchor {
+            file: self.current_file.clone(),
+            slug: None,
+        };
+        self.destinations
+            .insert(anchor, (self.page_number, self.cursor_y + self.options.margins.top / 2.0));
+    }
+
+    /// Where layout stands, when it is at the top of a page a file could begin on by itself
+    /// in a new layout.
+    fn checkpoint(&self, file: usize) -> Option<Checkpoint> {
+        let page_size = self.options.orientation.page_size_mm(self.options.page_size);
+        let at_top = self.cursor_y >= self.page_height_mm - self.options.margins.top
+            && self.columns.is_none()
+            && (self.page_width_mm, self.page_height_mm) == page_size
+            && self.page_number > 0;
+        at_top.then(|| Checkpoint {
+            file,
+            page: self.page_number,
+            cursor_y: self.cursor_y,
+            heading_counters: self.heading_counters.clone(),
+        })
+    }
+
+    /// Picks layout up at `checkpoint`, the pages before it kept from an earlier layout.
+    fn resume(&mut self, checkpoint: &Checkpoint) {
+        self.first_page = checkpoint.page;
+        self.page_number = checkpoint.page;
+        self.cursor_y = checkpoint.cursor_y;
+        self.heading_counters = checkpoint.heading_counters.clone();
+    }
+
+    /// Whether the pages before a file stay the same when only that file or later ones
+    /// change. A contents page and page references do not, nor do the pages left out for
+    /// `page_range` and `sections`.
+    fn can_resume(&self) -> bool {
+        !self.options.table_of_contents
+            && self.page_references.is_empty()
+            && self.options.page_range.is_none()
+            && self.options.sections.is_empty()
+    }
+
+    /// Whether the header or footer shows the page count.
+    fn counts_pages(&self) -> bool {
+        let templates = &self.options.page_templates;
+        [&templates.header, &templates.footer]
+            .into_iter()
+            .flatten()
+            .any(|template| template.contains("{pages}"))
+    }
+
+    /// Records the position of the heading about to be drawn under its GitHub-style slug.
+    fn heading_destination(&mut self, level: u32, text: &str) {
+        let font_size = self.heading_font_size(level);
+        self.ensure_space(self.line_height_mm(font_size));
+
+        let anchor = Anchor {
+            file: self.current_file.clone(),
+            slug: Some(unique_slug(&mut self.slug_counts, text)),
+        };
+        self.destinations.insert(
+            anchor,
+            (self.page_number, self.cursor_y + Self::pt_to_mm(font_size)),
+        );
+    }
+
+    /// Draws the page numbers of table of contents entries now that layout is done.
+    fn resolve_page_references(&self) {
+        for reference in &self.page_references {
+            let Some((page, _)) = self.destinations.get(&reference.anchor) else {
+                continue;
+            };
+            let number = (page + 1).to_string();
+            let width = Self::pt_to_mm(self.text_width_pt(&number, false, reference.font_size));
+            let layer = self
+                .doc
+                .get_page(reference.page)
+                .get_layer(reference.layer);
+            self.begin_artifact(&layer);
+            layer.use_text(
+                number,
+                reference.font_size,
+                Mm(reference.right_mm - width),
+                Mm(reference.y_mm),
+                &self.fonts.regular,
+            );
+
+            let dot_mm = Self::pt_to_mm(self.text_width_pt(" .", false, reference.font_size));
+            let gap_mm = Self::pt_to_mm(reference.font_size * 0.5);
+            let end_mm = reference.right_mm - width - gap_mm;
+            let dots = ((end_mm - reference.leader_mm - gap_mm) / dot_mm).floor();
+            if dots >= 2.0 {
+                layer.use_text(
+                    " .".repeat(dots as usize),
+                    reference.font_size,
+                    Mm(end_mm - dots * dot_mm),
+                    Mm(reference.y_mm),
+                    &self.fonts.regular,
+                );
+            }
+            self.end_artifact(&layer);
+        }
+    }
+
+    /// Stamps the header and footer on every page once the page count is known.
+    fn stamp_page_templates(&self, title: &str, date: &str) {
+        let templates = &self.options.page_templates;
+        let font_size = self.options.style.margin_text_size;
+        let margins = self.options.margins;
+        let header_offset = margins.top / 2.0 + Self::pt_to_mm(font_size) / 2.0;
+        let pages = (self.first_page + self.pages.len()).to_string();
+
+        let skip = usize::from(self.options.cover_page.is_some()).saturating_sub(self.first_page);
+        for (index, page) in self.pages.iter().enumerate().skip(skip) {
+            if page.blank {
+                continue;
+            }
+            let layer = self.doc.get_page(page.page).get_layer(page.layer);
+            self.begin_artifact(&layer);
+            let lines = [
+                (&templates.header, page.height_mm - header_offset),
+                (&templates.footer, margins.bottom / 2.0),
+            ];
+            for (template, y_mm) in &lines {
+                let Some(template) = template else {
+                    continue;
+                };
+                let text = template
+                    .replace("{title}", title)
+                    .replace("{file}", &page.file)
+                    .replace("{section}", &page.section)
+                    .replace("{date}", date)
+                    .replace("{page}", &(self.first_page + index + 1).to_string())
+                    .replace("{pages}", &pages);
+                let width = Self::pt_to_mm(self.text_width_pt(&text, false, font_size));
+                layer.use_text(
+                    text,
+                    font_size,
+                    Mm((page.width_mm - width) / 2.0),
+                    Mm(*y_mm),
+                    &self.fonts.regular,
+                );
+            }
+            self.end_artifact(&layer);
+        }
+    }
+
+    /// Draws the watermark over every page, the cover included.
+    fn stamp_watermark(&mut self) -> Result<(), String> {
+        let Some(watermark) = self.options.watermark.clone() else {
+            return Ok(());
+        };
+        let image = match &watermark.image {
+            Some(path) => Some(
+                self.pictures
+                    .load(Path::new(path))
+                    .map_err(|err| format!("Failed to open watermark image {path}: {err}"))?,
+            ),
+            None => None,
+        };
+        let margins = self.options.margins;
+
+        for index in 0..self.pages.len() {
+            let page = &self.pages[index];
+            let (page_width_mm, page_height_mm) = (page.width_mm, page.height_mm);
+            let center_x = page_width_mm / 2.0;
+            let center_y = page_height_mm / 2.0;
+            let layer = self.doc.get_page(page.page).get_layer(page.layer);
+            self.begin_artifact(&layer);
+            layer.save_graphics_state();
+            // The opacity lives in a graphics state the post-processing adds to each page.
+            layer.add_operation(Operation::new(
+                "gs",
+                vec![Object::Name(postprocess::WATERMARK_STATE.as_bytes().to_vec())],
+            ));
+            if let Some(image) = &image {
+                let dpi = 96.0f32;
+                let width_mm = image.width_px as f32 * 25.4 / dpi;
+                let height_mm = image.height_px as f32 * 25.4 / dpi;
+                let scale = ((page_width_mm - margins.left - margins.right) / width_mm)
+                    .min((page_height_mm - margins.top - margins.bottom) / height_mm)
+                    .min(1.0);
+                let x = center_x - width_mm * scale / 2.0;
+                let y = center_y - height_mm * scale / 2.0;
+                self.place_image(index, image, x, y, scale, dpi)?;
+            }
+            if let Some(text) = &watermark.text {
+                let runs = self.bold_runs(text, watermark.font_size);
+                // Start the baseline so the middle of the rotated text lands on the centre.
+                let half_width = runs.iter().map(|(_, _, width)| width).sum::<f32>() / 2.0;
+                let half_height = watermark.font_size * 0.35;
+                let (sin, cos) = watermark.angle.to_radians().sin_cos();
+                let x = Self::mm_to_pt(center_x) - half_width * cos + half_height * sin;
+                let y = Self::mm_to_pt(center_y) - half_width * sin - half_height * cos;
+                layer.set_fill_color(style::color(watermark.color));
+                layer.begin_text_section();
+                layer.set_text_matrix(TextMatrix::TranslateRotate(Pt(x), Pt(y), watermark.angle));
+                for (font, run, _) in runs {
+                    layer.set_font(font, watermark.font_size);
+                    layer.write_text(run, font);
+                }
+                layer.end_text_section();
+            }
+            layer.restore_graphics_state();
+            self.end_artifact(&layer);
+        }
+        Ok(())
+    }
+
+    /// Collects the document edits that have to wait until the PDF is saved.
+    fn post_process_edits(&mut self) -> postprocess::Edits {
+        let mut outline = std::mem::take(&mut self.outline);
+        for item in &mut outline {
+            item.page -= self.first_page;
+        }
+        postprocess::Edits {
+            internal_links: self.internal_links(),
+            restore_symbol_encoding: self.uses_symbol_font,
+            outline,
+            metadata: None,
+            pdf_a: self.options.pdf_a,
+            encryption: self.options.encryption.clone(),
+            watermark_opacity: self.options.watermark.as_ref().map(|watermark| watermark.opacity),
+            soft_masks: std::mem::take(&mut self.soft_masks),
+            attachments: Vec::new(),
+            structure: self.tags.take().map(|tags| tags.elements).unwrap_or_default(),
+            language: None,
+            linearize: self.options.linearize,
+            turned_pages: std::mem::take(&mut self.turned_pages),
+            pages_before: Vec::new(),
+            pages_after: Vec::new(),
+            removed_pages: Vec::new(),
+        }
+    }
+
+    /// Resolves internal links against the destinations recorded during layout. After a
+    /// resumed layout, links to the pages kept from the earlier one are left out.
+    fn internal_links(&self) -> Vec<postprocess::InternalLink> {
+        self.pending_links
+            .iter()
+            .filter_map(|link| {
+                let (target_page, target_top) = self.destinations.get(&link.anchor)?;
+                Some(postprocess::InternalLink {
+                    page: link.page - self.first_page,
+                    rect: link.rect_mm.map(Self::mm_to_pt),
+                    target_page: target_page.checked_sub(self.first_page)?,
+                    target_top: Self::mm_to_pt(*target_top),
+                })
+            })
+            .collect()
+    }
+
+    fn ensure_space(&mut self, height_mm: f32) {
+        if self.cursor_y - height_mm < self.floor_mm() {
+            self.next_frame();
+        }
+    }
+
+    /// Left edge of the area text flows into: the page margin, or the current column.
+    fn frame_left_mm(&self) -> f32 {
+        match &self.columns {
+            Some(columns) => {
+                self.left_margin_mm()
+                    + columns.index as f32 * (self.frame_width_mm() + COLUMN_GAP_MM)
+            }
+            None => self.left_margin_mm(),
+        }
+    }
+
+    /// Left margin of the current page. Mirrored margins swap sides on even pages.
+    fn left_margin_mm(&self) -> f32 {
+        // `page_number` counts from zero, so odd values are even pages.
+        if self.options.mirror_margins && self.page_number % 2 == 1 {
+            self.options.margins.right
+        } else {
+            self.options.margins.left
+        }
+    }
+
+    fn frame_width_mm(&self) -> f32 {
+        let text_width = self.page_width_mm - self.options.margins.left - self.options.margins.right;
+        match &self.columns {
+            Some(columns) => {
+                let gaps = (columns.count - 1) as f32 * COLUMN_GAP_MM;
+                (text_width - gaps) / columns.count as f32
+            }
+            None => text_width,
+        }
+    }
+
+    /// Lowest point content may reach before moving on to the next column or page.
+    fn floor_mm(&self) -> f32 {
+        match &self.columns {
+            Some(columns) if columns.index + 1 < columns.count => columns.floor_mm,
+            _ => self.options.margins.bottom,
+        }
+    }
+
+    /// Continues in the next column, or on a new page once the last column is full.
+    fn next_frame(&mut self) {
+        if let Some(columns) = self.columns.as_mut() {
+            columns.lowest_mm = columns.lowest_mm.min(self.cursor_y);
+            if columns.index + 1 < columns.count {
+                columns.index += 1;
+                self.cursor_y = columns.top_mm;
+                return;
+            }
+        }
+
+        self.add_page();
+        if let Some(columns) = self.columns.as_mut() {
+            columns.index = 0;
+            columns.top_mm = self.cursor_y;
+            columns.floor_mm = self.options.margins.bottom;
+            columns.lowest_mm = self.cursor_y;
+        }
+    }
+
+    /// Switches to `count` columns. With a `balance_height`, the columns on the first page
+    /// break early so they end up about that tall instead of filling the first one.
+    fn begin_columns(&mut self, count: usize, balance_height: Option<f32>) {
+        let floor_mm = balance_height
+            .map_or(self.options.margins.bottom, |height| {
+                self.cursor_y - height - COLUMN_BALANCE_SLACK_MM
+            })
+            .max(self.options.margins.bottom);
+        self.columns = Some(Columns {
+            count: count.max(1),
+            index: 0,
+            top_mm: self.cursor_y,
+            floor_mm,
+            lowest_mm: self.cursor_y,
+        });
+    }
+
+    /// Continues on a new page, unless nothing has been drawn on the current one yet.
+    fn page_break(&mut self) {
+        let at_top = self.cursor_y >= self.page_height_mm - self.options.margins.top
+            && self.columns.as_ref().is_none_or(|columns| columns.index == 0);
+        if at_top {
+            return;
+        }
+        if let Some(columns) = self.columns.as_mut() {
+            columns.index = columns.count - 1;
+        }
+        self.next_frame();
+    }
+
+    /// Continues at the top of the next right-hand page, leaving a blank left-hand page
+    /// before it when needed.
+    fn odd_page_break(&mut self) {
+        self.page_break();
+        if self.page_number % 2 == 1 {
+            if let Some(columns) = self.columns.as_mut() {
+                columns.index = columns.count - 1;
+            }
+            self.next_frame();
+            let blank = self.pages.len() - 2;
+            self.pages[blank].blank = true;
+        }
+    }
+
+    /// Whether wide content may go on a landscape page from here.
+    fn can_turn_page(&self) -> bool {
+        self.options.landscape_wide_content
+            && self.columns.is_none()
+            && self.page_width_mm < self.page_height_mm
+    }
+
+    /// Continues on a landscape page, turning the current page when nothing has been
+    /// drawn on it yet. Pages added until `end_landscape` are landscape too.
+    fn begin_landscape(&mut self) {
+        let at_top = self.cursor_y >= self.page_height_mm - self.options.margins.top;
+        std::mem::swap(&mut self.page_width_mm, &mut self.page_height_mm);
+        if at_top {
+            let index = self.pages.len() - 1;
+            let page = &mut self.pages[index];
+            std::mem::swap(&mut page.width_mm, &mut page.height_mm);
+            self.turned_pages.push(index);
+            self.cursor_y = self.page_height_mm - self.options.margins.top;
+        } else {
+            self.add_page();
+        }
+    }
+
+    /// Returns to portrait pages on a new page.
+    fn end_landscape(&mut self) {
+        std::mem::swap(&mut self.page_width_mm, &mut self.page_height_mm);
+        self.add_page();
+    }
+
+    fn end_columns(&mut self) {
+        if let Some(columns) = self.columns.take() {
+            self.cursor_y = columns.lowest_mm.min(self.cursor_y);
+        }
+    }
+
+    fn mm_to_pt(mm: f32) -> f32 {
+        mm / MM_PER_PT
+    }
+
+    fn pt_to_mm(pt: f32) -> f32 {
+        pt * MM_PER_PT
+    }
+
+    fn line_height_mm(&self, font_size: f32) -> f32 {
+        Self::pt_to_mm(font_size * self.options.style.line_spacing)
+    }
+
+    fn max_text_width_mm(&self, indent_mm: f32) -> f32 {
+        self.frame_width_mm() - indent_mm
+    }
+
+    /// Width of `text` set in the body font family, in points.
+    fn text_width_pt(&self, text: &str, bold: bool, font_size: f32) -> f32 {
+        if let Some(faces) = &self.fonts.embedded {
+            return faces.text[usize::from(bold)].text_width_pt(text, font_size);
+        }
+        match self.options.style.font_family {
+            style::FontFamily::Helvetica => metrics::text_width_pt(text, bold, font_size),
+            style::FontFamily::Times => metrics::serif_text_width_pt(text, bold, font_size),
+        }
+    }
+
+    /// Splits `text` into runs set in the bold body font and, for the characters it cannot
+    /// show, in the fallback fonts, as body text is, each with its width in points.
+    fn bold_runs<'a>(
+        &'a self,
+        text: &'a str,
+        font_size: f32,
+    ) -> Vec<(&'a printpdf::IndirectFontRef, &'a str, f32)> {
+        let mut runs = Vec::new();
+        let mut rest = text;
+        while let Some(first) = rest.chars().next() {
+            let win_ansi = fallback::is_win_ansi(first) || self.fonts.fallback.is_empty();
+            let end = rest
+                .find(|ch| fallback::is_win_ansi(ch) != fallback::is_win_ansi(first))
+                .unwrap_or(rest.len());
+            let (run, after) = rest.split_at(end);
+            if win_ansi {
+                runs.push((&self.fonts.bold, run, self.text_width_pt(run, true, font_size)));
+            } else {
+                for (font, run) in self.fonts.fallback_runs(run) {
+                    runs.push((&font.font, run, font.text_width_pt(run, font_size)));
+                }
+            }
+            rest = after;
+        }
+        runs
+    }
+
+    fn mono_text_width_pt(&self, text: &str, font_size: f32) -> f32 {
+        match &self.fonts.embedded {
+            Some(faces) => faces.mono.text_width_pt(text, font_size),
+            None => metrics::mono_text_width_pt(text, font_size),
+        }
+    }
+
+    fn span_width_pt(&self, span: &Span, font_size: f32) -> f32 {
+        let font_size = font_size * span.style.script.scale();
+        if span.style.code {
+            self.mono_text_width_pt(&span.text, font_size)
+        } else if let (true, Some(faces)) = (span.style.symbol, &self.fonts.embedded) {
+            faces
+                .symbol
+                .text_width_pt(&math::symbol_to_unicode(&span.text), font_size)
+        } else if span.style.symbol {
+            metrics::symbol_text_width_pt(&span.text, font_size)
+        } else if let (true, Some(emoji)) = (span.style.emoji, &self.fonts.emoji) {
+            emoji.text_width_pt(&span.text, font_size)
+        } else if self.fonts.falls_back(span.style) {
+            self.fonts
+                .fallback_runs(&span.text)
+                .into_iter()
+                .map(|(font, run)| font.text_width_pt(run, font_size))
+                .sum()
+        } else if self.fonts.spells_emoji(span.style) {
+            let text = emoji::shortcodes(&span.text);
+            match &self.fonts.embedded {
+                Some(faces) => faces.for_style(span.style).text_width_pt(&text, font_size),
+                None => self.text_width_pt(&text, span.style.bold, font_size),
+            }
+        } else if let Some(faces) = &self.fonts.embedded {
+            faces.for_style(span.style).text_width_pt(&span.text, font_size)
+        } else {
+            self.text_width_pt(&span.text, span.style.bold, font_size)
+        }
+    }
+
+    /// Wraps styled spans into lines, keeping words that straddle a style change intact.
+    fn wrap_spans(&self, spans: &[Span], font_size: f32, max_width_mm: f32) -> Vec<Vec<Span>> {
+        self.wrap_spans_with_breaks(spans, font_size, max_width_mm)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    /// Like `wrap_spans`, also telling whether each line ends the paragraph or a forced
+    /// break rather than running out of width.
+    fn wrap_spans_with_breaks(
+        &self,
+        spans: &[Span],
+        font_size: f32,
+        max_width_mm: f32,
+    ) -> Vec<(Vec<Span>, bool)> {
+        let max_width_pt = Self::mm_to_pt(max_width_mm);
+        // `None` marks a forced line break from a hard break or `<br>`.
+        let mut words: Vec<Option<Vec<Span>>> = Vec::new();
+        let mut word: Vec<Span> = Vec::new();
+        for span in spans {
+            for ch in span.text.chars() {
+                if ch.is_whitespace() {
+                    if !word.is_empty() {
+                        words.push(Some(std::mem::take(&mut word)));
+                    }
+                    if ch == '\n' {
+                        words.push(None);
+                    }
+                } else {
+                    push_span(&mut word, ch.encode_utf8(&mut [0; 4]), span.style);
+                }
+            }
+        }
+        if !word.is_empty() {
+            words.push(Some(word));
+        }
+
+        let mut lines: Vec<(Vec<Span>, bool)> = Vec::new();
+        let mut current: Vec<Span> = Vec::new();
+        let mut current_width = 0.0f32;
+        for word in words {
+            let Some(mut word) = word else {
+                lines.push((std::mem::take(&mut current), true));
+                current_width = 0.0;
+                continue;
+            };
+            loop {
+                let word_width: f32 = word
+                    .iter()
+                    .map(|fragment| self.span_width_pt(fragment, font_size))
+                    .sum();
+                let space_style = current
+                    .last()
+                    .map(|span| span.style)
+                    .filter(|style| *style == word[0].style)
+                    .unwrap_or_default();
+                let space_width = if current.is_empty() {
+                    0.0
+                } else {
+                    self.span_width_pt(
+                        &Span {
+                            text: " ".to_string(),
+                            style: space_style,
+                        },
+                        font_size,
+                    )
+                };
+                let available = max_width_pt - current_width - space_width;
+                if word_width > available {
+                    if let Some((head, tail)) = self.hyphenate(&word, font_size, available) {
+                        if !current.is_empty() {
+                            push_span(&mut current, " ", space_style);
+                        }
+                        push_span(&mut current, &head.text, head.style);
+                        lines.push((std::mem::take(&mut current), false));
+                        current_width = 0.0;
+                        word = vec![tail];
+                        continue;
+                    }
+                    if !current.is_empty() {
+                        // Retried on the empty line, where a long word may still be split.
+                        lines.push((std::mem::take(&mut current), false));
+                        current_width = 0.0;
+                        continue;
+                    }
+                }
+
+                if !current.is_empty() {
+                    push_span(&mut current, " ", space_style);
+                }
+                for fragment in &word {
+                    push_span(&mut current, &fragment.text, fragment.style);
+                }
+                current_width += space_width + word_width;
+                break;
+            }
+        }
+
+        if !current.is_empty() || lines.is_empty() {
+            lines.push((current, true));
+        }
+
+        lines
+    }
+
+    /// Splits `word` at its last break point that leaves the first part, hyphen included,
+    /// at most `available_pt` wide. Only words set in a single text style are split.
+    fn hyphenate(&self, word: &[Span], font_size: f32, available_pt: f32) -> Option<(Span, Span)> {
+        let lang = self.hyphenation?;
+        let [fragment] = word else {
+            return None;
+        };
+        let style = fragment.style;
+        if style.code || style.symbol || style.emoji {
+            return None;
+        }
+        let text = &fragment.text;
+        hyphenation::break_points(text, lang)
+            .into_iter()
+            .rev()
+            .find_map(|point| {
+                let head = Span {
+                    text: format!("{}-", &text[..point]),
+                    style,
+                };
+                let tail = Span {
+                    text: text[point..].to_string(),
+                    style,
+                };
+                (self.span_width_pt(&head, font_size) <= available_pt).then_some((head, tail))
+            })
+    }
+
+    fn write_span_line(&mut self, line: &[Span], font_size: f32, indent_mm: f32) {
+        let layer = self.layer();
+        let start_x = self.frame_left_mm() + indent_mm;
+        let mut extents: Vec<(f32, f32)> = Vec::with_capacity(line.len());
+        let mut x = start_x;
+        for span in line {
+            let width = Self::pt_to_mm(self.span_width_pt(span, font_size));
+            extents.push((x, x + width));
+            x += width + self.word_spacing_mm * span.text.matches(' ').count() as f32;
+        }
+
+        let code_extents: Vec<(f32, f32)> = line
+            .iter()
+            .zip(&extents)
+            .filter(|(span, _)| span.style.code && !span.text.trim().is_empty())
+            .map(|(_, extent)| *extent)
+            .collect();
+        if !code_extents.is_empty() {
+            let padding = 0.4f32;
+            let bottom = self.cursor_y - Self::pt_to_mm(font_size * 0.25);
+            let top = self.cursor_y + Self::pt_to_mm(font_size * 0.8);
+            layer.save_graphics_state();
+            layer.set_fill_color(style::color(self.options.style.code_background));
+            for (start, end) in code_extents {
+                layer.add_rect(
+                    Rect::new(Mm(start - padding), Mm(bottom), Mm(end + padding), Mm(top))
+                        .with_mode(PaintMode::Fill),
+                );
+            }
+            layer.restore_graphics_state();
+        }
+
+        layer.save_graphics_state();
+        layer.begin_text_section();
+        layer.set_text_cursor(Mm(start_x), Mm(self.cursor_y));
+        let mut script = Script::Normal;
+        let mut color = None;
+        let mut line_start = start_x;
+        for (span, (span_start, _)) in line.iter().zip(&extents) {
+            if self.word_spacing_mm > 0.0 {
+                // Moves relative to where the previous span started.
+                layer.set_text_cursor(Mm(span_start - line_start), Mm(0.0));
+                line_start = *span_start;
+            }
+            if span.style.script != script {
+                script = span.style.script;
+                layer.set_line_offset(font_size * script.rise());
+            }
+            let span_color = if span.style.code {
+                self.options.style.code_color
+            } else if span.style.link.is_some() {
+                self.options.style.link_color
+            } else {
+                self.ink
+            };
+            if color != Some(span_color) {
+                color = Some(span_color);
+                layer.set_fill_color(style::color(span_color));
+            }
+            if self.fonts.falls_back(span.style) {
+                for (fallback, run) in self.fonts.fallback_runs(&span.text) {
+                    layer.set_font(&fallback.font, font_size * script.scale());
+                    layer.write_text(run, &fallback.font);
+                }
+                continue;
+            }
+            let font = self.fonts.for_style(span.style);
+            layer.set_font(font, font_size * script.scale());
+            if span.style.symbol && self.fonts.embedded.is_some() {
+                layer.write_text(math::symbol_to_unicode(&span.text), font);
+            } else if self.fonts.spells_emoji(span.style) {
+                layer.write_text(emoji::shortcodes(&span.text), font);
+            } else {
+                layer.write_text(span.text.as_str(), font);
+            }
+        }
+        if script != Script::Normal {
+            layer.set_line_offset(0.0);
+        }
+        layer.end_text_section();
+        layer.restore_graphics_state();
+        if self.fonts.embedded.is_none() && line.iter().any(|span| span.style.symbol) {
+            self.uses_symbol_font = true;
+        }
+
+        let strikes: Vec<(f32, f32)> = line
+            .iter()
+            .zip(&extents)
+            .filter(|(span, _)| span.style.strikethrough)
+            .map(|(_, extent)| *extent)
+            .collect();
+        if !strikes.is_empty() {
+            let strike_y = self.cursor_y + Self::pt_to_mm(font_size * 0.3);
+            layer.save_graphics_state();
+            layer.set_outline_thickness(font_size * 0.06);
+            for (start, end) in strikes {
+                layer.add_line(horizontal_line(start, end, strike_y));
+            }
+            layer.restore_graphics_state();
+        }
+
+        self.link_annotations(line, &extents, font_size);
+    }
+
+    /// Covers each run of link spans with a URI annotation and, if enabled, an underline.
+    fn link_annotations(&mut self, line: &[Span], extents: &[(f32, f32)], font_size: f32) {
+        let mut runs: Vec<(usize, f32, f32)> = Vec::new();
+        for (span, (start, end)) in line.iter().zip(extents) {
+            let Some(link) = span.style.link else {
+                continue;
+            };
+            match runs.last_mut() {
+                Some((last, _, run_end)) if *last == link => *run_end = *end,
+                _ => runs.push((link, *start, *end)),
+            }
+        }
+        if runs.is_empty() {
+            return;
+        }
+
+        let layer = self.layer();
+        let [r, g, b] = self.options.style.link_color;
+        let bottom = self.cursor_y - Self::pt_to_mm(font_size * 0.25);
+        let top = self.cursor_y + Self::pt_to_mm(font_size * 0.8);
+        let underline_y = self.cursor_y - Self::pt_to_mm(font_size * 0.12);
+        for (link, start, end) in runs {
+            match &self.links[link] {
+                LinkTarget::Uri(uri) => {
+                    layer.add_link_annotation(LinkAnnotation::new(
+                        Rect::new(Mm(start), Mm(bottom), Mm(end), Mm(top)),
+                        Some(BorderArray::Solid([0.0, 0.0, 0.0])),
+                        Some(ColorArray::RGB([r, g, b])),
+                        Actions::uri(uri.clone()),
+                        Some(HighlightingMode::Invert),
+                    ));
+                }
+                LinkTarget::Internal(anchor) => {
+                    let anchor = anchor.clone();
+                    self.pending_links.push(PendingLink {
+                        page: self.page_number,
+                        rect_mm: [start, bottom, end, top],
+                        anchor,
+                    });
+                }
+            }
+            if self.options.style.underline_links {
+                layer.save_graphics_state();
+                layer.set_outline_color(Color::Rgb(Rgb::new(r, g, b, None)));
+                layer.set_outline_thickness(font_size * 0.05);
+                layer.add_line(horizontal_line(start, end, underline_y));
+                layer.restore_graphics_state();
+            }
+        }
+    }
+
+    /// Moves on to the next column or page unless a heading of `level` fits together with
+    /// the first lines below it.
+    fn keep_with_next(&mut self, level: u32) {
+        let height = self.line_height_mm(self.heading_font_size(level))
+            + Self::pt_to_mm(self.options.style.heading_spacing)
+            + self.line_height_mm(self.options.style.body_size) * KEEP_WITH_NEXT_LINES;
+        self.ensure_space(height);
+    }
+
+    fn write_span_lines(&mut self, lines: &[Vec<Span>], font_size: f32, indent_mm: f32) {
+        let line_height = self.line_height_mm(font_size);
+        for line in lines {
+            self.ensure_space(line_height);
+            self.write_span_line(line, font_size, indent_mm);
+            self.cursor_y -= line_height;
+        }
+    }
+
+    /// Wraps `spans` to the width left after `indent_mm` and places each line according to
+    /// `align`.
+    fn write_aligned(&mut self, spans: &[Span], font_size: f32, indent_mm: f32, align: TextAlign) {
+        let max_width_mm = self.max_text_width_mm(indent_mm);
+        let line_height = self.line_height_mm(font_size);
+        let lines = self.wrap_spans_with_breaks(spans, font_size, max_width_mm);
+        let count = lines.len();
+        for (index, (line, ends_paragraph)) in lines.into_iter().enumerate() {
+            let width: f32 = line
+                .iter()
+                .map(|span| Self::pt_to_mm(self.span_width_pt(span, font_size)))
+                .sum();
+            let offset = match align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => ((max_width_mm - width) / 2.0).max(0.0),
+                TextAlign::Right => (max_width_mm - width).max(0.0),
+            };
+            self.ensure_space(line_height * lines_kept_together(index, count) as f32);
+            let spaces: usize = line.iter().map(|span| span.text.matches(' ').count()).sum();
+            if align == TextAlign::Justify && !ends_paragraph && spaces > 0 {
+                // Each word becomes its own span so it can be placed on its own.
+                self.word_spacing_mm = ((max_width_mm - width) / spaces as f32).max(0.0);
+                self.write_span_line(&split_words(&line), font_size, indent_mm);
+                self.word_spacing_mm = 0.0;
+            } else {
+                self.write_span_line(&line, font_size, indent_mm + offset);
+            }
+            self.cursor_y -= line_height;
+        }
+    }
+
+    fn paragraph(&mut self, spans: &[Span]) {
+        let font_size = self.options.style.body_size;
+        self.cursor_y -= Self::pt_to_mm(self.options.style.paragraph_spacing_before);
+        self.begin_tag("P", None);
+        self.write_aligned(spans, font_size, 0.0, self.options.style.text_align);
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    /// Draws a paragraph of a plain block quote nested `depth` levels deep.
+    fn quote(&mut self, spans: &[Span], depth: usize) {
+        let font_size = self.options.style.quote_size;
+        let indent_mm = Self::pt_to_mm(self.options.style.quote_indent) * depth as f32;
+        let ink = std::mem::replace(&mut self.ink, self.options.style.quote_color);
+        self.begin_tag("BlockQuote", None);
+        self.write_aligned(spans, font_size, indent_mm, self.options.style.text_align);
+        self.end_tag();
+        self.ink = ink;
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    fn display_math(&mut self, spans: &[Span]) {
+        let font_size = self.options.style.math_size;
+        self.cursor_y -= Self::pt_to_mm(self.options.style.item_spacing);
+        self.begin_tag("Formula", None);
+        self.write_aligned(spans, font_size, 0.0, TextAlign::Center);
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    fn definition_term(&mut self, spans: &[Span]) {
+        let font_size = self.options.style.body_size;
+        let lines = self.wrap_spans(spans, font_size, self.max_text_width_mm(0.0));
+        self.begin_tag("P", None);
+        self.write_span_lines(&lines, font_size, 0.0);
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.item_spacing);
+    }
+
+    fn definition(&mut self, spans: &[Span]) {
+        let font_size = self.options.style.body_size;
+        let max_width_mm = self.max_text_width_mm(DEFINITION_INDENT_MM);
+        let lines = self.wrap_spans(spans, font_size, max_width_mm);
+        self.begin_tag("P", None);
+        self.write_span_lines(&lines, font_size, DEFINITION_INDENT_MM);
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    /// Draws a GitHub alert: an icon and colored title over the alert's paragraphs, with
+    /// a bar in the same color down the left edge of every page the alert spans.
+    fn alert(&mut self, kind: BlockQuoteKind, paragraphs: &[Vec<Span>]) {
+        let font_size = self.options.style.body_size;
+        let line_height = self.line_height_mm(font_size);
+        let (title, glyph, (r, g, b)) = match kind {
+            BlockQuoteKind::Note => ("Note", "i", (0.04, 0.41, 0.85)),
+            BlockQuoteKind::Tip => ("Tip", "*", (0.1, 0.5, 0.22)),
+            BlockQuoteKind::Important => ("Important", "!", (0.51, 0.31, 0.87)),
+            BlockQuoteKind::Warning => ("Warning", "!", (0.6, 0.4, 0.0)),
+            BlockQuoteKind::Caution => ("Caution", "×", (0.82, 0.14, 0.18)),
+        };
+        let color = Color::Rgb(Rgb::new(r, g, b, None));
+
+        self.ensure_space(line_height * 2.0);
+        self.begin_tag("BlockQuote", None);
+        let mut segment_top = self.cursor_y + Self::pt_to_mm(font_size);
+        let radius = Self::pt_to_mm(font_size * 0.42);
+        let icon_x = self.frame_left_mm() + ALERT_INDENT_MM + radius;
+        let icon_y = self.cursor_y + Self::pt_to_mm(font_size * 0.35);
+        let glyph_size = font_size * 0.75;
+        let glyph_width = Self::pt_to_mm(self.text_width_pt(glyph, true, glyph_size));
+        let layer = self.layer();
+        layer.save_graphics_state();
+        layer.set_fill_color(color.clone());
+        layer.add_polygon(Polygon {
+            rings: vec![printpdf::calculate_points_for_circle(
+                Mm(radius),
+                Mm(icon_x),
+                Mm(icon_y),
+            )],
+            mode: PaintMode::Fill,
+            winding_order: WindingOrder::NonZero,
+        });
+        layer.use_text(
+            title,
+            font_size,
+            Mm(icon_x + radius + 1.5),
+            Mm(self.cursor_y),
+            &self.fonts.bold,
+        );
+        layer.set_fill_color(Color::Greyscale(Greyscale::new(1.0, None)));
+        layer.use_text(
+            glyph,
+            glyph_size,
+            Mm(icon_x - glyph_width / 2.0),
+            Mm(icon_y - Self::pt_to_mm(glyph_size * 0.35)),
+            &self.fonts.bold,
+        );
+        layer.restore_graphics_state();
+        self.cursor_y -= line_height + Self::pt_to_mm(self.options.style.item_spacing);
+
+        let max_width_mm = self.max_text_width_mm(ALERT_INDENT_MM);
+        for paragraph in paragraphs {
+            for line in self.wrap_spans(paragraph, font_size, max_width_mm) {
+                if self.cursor_y - line_height < self.floor_mm() {
+                    self.alert_bar(&color, segment_top, self.floor_mm());
+                    self.next_frame();
+                    segment_top = self.cursor_y + Self::pt_to_mm(font_size);
+                }
+                self.write_span_line(&line, font_size, ALERT_INDENT_MM);
+                self.cursor_y -= line_height;
+            }
+            self.cursor_y -= Self::pt_to_mm(self.options.style.item_spacing);
+        }
+        let bottom = self.cursor_y + line_height - Self::pt_to_mm(font_size * 0.3);
+        self.alert_bar(&color, segment_top, bottom);
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    fn alert_bar(&self, color: &Color, top_mm: f32, bottom_mm: f32) {
+        let layer = self.layer();
+        let left = self.frame_left_mm();
+        layer.save_graphics_state();
+        layer.set_fill_color(color.clone());
+        layer.add_rect(
+            Rect::new(Mm(left), Mm(bottom_mm), Mm(left + 1.2), Mm(top_mm))
+                .with_mode(PaintMode::Fill),
+        );
+        layer.restore_graphics_state();
+    }
+
+    /// Lists headings with links to them; page numbers are filled in after layout.
+    fn table_of_contents(&mut self, entries: &[TocEntry]) {
+        let font_size = self.options.style.body_size;
+        let line_height = self.line_height_mm(font_size);
+        let number_width = Self::pt_to_mm(self.text_width_pt("0000", false, font_size));
+        let min_level = entries.iter().map(|entry| entry.level).min().unwrap_or(1);
+
+        self.begin_tag("TOC", None);
+        for entry in entries {
+            let indent_mm = (entry.level - min_level) as f32 * TOC_INDENT_MM;
+            let max_width_mm = self.max_text_width_mm(indent_mm) - number_width;
+            let style = SpanStyle {
+                bold: entry.level == min_level,
+                ..SpanStyle::default()
+            };
+            let mut spans = Vec::new();
+            push_text(&mut spans, &entry.text, style);
+            let lines = self.wrap_spans(&spans, font_size, max_width_mm);
+            self.begin_tag("TOCI", None);
+            for (index, line) in lines.iter().enumerate() {
+                self.ensure_space(line_height);
+                let right_mm = self.frame_left_mm() + self.frame_width_mm();
+                self.write_span_line(line, font_size, indent_mm);
+                if index + 1 == lines.len() {
+                    let text_width: f32 = line
+                        .iter()
+                        .map(|span| self.span_width_pt(span, font_size))
+                        .sum();
+                    self.page_references.push(PageReference {
+                        page: self.current_page,
+                        layer: self.current_layer,
+                        right_mm,
+                        y_mm: self.cursor_y,
+                        font_size,
+                        leader_mm: self.frame_left_mm() + indent_mm + Self::pt_to_mm(text_width),
+                        anchor: entry.anchor.clone(),
+                    });
+                }
+                self.pending_links.push(PendingLink {
+                    page: self.page_number,
+                    rect_mm: [
+                        self.frame_left_mm() + indent_mm,
+                        self.cursor_y - Self::pt_to_mm(font_size * 0.25),
+                        right_mm,
+                        self.cursor_y + Self::pt_to_mm(font_size * 0.8),
+                    ],
+                    anchor: entry.anchor.clone(),
+                });
+                self.cursor_y -= line_height;
+            }
+            self.end_tag();
+        }
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    fn heading_font_size(&self, level: u32) -> f32 {
+        self.options.style.heading_size(level)
+    }
+
+    fn heading(&mut self, level: u32, text: &str) {
+        let text = if self.options.number_headings {
+            let number = heading_number(&mut self.heading_counters, level);
+            format!("{number} {text}")
+        } else {
+            text.to_string()
+        };
+        if level <= self.options.outline_depth {
+            self.add_outline_item(level, &text, self.heading_font_size(level));
+        }
+        if level <= 2 {
+            self.begin_section(&text);
+        }
+        self.heading_text(level, &text);
+    }
+
+    /// Makes `title` the running section from the current page on. The first section
+    /// started on a page names it.
+    fn begin_section(&mut self, title: &str) {
+        self.section = title.to_string();
+        let Some(page) = self.pages.last_mut() else {
+            return;
+        };
+        if !page.sections.iter().any(|section| section == title) {
+            page.sections.push(title.to_string());
+        }
+        if !page.starts_section {
+            page.section = title.to_string();
+            page.starts_section = true;
+        }
+    }
+
+    /// Zero-based indices of the pages `page_range` and `sections` leave out.
+    fn removed_pages(&self) -> Result<Vec<usize>, String> {
+        let ranges = match &self.options.page_range {
+            Some(range) => pagerange::parse(range)?,
+            None => Vec::new(),
+        };
+        let wanted: Vec<String> = self
+            .options
+            .sections
+            .iter()
+            .map(|section| section.trim().to_lowercase())
+            .filter(|section| !section.is_empty())
+            .collect();
+        let removed: Vec<usize> = (0..self.pages.len())
+            .filter(|&index| {
+                let in_range =
+                    ranges.is_empty() || ranges.iter().any(|range| range.contains(&(index + 1)));
+                let in_sections = wanted.is_empty()
+                    || self.pages[index]
+                        .sections
+                        .iter()
+                        .any(|section| wanted.contains(&section.trim().to_lowercase()));
+                !(in_range && in_sections)
+            })
+            .collect();
+        if removed.len() == self.pages.len() {
+            return Err(format!(
+                "None of the {} pages are in the chosen page range and sections",
+                self.pages.len()
+            ));
+        }
+        Ok(removed)
+    }
+
+    /// Bookmarks the line about to be drawn at the cursor. Files are level `0`.
+    fn add_outline_item(&mut self, level: u32, title: &str, font_size: f32) {
+        self.ensure_space(self.line_height_mm(font_size));
+        self.outline.push(postprocess::OutlineItem {
+            level: level + self.outline_offset,
+            title: title.to_string(),
+            page: self.page_number,
+            top: Self::mm_to_pt(self.cursor_y + Self::pt_to_mm(font_size)),
+        });
+    }
+
+    /// Draws a heading without numbering, as used for file titles.
+    fn heading_text(&mut self, level: u32, text: &str) {
+        let font_size = self.heading_font_size(level);
+        let mut spans = Vec::new();
+        push_text(
+            &mut spans,
+            text,
+            SpanStyle {
+                bold: true,
+                ..SpanStyle::default()
+            },
+        );
+        let ink = std::mem::replace(&mut self.ink, self.options.style.heading_color);
+        self.begin_tag(tagging::heading_role(level), None);
+        self.write_aligned(&spans, font_size, 0.0, self.options.style.heading_align);
+        self.end_tag();
+        self.ink = ink;
+        self.cursor_y -= Self::pt_to_mm(self.options.style.heading_spacing);
+    }
+
+    /// Fills the current page with the cover: the logo, title and subtitle centred a third
+    /// of the way down, then the author and date.
+    fn cover_page(&mut self, cover: &CoverPage) -> Result<(), String> {
+        let margins = self.options.margins;
+        let text_height_mm = self.page_height_mm - margins.top - margins.bottom;
+        self.cursor_y = self.page_height_mm - margins.top - text_height_mm / 3.0;
+
+        if let Some(logo) = &cover.logo {
+            let image = self
+                .pictures
+                .load(Path::new(logo))
+                .map_err(|err| format!("Failed to open cover logo {logo}: {err}"))?;
+            let dpi = 96.0f32;
+            let width_mm = image.width_px as f32 * 25.4 / dpi;
+            let height_mm = image.height_px as f32 * 25.4 / dpi;
+            let scale = (self.max_text_width_mm(0.0) / width_mm)
+                .min(COVER_LOGO_HEIGHT_MM / height_mm)
+                .min(1.0);
+            let x = self.frame_left_mm() + (self.max_text_width_mm(0.0) - width_mm * scale) / 2.0;
+            let layer = self.layer();
+            self.begin_artifact(&layer);
+            self.place_image(self.pages.len() - 1, &image, x, self.cursor_y, scale, dpi)?;
+            self.end_artifact(&layer);
+            self.cursor_y -= Self::pt_to_mm(self.options.style.heading_spacing) * 2.0;
+        }
+
+        let style = &self.options.style;
+        let lines = [
+            (cover.title.as_deref(), style.heading_size(1), true, style.heading_color),
+            (cover.subtitle.as_deref(), style.heading_size(3), false, style.heading_color),
+            (cover.author.as_deref(), style.body_size, false, style.text_color),
+            (cover.date.as_deref(), style.body_size, false, style.text_color),
+        ];
+        let spacing = Self::pt_to_mm(style.heading_spacing);
+        for (index, (text, font_size, bold, color)) in lines.into_iter().enumerate() {
+            let Some(text) = text else {
+                continue;
+            };
+            if index == 2 {
+                self.cursor_y -= spacing * 2.0;
+            }
+            let mut spans = Vec::new();
+            push_text(
+                &mut spans,
+                text,
+                SpanStyle {
+                    bold,
+                    ..SpanStyle::default()
+                },
+            );
+            let ink = std::mem::replace(&mut self.ink, color);
+            self.begin_tag("P", None);
+            self.write_aligned(&spans, font_size, 0.0, TextAlign::Center);
+            self.end_tag();
+            self.ink = ink;
+            self.cursor_y -= spacing;
+        }
+        Ok(())
+    }
+
+    /// Draws a file's front matter title with its author and date underneath.
+    fn title(&mut self, title: &str, front_matter: &frontmatter::FrontMatter) {
+        self.heading_text(1, title);
+
+        let byline = [front_matter.author.as_deref(), front_matter.date.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · ");
+        if !byline.is_empty() {
+            self.cursor_y += Self::pt_to_mm(4.0);
+            let mut spans = Vec::new();
+            push_text(
+                &mut spans,
+                &byline,
+                SpanStyle {
+                    italic: true,
+                    ..SpanStyle::default()
+                },
+            );
+            self.paragraph(&spans);
+        }
+    }
+
+    fn list(&mut self, items: &[ListItem], start: Option<u64>) {
+        let font_size = self.options.style.body_size;
+        let bullet = self.options.style.bullet.clone();
+        let marker = |index: usize| match start {
+            Some(first) => format!("{}.", first + index as u64),
+            None => bullet.clone(),
+        };
+        let widest_marker = Self::pt_to_mm(self.text_width_pt(
+            &marker(items.len().saturating_sub(1)),
+            false,
+            font_size,
+        ));
+        let indent_mm = 6.0f32.max(widest_marker + 2.0);
+        self.begin_tag("L", None);
+        for (index, item) in items.iter().enumerate() {
+            let mut lines =
+                self.wrap_spans(&item.spans, font_size, self.max_text_width_mm(indent_mm));
+            // An empty item still gets its marker, on a line of its own.
+            if lines.is_empty() {
+                lines.push(Vec::new());
+            }
+            self.begin_tag("LI", None);
+            if let Some(first) = lines.first() {
+                self.ensure_space(self.line_height_mm(font_size));
+                self.begin_tag("Lbl", None);
+                match item.checked {
+                    Some(checked) => self.checkbox(checked, font_size),
+                    None => {
+                        let mut spans = Vec::new();
+                        push_text(&mut spans, &marker(index), SpanStyle::default());
+                        self.write_span_line(&spans, font_size, 0.0);
+                    }
+                }
+                self.end_tag();
+                self.begin_tag("LBody", None);
+                self.write_span_line(first, font_size, indent_mm);
+                self.cursor_y -= self.line_height_mm(font_size);
+                if lines.len() > 1 {
+                    self.write_span_lines(&lines[1..], font_size, indent_mm);
+                }
+                self.end_tag();
+            }
+            self.end_tag();
+            self.cursor_y -= Self::pt_to_mm(self.options.style.item_spacing);
+        }
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing - self.options.style.item_spacing);
+    }
+
+    fn checkbox(&self, checked: bool, font_size: f32) {
+        let size = Self::pt_to_mm(font_size * 0.75);
+        let x = self.frame_left_mm();
+        let y = self.cursor_y - Self::pt_to_mm(font_size * 0.05);
+        let layer = self.layer();
+        layer.save_graphics_state();
+        layer.set_outline_thickness(0.7);
+        layer.add_rect(
+            Rect::new(Mm(x), Mm(y), Mm(x + size), Mm(y + size)).with_mode(PaintMode::Stroke),
+        );
+        if checked {
+            layer.set_outline_thickness(1.2);
+            layer.add_line(Line {
+                points: vec![
+                    (Point::new(Mm(x + size * 0.2), Mm(y + size * 0.5)), false),
+                    (Point::new(Mm(x + size * 0.42), Mm(y + size * 0.2)), false),
+                    (Point::new(Mm(x + size * 0.82), Mm(y + size * 0.82)), false),
+                ],
+                is_closed: false,
+            });
+        }
+        layer.restore_graphics_state();
+    }
+
+    fn code_block(&mut self, text: &str, language: Option<&str>) {
+        let font_size = self.options.style.code_size;
+        let mut indent_mm = 4.0f32;
+        let line_count = text.lines().count();
+
+        self.begin_tag("Code", None);
+        if let (true, Some(language)) = (self.options.code_language_labels, language) {
+            let label_size = self.options.style.code_label_size;
+            self.ensure_space(self.line_height_mm(label_size) + self.line_height_mm(font_size));
+            let layer = self.layer();
+            layer.save_graphics_state();
+            layer.set_fill_color(style::color(self.options.style.code_label_color));
+            layer.use_text(
+                language,
+                label_size,
+                Mm(self.frame_left_mm() + indent_mm),
+                Mm(self.cursor_y),
+                &self.fonts.italic,
+            );
+            layer.restore_graphics_state();
+            self.cursor_y -= self.line_height_mm(label_size);
+        }
+
+        let gutter_mm = if self.options.code_line_numbers {
+            let digits = line_count.max(1).to_string().len();
+            Self::pt_to_mm(self.mono_text_width_pt(&"0".repeat(digits), font_size)) + 3.0
+        } else {
+            0.0
+        };
+        let number_right_mm = self.frame_left_mm() + indent_mm + gutter_mm - 3.0;
+        indent_mm += gutter_mm;
+        let max_width_mm = self.max_text_width_mm(indent_mm);
+        let max_chars = (Self::mm_to_pt(max_width_mm) / (font_size * 0.6)) as usize;
+
+        for (number, line) in text.lines().enumerate() {
+            let mut start = 0;
+            let chars: Vec<char> = line.chars().collect();
+            // Blank lines still take up a row, so numbering stays in step with the source.
+            loop {
+                let end = (start + max_chars).min(chars.len());
+                let slice: String = chars[start..end].iter().collect();
+                self.ensure_space(self.line_height_mm(font_size));
+                if self.options.code_line_numbers && start == 0 {
+                    let label = (number + 1).to_string();
+                    let width = Self::pt_to_mm(self.mono_text_width_pt(&label, font_size));
+                    let layer = self.layer();
+                    layer.save_graphics_state();
+                    layer.set_fill_color(style::color(self.options.style.line_number_color));
+                    layer.use_text(
+                        label,
+                        font_size,
+                        Mm(number_right_mm - width),
+                        Mm(self.cursor_y),
+                        &self.fonts.mono,
+                    );
+                    layer.restore_graphics_state();
+                }
+                let layer = self.layer();
+                layer.save_graphics_state();
+                layer.set_fill_color(style::color(self.options.style.code_color));
+                layer.use_text(
+                    &slice,
+                    font_size,
+                    Mm(self.frame_left_mm() + indent_mm),
+                    Mm(self.cursor_y),
+                    &self.fonts.mono,
+                );
+                layer.restore_graphics_state();
+                self.cursor_y -= self.line_height_mm(font_size);
+                start = end;
+                if start >= chars.len() {
+                    break;
+                }
+            }
+        }
+        self.end_tag();
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+    }
+
+    fn table(&mut self, header: &[String], rows: &[Vec<String>], alignments: &[Alignment]) {
+        let font_size = self.options.style.table_size;
+        let columns = header
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        if columns == 0 {
+            return;
+        }
+
+        let mut natural_widths = vec![0.0f32; columns];
+        for row in std::iter::once(header).chain(rows.iter().map(|row| row.as_slice())) {
+            for (index, cell) in row.iter().enumerate() {
+                let width = Self::pt_to_mm(self.text_width_pt(cell, true, font_size))
+                    + 2.0 * TABLE_CELL_PADDING_MM;
+                natural_widths[index] = natural_widths[index].max(width);
+            }
+        }
+        let turned = self.can_turn_page()
+            && natural_widths.iter().sum::<f32>() > self.max_text_width_mm(0.0);
+        if turned {
+            self.begin_landscape();
+        }
+        let widths = fit_column_widths(&natural_widths, self.max_text_width_mm(0.0));
+
+        self.begin_tag("Table", None);
+        let header_height = if header.is_empty() {
+            0.0
+        } else {
+            self.table_row_height(header, &widths, font_size, true)
+        };
+        if !header.is_empty() {
+            self.ensure_space(header_height);
+            self.table_row(header, &widths, alignments, font_size, true);
+        }
+
+        for row in rows {
+            let height = self.table_row_height(row, &widths, font_size, false);
+            let y_before = self.cursor_y;
+            self.ensure_space(height);
+            if self.cursor_y > y_before && !header.is_empty() {
+                self.ensure_space(header_height + height);
+                // Screen readers already have the header from the first page.
+                let layer = self.layer();
+                self.begin_artifact(&layer);
+                let tags = self.tags.take();
+                self.table_row(header, &widths, alignments, font_size, true);
+                self.tags = tags;
+                self.end_artifact(&layer);
+            }
+            self.table_row(row, &widths, alignments, font_size, false);
+        }
+        self.end_tag();
+
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing) + self.line_height_mm(self.options.style.body_size);
+        if turned {
+            self.end_landscape();
+        }
+    }
+
+    fn table_row_height(
+        &self,
+        cells: &[String],
+        widths: &[f32],
+        font_size: f32,
+        is_header: bool,
+    ) -> f32 {
+        let max_lines = widths
+            .iter()
+            .enumerate()
+            .map(|(index, width)| {
+                let text = cells.get(index).map(String::as_str).unwrap_or("");
+                self.table_cell_lines(text, *width, font_size, is_header).len()
+            })
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        max_lines as f32 * self.line_height_mm(font_size) + 2.0 * TABLE_CELL_PADDING_MM
+    }
+
+    fn table_row(
+        &mut self,
+        cells: &[String],
+        widths: &[f32],
+        alignments: &[Alignment],
+        font_size: f32,
+        is_header: bool,
+    ) {
+        let height = self.table_row_height(cells, widths, font_size, is_header);
+        let top = self.cursor_y;
+        let bottom = top - height;
+        let line_height = self.line_height_mm(font_size);
+
+        let layer = self.layer();
+        let mut x = self.frame_left_mm();
+        self.begin_tag("TR", None);
+        for (index, width) in widths.iter().enumerate() {
+            let mode = if is_header {
+                PaintMode::FillStroke
+            } else {
+                PaintMode::Stroke
+            };
+            self.begin_artifact(&layer);
+            layer.save_graphics_state();
+            layer.set_fill_color(style::color(self.options.style.table_header_background));
+            layer.set_outline_color(style::color(self.options.style.table_border_color));
+            layer.set_outline_thickness(0.5);
+            layer.add_rect(Rect::new(Mm(x), Mm(bottom), Mm(x + width), Mm(top)).with_mode(mode));
+            layer.restore_graphics_state();
+            self.end_artifact(&layer);
+
+            let text = cells.get(index).map(String::as_str).unwrap_or("");
+            let inner_width = width - 2.0 * TABLE_CELL_PADDING_MM;
+            let lines = self.table_cell_lines(text, *width, font_size, is_header);
+            self.cursor_y = top - TABLE_CELL_PADDING_MM - Self::pt_to_mm(font_size * 0.8);
+            self.begin_tag(if is_header { "TH" } else { "TD" }, None);
+            for line in &lines {
+                let line_width: f32 = line
+                    .iter()
+                    .map(|span| Self::pt_to_mm(self.span_width_pt(span, font_size)))
+                    .sum();
+                let offset = match alignments.get(index) {
+                    Some(Alignment::Center) => ((inner_width - line_width) / 2.0).max(0.0),
+                    Some(Alignment::Right) => (inner_width - line_width).max(0.0),
+                    _ => 0.0,
+                };
+                let indent_mm = x + TABLE_CELL_PADDING_MM + offset - self.frame_left_mm();
+                self.write_span_line(line, font_size, indent_mm);
+                self.cursor_y -= line_height;
+            }
+            self.end_tag();
+            x += width;
+        }
+        self.end_tag();
+
+        self.cursor_y = bottom;
+    }
+
+    fn table_cell_lines(
+        &self,
+        text: &str,
+        width: f32,
+        font_size: f32,
+        is_header: bool,
+    ) -> Vec<Vec<Span>> {
+        let mut spans = Vec::new();
+        push_text(
+            &mut spans,
+            text,
+            SpanStyle {
+                bold: is_header,
+                ..SpanStyle::default()
+            },
+        );
+        self.wrap_spans(&spans, font_size, width - 2.0 * TABLE_CELL_PADDING_MM)
+    }
+
+    /// Draws the image at `dest`, returning whether it is an animation of which only the
+    /// first frame is shown. `alt` describes the image to screen readers; the file name
+    /// stands in when it is empty.
+    fn image(
+        &mut self,
+        markdown_path: &Path,
+        dest: &str,
+        alt: &str,
+        attributes: &ImageAttributes,
+    ) -> Result<bool, String> {
+        if dest.starts_with("http://") || dest.starts_with("https://") {
+            return Ok(false);
+        }
+
+        let dest = if self.options.notion_export {
+            notion::decode(dest)
+        } else {
+            dest.to_string()
+        };
+        let mut image_path = if Path::new(&dest).is_absolute() {
+            PathBuf::from(&dest)
+        } else {
+            let base = markdown_path.parent().unwrap_or(Path::new("."));
+            base.join(&dest)
+        };
+        if self.options.notion_export && !image_path.exists() {
+            let base = normalize_path(markdown_path.parent().unwrap_or(Path::new(".")));
+            if let Some(found) = notion::find(&base.join(&dest), &self.image_files) {
+                image_path = found.clone();
+            }
+        }
+        if self.options.obsidian_vault && !image_path.exists() {
+            if let Some(found) = self.resolve_attachment(&notion::decode(&dest)) {
+                image_path = found;
+            }
+        }
+
+        if !image_path.exists() {
+            return Err(format!(
+                "Image not found: {}",
+                image_path.to_string_lossy()
+            ));
+        }
+
+        let image = self
+            .pictures
+            .load(&image_path)
+            .map_err(|err| format!("Failed to open image {}: {}", image_path.display(), err))?;
+        let alt = match alt.trim() {
+            "" => file_name(&image_path).to_string(),
+            alt => alt.to_string(),
+        };
+        let turned = attributes.width.is_none()
+            && attributes.height.is_none()
+            && self.shows_larger_turned(&image);
+        if turned {
+            self.begin_landscape();
+        }
+        let drawn = self.draw_image(&image, alt, attributes);
+        if turned {
+            self.end_landscape();
+        }
+        drawn?;
+        Ok(image.animated)
+    }
+
+    /// Whether `image` shows larger on a landscape page than on the current portrait one,
+    /// being too wide for the text area.
+    fn shows_larger_turned(&self, image: &images::Picture) -> bool {
+        if !self.can_turn_page() {
+            return false;
+        }
+        let width_mm = image.width_px as f32 * 25.4 / 96.0;
+        let height_mm = image.height_px as f32 * 25.4 / 96.0;
+        let margins = self.options.margins;
+        let scale = |area_width_mm: f32| {
+            (area_width_mm / width_mm)
+                .min(1.0)
+                .min(MAX_IMAGE_HEIGHT_MM / height_mm)
+        };
+        scale(self.page_height_mm - margins.left - margins.right)
+            > scale(self.page_width_mm - margins.left - margins.right)
+    }
+
+    /// Draws `image` at its natural size, shrunk to the text area, or at the size its
+    /// `attributes` ask for.
+    fn draw_image(
+        &mut self,
+        image: &images::Picture,
+        alt: String,
+        attributes: &ImageAttributes,
+    ) -> Result<(), String> {
+        let dpi = 96.0f32;
+        let width_mm = image.width_px as f32 * 25.4 / dpi;
+        let height_mm = image.height_px as f32 * 25.4 / dpi;
+
+        let area_width_mm = self.max_text_width_mm(0.0);
+        let area_height_mm =
+            self.page_height_mm - self.options.margins.top - self.options.margins.bottom;
+        let width_scale = (area_width_mm / width_mm).min(1.0);
+        let requested = attributes.scale(width_mm, height_mm, area_width_mm, area_height_mm);
+        let scale = match requested {
+            // A requested size may enlarge the image, but never past the text area.
+            Some(scale) => scale
+                .min(area_width_mm / width_mm)
+                .min(area_height_mm / height_mm),
+            None if self.options.split_tall_images
+                && height_mm * width_scale > MAX_IMAGE_HEIGHT_MM =>
+            {
+                self.begin_tag("Figure", Some(alt));
+                self.split_image(image, width_scale, dpi)?;
+                self.end_tag();
+                return Ok(());
+            }
+            // Shrink to the text width and the height limit, but never enlarge.
+            None => width_scale.min(MAX_IMAGE_HEIGHT_MM / height_mm),
+        };
+        let height_mm = height_mm * scale;
+
+        self.ensure_space(height_mm + Self::pt_to_mm(self.options.style.block_spacing));
+        let y = self.cursor_y - height_mm;
+        let spare_mm = area_width_mm - width_mm * scale;
+        let x = self.frame_left_mm()
+            + match attributes.align {
+                attributes::Align::Left => 0.0,
+                attributes::Align::Center => spare_mm / 2.0,
+                attributes::Align::Right => spare_mm,
+            };
+        self.begin_tag("Figure", Some(alt));
+        self.place_image(self.pages.len() - 1, image, x, y, scale, dpi)?;
+        self.end_tag();
+        self.cursor_y = y - Self::pt_to_mm(self.options.style.block_spacing);
+        Ok(())
+    }
+
+    /// Draws `image` in slices that each fill the rest of the column, continuing in the
+    /// following columns and pages until all of it is drawn.
+    fn split_image(&mut self, image: &images::Picture, scale: f32, dpi: f32) -> Result<(), String> {
+        let mm_per_px = 25.4 / dpi * scale;
+        let mut top_px = 0;
+        while top_px < image.height_px {
+            if self.cursor_y - self.floor_mm() < MIN_IMAGE_SLICE_MM {
+                self.next_frame();
+            }
+            let available_px = ((self.cursor_y - self.floor_mm()) / mm_per_px) as u32;
+            let rows = available_px.clamp(1, image.height_px - top_px);
+            let slice = image
+                .crop_rows(top_px, rows)
+                .map_err(|err| format!("Failed to split image: {err}"))?;
+            let y = self.cursor_y - rows as f32 * mm_per_px;
+            let x = self.frame_left_mm();
+            self.place_image(self.pages.len() - 1, &slice, x, y, scale, dpi)?;
+            self.cursor_y = y;
+            top_px += rows;
+        }
+        self.cursor_y -= Self::pt_to_mm(self.options.style.block_spacing);
+        Ok(())
+    }
+
+    /// Draws `image` on the page at `page_index` with its lower-left corner at `x_mm`,
+    /// `y_mm`.
+    fn place_image(
+        &mut self,
+        page_index: usize,
+        image: &images::Picture,
+        x_mm: f32,
+        y_mm: f32,
+        scale: f32,
+        dpi: f32,
+    ) -> Result<(), String> {
+        // The picture shows `dpi / scale` pixels per inch on the page.
+        let mut dpi = dpi;
+        let downsampled;
+        let image = match &self.options.image_quality {
+            Some(quality) if dpi / scale > quality.max_dpi => {
+                let factor = quality.max_dpi * scale / dpi;
+                downsampled = image.downsample(factor, quality.jpeg_quality)?;
+                dpi *= downsampled.width_px as f32 / image.width_px as f32;
+                &downsampled
+            }
+            _ => image,
+        };
+
+        let page = &mut self.pages[page_index];
+        let layer = self.doc.get_page(page.page).get_layer(page.layer);
+        let (width_px, height_px) = image.stored_size();
+        if let Some(alpha) = image.alpha() {
+            self.soft_masks.push(postprocess::SoftMask {
+                page: page_index,
+                xobject: format!("X{}", page.images),
+                width_px,
+                height_px,
+                alpha: alpha.to_vec(),
+            });
+        }
+        page.images += 1;
+
+        // Turn the picture upright around the origin, then move it into place.
+        let width_pt = width_px as f32 * 72.0 / dpi * scale;
+        let height_pt = height_px as f32 * 72.0 / dpi * scale;
+        let [a, b, c, d, e, f] = image.orientation_matrix(width_pt, height_pt);
+        layer.save_graphics_state();
+        layer.set_ctm(CurTransMat::Raw([
+            a,
+            b,
+            c,
+            d,
+            e + Self::mm_to_pt(x_mm),
+            f + Self::mm_to_pt(y_mm),
+        ]));
+        // PDF/A forbids image interpolation.
+        Image::from(image.xobject(!self.options.pdf_a)).add_to_layer(
+            layer.clone(),
+            ImageTransform {
+                scale_x: Some(scale),
+                scale_y: Some(scale),
+                dpi: Some(dpi),
+                ..Default::default()
+            },
+        );
+        layer.restore_graphics_state();
+        Ok(())
+    }
+}
+
+/// Splits an Obsidian note or attachment name into lowercased path components.
+fn name_components(name: &str) -> Vec<String> {
+    name.split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn lowercase_components(path: &Path) -> Vec<String> {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+        .collect()
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether the link destination `dest` starts with a URI scheme such as `https:`.
+fn has_scheme(dest: &str) -> bool {
+    dest.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    })
+}
+
+/// Classifies a link destination, resolving relative markdown targets against `markdown_path`.
+fn link_target(markdown_path: &Path, dest: &str) -> LinkTarget {
+    if has_scheme(dest) {
+        return LinkTarget::Uri(dest.to_string());
+    }
+
+    let (path_part, fragment) = match dest.split_once('#') {
+        Some((path_part, fragment)) => (path_part, Some(fragment)),
+        None => (dest, None),
+    };
+    let file = if path_part.is_empty() {
+        markdown_path.to_path_buf()
+    } else {
+        let candidate = markdown_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(path_part);
+        if !is_markdown(&candidate) && !is_html(&candidate) && !is_rst(&candidate) {
+            return LinkTarget::Uri(dest.to_string());
+        }
+        normalize_path(&candidate)
+    };
+
+    LinkTarget::Internal(Anchor {
+        file,
+        slug: fragment
+            .filter(|fragment| !fragment.is_empty())
+            .map(|fragment| fragment.to_lowercase()),
+    })
+}
+
+/// Counts a heading of `level` in `counters`, the level and count of each open heading,
+/// and formats its number. Headings are numbered by how deep they are nested rather than
+/// by level, so a `###` straight under a `#` is `1.1` rather than `1.0.1`, and documents
+/// that start at `##` still begin at `1`.
+fn heading_number(counters: &mut Vec<(u32, u32)>, level: u32) -> String {
+    // A heading closed here may have held the place the new one takes, as a `###` under
+    // a `#` holds the place of a following `##`, which carries on from its count.
+    let mut closed = None;
+    while counters.last().is_some_and(|(open, _)| *open > level) {
+        closed = counters.pop();
+    }
+    match counters.last_mut() {
+        Some((open, count)) if *open == level => *count += 1,
+        _ => counters.push((level, closed.map_or(1, |(_, count)| count + 1))),
+    }
+    counters
+        .iter()
+        .map(|(_, count)| count.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Returns the slug for a heading, suffixed with `-1`, `-2`, ... when the same slug
+/// already occurred in the file, as GitHub does.
+fn unique_slug(counts: &mut HashMap<String, usize>, text: &str) -> String {
+    let base = heading_slug(text);
+    let count = counts.entry(base.clone()).or_insert(0);
+    let slug = if *count == 0 {
+        base
+    } else {
+        format!("{}-{}", base, count)
+    };
+    *count += 1;
+    slug
+}
+
+/// Builds the anchor slug GitHub generates for a heading.
+fn heading_slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| {
+            if ch.is_alphanumeric() || ch == '-' || ch == '_' {
+                Some(ch)
+            } else if ch.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Splits spans after every space, so each holds at most one word.
+fn split_words(line: &[Span]) -> Vec<Span> {
+    let mut words = Vec::new();
+    for span in line {
+        for word in span.text.split_inclusive(' ') {
+            words.push(Span {
+                text: word.to_string(),
+                style: span.style,
+            });
+        }
+    }
+    words
+}
+
+/// Lines that must fit before line `index` of a `count`-line paragraph is drawn, so no
+/// break leaves a single line at the bottom of one page or the top of the next.
+fn lines_kept_together(index: usize, count: usize) -> usize {
+    if index == 0 && count <= 3 {
+        // Any break would strand a line on one side.
+        count
+    } else if index == 0 || count - index == 2 {
+        2
+    } else {
+        1
+    }
+}
+
+/// Whether a paragraph or HTML block is a page break directive: `\newpage`, `\pagebreak`
+/// or a comment such as `<!-- pagebreak -->`.
+fn is_page_break(text: &str) -> bool {
+    let text = text.trim();
+    match text.strip_prefix("<!--").and_then(|comment| comment.strip_suffix("-->")) {
+        Some(comment) => ["pagebreak", "page-break", "newpage"]
+            .iter()
+            .any(|name| comment.trim().eq_ignore_ascii_case(name)),
+        None => text == "\\newpage" || text == "\\pagebreak",
+    }
+}
+
+fn horizontal_line(start_mm: f32, end_mm: f32, y_mm: f32) -> Line {
+    Line {
+        points: vec![
+            (Point::new(Mm(start_mm), Mm(y_mm)), false),
+            (Point::new(Mm(end_mm), Mm(y_mm)), false),
+        ],
+        is_closed: false,
+    }
+}
+
+/// Shrinks the widest columns first so narrow columns keep their natural width.
+fn fit_column_widths(natural: &[f32], available: f32) -> Vec<f32> {
+    let total: f32 = natural.iter().sum();
+    if total <= available {
+        return natural.to_vec();
+    }
+
+    let mut sorted = natural.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let mut remaining = available;
+    let mut cap = available / natural.len() as f32;
+    for (index, width) in sorted.iter().enumerate() {
+        let share = remaining / (sorted.len() - index) as f32;
+        if *width > share {
+            cap = share;
+            break;
+        }
+        remaining -= width;
+    }
+
+    natural.iter().map(|width| width.min(cap)).collect()
+}
+
+/// The markdown of the source file at `path` as it is rendered, with the file's bytes, or
+/// `None` for a draft `options` leave out.
+fn load_source(
+    path: &Path,
+    options: &RenderOptions,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .map_err(|err| err.to_string())?
+        .read_to_end(&mut bytes)
+        .map_err(|err| err.to_string())?;
+    prepare_source(path, bytes, options)
+}
+
+/// The markdown of the file `path` holding `bytes`, as `load_source` gives it.
+fn prepare_source(
+    path: &Path,
+    bytes: Vec<u8>,
+    options: &RenderOptions,
+) -> Result<Option<(String, Vec<u8>)>, String> {
+    let mut contents = source_markdown(path, encoding::decode(&bytes));
+    if let Some(site) = &options.site_content {
+        if site.skip_drafts && split_source(path, &contents).0.draft {
+            return Ok(None);
+        }
+        if !is_text(path) {
+            contents = site::replace(&contents, site.shortcodes);
+        }
+    }
+    Ok(Some((contents, bytes)))
+}
+
+/// Writes the markdown `files` in order as one plain-text file at `output_path`, each under
+/// its title, chapter title or name. `options` say which files are left out and how file
+/// names are shown.
+pub fn render_plain_text(
+    files: &[String],
+    chapters: &[book::Chapter],
+    output_path: &Path,
+    options: &RenderOptions,
+) -> Result<(), String> {
+    let chapters: HashMap<PathBuf, &book::Chapter> = chapters
+        .iter()
+        .map(|chapter| (normalize_path(Path::new(&chapter.path)), chapter))
+        .collect();
+    let mut parts = Vec::with_capacity(files.len());
+    for file in files {
+        let path = Path::new(file);
+        let Some((contents, _)) = load_source(path, options)? else {
+            continue;
+        };
+        let (front_matter, body) = page_source(path, &contents, options);
+        let chapter = chapters.get(&normalize_path(path));
+        let mut part = String::new();
+        for section in chapter.iter().flat_map(|chapter| &chapter.sections) {
+            part.push_str(&plaintext::heading(section, 1));
+            part.push_str("\n\n");
+        }
+        let title = front_matter
+            .title
+            .clone()
+            .or(chapter.map(|chapter| chapter.title.clone()))
+            .unwrap_or_else(|| display_name(path, options.notion_export));
+        part.push_str(&plaintext::heading(&title, 1));
+        part.push_str("\n\n");
+        let byline = [front_matter.author, front_matter.date]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · ");
+        if !byline.is_empty() {
+            part.push_str(&byline);
+            part.push_str("\n\n");
+        }
+        if is_text(path) {
+            for line in contents.lines() {
+                part.push_str(format!("    {line}").trim_end());
+                part.push('\n');
+            }
+        } else {
+            part.push_str(&plaintext::to_text(body));
+        }
+        parts.push(part.trim_end().to_string());
+    }
+    if parts.is_empty() {
+        return Err("Every markdown file is a draft".to_string());
+    }
+    let mut text = parts.join("\n\n\n");
+    text.push('\n');
+    fs::write(output_path, text).map_err(|err| err.to_string())
+}
+
+/// Renders `document` into one PDF at `output_path`, returning the files written and how,
+/// as `existing_output` says for files already there.
+pub fn render(
+    document: &Document,
+    output_path: &Path,
+    options: RenderOptions,
+) -> Result<Vec<(PathBuf, OutputWrite)>, String> {
+    render_with_progress(document, output_path, options, |_| {})
+}
+
+/// Renders `document` like `render`, passing `progress` how far it has got as it goes.
+pub fn render_with_progress(
+    document: &Document,
+    output_path: &Path,
+    options: RenderOptions,
+    progress: impl Fn(Progress),
+) -> Result<Vec<(PathBuf, OutputWrite)>, String> {
+    if document.markdown_files.is_empty() {
+        return Err("No markdown files found".to_string());
+    }
+    render_pdf(
+        &document.markdown_files,
+        &document.chapters,
+        &document.image_files,
+        output_path,
+        options,
+        &progress,
+        &mut load_source,
+    )
+}
+
+/// Page `page` of `document`, numbered from 1, as it will be exported with `options`,
+/// rasterized to PNG. The preview leaves out encryption and splitting, so it can be shown.
+pub fn render_preview(
+    document: &Document,
+    page: usize,
+    options: RenderOptions,
+) -> Result<preview::Preview, String> {
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let path = dir.path().join("preview.pdf");
+    render(document, &path, preview::previewable(options))?;
+    let bytes = fs::read(&path).map_err(|err| err.to_string())?;
+    preview::rasterize(&bytes, page)
+}
+
+/// Renders the markdown `files` in order into one PDF at `output_path`. `chapters` name and
+/// nest the files of mdBook books and `image_files` are searched for `![[image]]` embeds.
+/// Returns the files written and how, several when `split` divides the PDF into parts.
+pub fn render_markdown_pdf(
+    files: &[String],
+    chapters: &[book::Chapter],
+    image_files: &[String],
+    output_path: &Path,
+    options: RenderOptions,
+) -> Result<Vec<(PathBuf, OutputWrite)>, String> {
+    render_pdf(
+        files,
+        chapters,
+        image_files,
+        output_path,
+        options,
+        &|_| {},
+        &mut load_source,
+    )
+}
+
+/// Type of the functions `render_pdf` reads each source file with, such as `load_source`.
+type ReadSource<'a> =
+    dyn FnMut(&Path, &RenderOptions) -> Result<Option<(String, Vec<u8>)>, String> + 'a;
+
+fn render_pdf(
+    files: &[String],
+    chapters: &[book::Chapter],
+    image_files: &[String],
+    output_path: &Path,
+    options: RenderOptions,
+    progress: &dyn Fn(Progress),
+    read_source: &mut ReadSource,
+) -> Result<Vec<(PathBuf, OutputWrite)>, String> {
+    let split = options.split.clone();
+    let existing_output = options.existing_output;
+    let LaidOut {
+        mut bytes,
+        mut edits,
+        removed,
+        page_count,
+        file_count,
+    } = lay_out(files, chapters, image_files, options, progress, read_source, None)?;
+    progress(Progress::new(RenderStage::Writing, file_count, file_count));
+    let parts = match &split {
+        Some(split) => split::parts(&bytes, split, &removed)?,
+        None => Vec::new(),
+    };
+    if parts.len() <= 1 {
+        edits.remove_pages(removed);
+        if !edits.is_empty() {
+            bytes = postprocess::apply(&bytes, &edits)?;
+        }
+        let (path, written) = free_output_path(output_path.to_path_buf(), existing_output)?;
+        fs::write(&path, bytes).map_err(|err| err.to_string())?;
+        return Ok(vec![(path, written)]);
+    }
+
+    let paths = free_part_paths(output_path, parts.len(), existing_output)?;
+    for (number, (part, (path, _))) in parts.iter().zip(&paths).enumerate() {
+        let mut part_edits = edits.clone();
+        part_edits.remove_pages((0..page_count).filter(|page| !part.contains(page)).collect());
+        if number > 0 {
+            part_edits.pages_before.clear();
+        }
+        if number + 1 < parts.len() {
+            part_edits.pages_after.clear();
+        }
+        let bytes = postprocess::apply(&bytes, &part_edits)?;
+        fs::write(path, bytes).map_err(|err| err.to_string())?;
+    }
+    Ok(paths)
+}
+
+/// A document laid out and saved, with the edits left to make to it.
+struct LaidOut {
+    bytes: Vec<u8>,
+    edits: postprocess::Edits,
+    /// Pages `page_range` and `sections` leave out.
+    removed: Vec<usize>,
+    page_count: usize,
+    /// Files laid out, drafts left out.
+    file_count: usize,
+}
+
+/// Lays out the markdown `files` for `render_pdf`. With a `layout` from an earlier run,
+/// layout resumes at the last checkpoint before the first file that changed, when there
+/// is one, and the pages before it are taken from the earlier PDF; `layout` is then
+/// updated to resume from next time.
+fn lay_out(
+    files: &[String],
+    chapters: &[book::Chapter],
+    image_files: &[String],
+    mut options: RenderOptions,
+    progress: &dyn Fn(Progress),
+    read_source: &mut ReadSource,
+    mut layout: Option<&mut Layout>,
+) -> Result<LaidOut, String> {
+    let mut sources = Vec::with_capacity(files.len());
+    let mut attachments = Vec::new();
+    let attachment_names = relative_names(files);
+    for (index, (file, name)) in files.iter().zip(attachment_names).enumerate() {
+        progress(Progress::new(RenderStage::Reading, index, files.len()));
+        let path = PathBuf::from(file);
+        let Some((contents, bytes)) = read_source(&path, &options)? else {
+            continue;
+        };
+        let mime_type = source_mime_type(&path);
+        sources.push((path, contents));
+        if options.attach_sources {
+            attachments.push(postprocess::Attachment {
+                name,
+                mime_type: mime_type.to_string(),
+                contents: bytes,
+            });
+        }
+    }
+
+    if sources.is_empty() {
+        return Err("Every markdown file is a draft".to_string());
+    }
+    if options.obsidian_vault && options.attachments_folder.is_none() {
+        if let Some(vault) = files.first().and_then(|file| obsidian::vault_root(Path::new(file))) {
+            options.attachments_folder = obsidian::attachments_folder(&vault)?;
+        }
+    }
+    if let Some(theme) = options.theme.take() {
+        options.style = style::Style::load(Path::new(&theme))?;
+    }
+    if let Some(stylesheet) = options.stylesheet.take() {
+        let stylesheet = fs::read_to_string(stylesheet).map_err(|err| err.to_string())?;
+        css::apply(&stylesheet, &mut options.style);
+    }
+    // Read ahead of rendering, so a missing or unreadable PDF fails early.
+    let load_pdfs = |paths: &[String]| -> Result<Vec<_>, String> {
+        paths.iter().map(|path| merge::load(Path::new(path))).collect()
+    };
+    let pages_before = load_pdfs(&options.pdfs_before)?;
+    let pages_after = load_pdfs(&options.pdfs_after)?;
+    if let Some(range) = &options.page_range {
+        pagerange::parse(range)?;
+    }
+    let book_chapters: HashMap<PathBuf, &book::Chapter> = chapters
+        .iter()
+        .map(|chapter| (normalize_path(Path::new(&chapter.path)), chapter))
+        .collect();
+    // Shortcodes are expanded first, so the emoji they stand for find fonts too.
+    let mut text: String = sources
+        .iter()
+        .map(|(_, contents)| emoji::expand_shortcodes(contents))
+        .collect();
+    // The watermark is drawn on every page, so its characters need fonts as much.
+    if let Some(watermark) = options.watermark.as_ref().and_then(|mark| mark.text.as_deref()) {
+        text.push_str(watermark);
+    }
+    let fallback_fonts = fallback::font_paths(&text, &options.fallback_fonts);
+    let document = document_front_matter(&sources);
+    let date = document
+        .date
+        .clone()
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let drawn_with = (fallback_fonts.clone(), document.clone(), date.clone());
+    let resume_point = layout
+        .as_deref()
+        .and_then(|layout| layout.resume_point(&sources, &drawn_with));
+    let mut renderer = Renderer::new(options, &fallback_fonts)?;
+    renderer.markdown_files = files
+        .iter()
+        .map(|file| normalize_path(Path::new(file)))
+        .collect();
+    renderer.image_files = image_files
+        .iter()
+        .map(|file| normalize_path(Path::new(file)))
+        .collect();
+    let first_file = match &resume_point {
+        Some(checkpoint) => {
+            renderer.resume(checkpoint);
+            checkpoint.file
+        }
+        None => 0,
+    };
+    let mut checkpoints: Vec<Checkpoint> = layout
+        .as_deref()
+        .into_iter()
+        .flat_map(|layout| &layout.checkpoints)
+        .take_while(|checkpoint| checkpoint.file < first_file)
+        .cloned()
+        .collect();
+
+    // A resumed layout keeps the cover and contents pages of the earlier one.
+    if let Some(cover) = renderer.options.cover_page.clone().filter(|_| first_file == 0) {
+        let cover = CoverPage {
+            title: cover.title.or_else(|| document.title.clone()),
+            subtitle: cover.subtitle.or_else(|| document.subtitle.clone()),
+            author: cover.author.or_else(|| document.author.clone()),
+            date: cover.date.or_else(|| Some(date.clone())),
+            logo: cover.logo.or_else(|| document.logo.clone()),
+        };
+        renderer.cover_page(&cover)?;
+        renderer.add_page();
+    }
+
+    if renderer.options.table_of_contents && first_file == 0 {
+        renderer.heading_text(1, "Contents");
+        let entries = document_toc_entries(&sources, &book_chapters, &renderer.options);
+        renderer.table_of_contents(&entries);
+        renderer.add_page();
+    }
+
+    let hyphenation = if renderer.options.hyphenation {
+        let code = renderer.options.language.as_deref().unwrap_or("en");
+        let lang = hyphenation::language(code)
+            .ok_or_else(|| format!("Hyphenation is not available for language {code}"))?;
+        Some(lang)
+    } else {
+        None
+    };
+
+    for (index, (path, contents)) in sources.iter().enumerate().skip(first_file) {
+        progress(Progress::new(RenderStage::Rendering, index, sources.len()));
+        let (front_matter, body) = page_source(path, contents, &renderer.options);
+        renderer.hyphenation = hyphenation.map(|lang| {
+            front_matter
+                .language
+                .as_deref()
+                .and_then(hyphenation::language)
+                .unwrap_or(lang)
+        });
+
+        if renderer.options.start_on_odd_page == Some(OddPageStart::Files) {
+            renderer.odd_page_break();
+        } else if renderer.options.page_break_between_files {
+            renderer.page_break();
+        }
+        if layout.is_some() {
+            checkpoints.extend(renderer.checkpoint(index));
+        }
+        renderer.begin_file(path);
+        let file_name = display_name(path, renderer.options.notion_export);
+        let file_name = file_name.as_str();
+        let chapter = book_chapters.get(&normalize_path(path));
+        if let Some(chapter) = chapter {
+            let first = chapter.depth.saturating_sub(chapter.sections.len() as u32);
+            for (index, section) in chapter.sections.iter().enumerate() {
+                renderer.outline_offset = first + index as u32;
+                if renderer.options.outline_depth > 0 {
+                    let font_size = renderer.heading_font_size(1);
+                    renderer.add_outline_item(0, section, font_size);
+                }
+                renderer.heading_text(1, section);
+            }
+        }
+        renderer.outline_offset = chapter.map_or(0, |chapter| chapter.depth);
+        let title = front_matter
+            .title
+            .as_deref()
+            .or(chapter.map(|chapter| chapter.title.as_str()));
+        if renderer.options.outline_depth > 0 {
+            let font_size = renderer.heading_font_size(if title.is_some() { 1 } else { 2 });
+            renderer.add_outline_item(0, title.unwrap_or(file_name), font_size);
+        }
+        match title {
+            Some(title) => renderer.title(title, &front_matter),
+            None if is_csv(path) => renderer.heading_text(2, file_name),
+            None => renderer.heading_text(2, &format!("File: {}", file_name)),
+        }
+
+        if is_text(path) {
+            renderer.code_block(contents, None);
+        } else {
+            render_markdown_content(body, path, &mut renderer)?;
+        }
+    }
+
+    let total_pages = renderer.first_page + renderer.pages.len();
+    if let (Some(_), Some(layout)) = (&resume_point, layout.as_deref_mut()) {
+        if renderer.counts_pages() && total_pages != layout.page_count {
+            // The pages kept show the old page count, so they are laid out again too.
+            layout.checkpoints.clear();
+            let options = renderer.options;
+            let layout = Some(layout);
+            return lay_out(files, chapters, image_files, options, progress, read_source, layout);
+        }
+    }
+
+    let settings = renderer.options.metadata.clone();
+    let metadata = postprocess::Metadata {
+        title: settings
+            .title
+            .or(document.title)
+            .or_else(|| {
+                let (path, _) = sources.first()?;
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| DOCUMENT_NAME.to_string()),
+        author: settings.author.or(document.author),
+        subject: settings.subject.or(document.subject),
+        keywords: if settings.keywords.is_empty() {
+            document.keywords
+        } else {
+            settings.keywords
+        },
+        creator: settings.creator.unwrap_or_else(|| CREATOR.to_string()),
+    };
+
+    progress(Progress::new(RenderStage::Finishing, sources.len(), sources.len()));
+    renderer.resolve_page_references();
+    renderer.stamp_page_templates(&metadata.title, &date);
+    renderer.stamp_watermark()?;
+    let mut edits = renderer.post_process_edits();
+    let language = document.language.or_else(|| renderer.options.language.clone());
+    edits.language = match language {
+        None if renderer.options.tagged => Some("en".to_string()),
+        language => language,
+    };
+    edits.metadata = Some(metadata);
+    edits.attachments = attachments;
+    edits.pages_before = match (&layout, &resume_point) {
+        (Some(layout), Some(checkpoint)) => {
+            // The earlier PDF already starts with the pages of `pdfs_before`.
+            let leading: usize = pages_before.iter().map(|doc| doc.get_pages().len()).sum();
+            vec![layout.leading_pages(leading + checkpoint.page)?]
+        }
+        _ => pages_before,
+    };
+    edits.pages_after = pages_after;
+    if let Some(layout) = layout {
+        layout.checkpoints = if renderer.can_resume() {
+            checkpoints
+        } else {
+            Vec::new()
+        };
+        layout.sources = sources.clone();
+        layout.drawn_with = Some(drawn_with);
+        layout.page_count = total_pages;
+    }
+    let removed = renderer.removed_pages()?;
+    let page_count = renderer.pages.len();
+    let bytes = renderer.doc.save_to_bytes().map_err(|err| err.to_string())?;
+    Ok(LaidOut {
+        bytes,
+        edits,
+        removed,
+        page_count,
+        file_count: sources.len(),
+    })
+}
+
+/// Lays out the markdown `files` like `render_pdf` and returns the PDF rather than writing
+/// it, resuming from `layout`, the live preview's last layout, where it can.
+pub(crate) fn render_live(
+    files: &[String],
+    chapters: &[book::Chapter],
+    image_files: &[String],
+    options: RenderOptions,
+    read_source: &mut ReadSource,
+    layout: &mut Layout,
+) -> Result<Vec<u8>, String> {
+    let LaidOut {
+        mut bytes,
+        mut edits,
+        removed,
+        ..
+    } = lay_out(files, chapters, image_files, options, &|_| {}, read_source, Some(&mut *layout))?;
+    edits.remove_pages(removed);
+    if !edits.is_empty() {
+        bytes = postprocess::apply(&bytes, &edits)?;
+    }
+    layout.bytes = bytes.clone();
+    Ok(bytes)
+}
+
+/// The path of part `number` of the PDF at `path`: `name-1.pdf` and so on.
+fn part_path(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{number}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{number}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Paths of `files` relative to the deepest folder they all share, with `/` between
+/// folders.
+fn relative_names(files: &[String]) -> Vec<String> {
+    let paths: Vec<PathBuf> = files.iter().map(|file| normalize_path(Path::new(file))).collect();
+    let mut common = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    while !paths.iter().all(|path| path.starts_with(&common)) {
+        if !common.pop() {
+            break;
+        }
+    }
+    paths
+        .iter()
+        .map(|path| {
+            let relative = path.strip_prefix(&common).unwrap_or(path);
+            let parts: Vec<_> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect();
+            parts.join("/")
+        })
+        .collect()
+}
+
+/// Name the file at `path` is shown under, without its page id when `notion_export` is set.
+fn display_name(path: &Path, notion_export: bool) -> String {
+    if notion_export {
+        notion::strip_id(file_name(path))
+    } else {
+        file_name(path).to_string()
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("Markdown File")
+}
+
+/// Media type of the source file at `path`, for its attachment.
+fn source_mime_type(path: &Path) -> &'static str {
+    if is_text(path) {
+        "text/plain"
+    } else if is_html(path) {
+        "text/html"
+    } else if is_csv(path) {
+        "text/csv"
+    } else if is_rst(path) {
+        "text/x-rst"
+    } else {
+        "text/markdown"
+    }
+}
+
+/// The decoded `contents` of the source file at `path`, with HTML pages, CSV files and
+/// reStructuredText converted to markdown.
+fn source_markdown(path: &Path, contents: Cow<str>) -> String {
+    if is_html(path) {
+        htmlpage::to_markdown(&contents)
+    } else if is_csv(path) {
+        csv::to_markdown(&contents)
+    } else if is_rst(path) {
+        rst::to_markdown(&contents)
+    } else {
+        contents.into_owned()
+    }
+}
+
+/// The front matter and markdown body of the source file at `path`. Plain text files
+/// have neither; their contents are drawn as they are.
+fn split_source<'a>(path: &Path, contents: &'a str) -> (frontmatter::FrontMatter, &'a str) {
+    if is_text(path) {
+        (frontmatter::FrontMatter::default(), "")
+    } else {
+        frontmatter::split(contents)
+    }
+}
+
+/// The front matter and markdown body of the source file at `path` as its page is headed,
+/// the title and date of site pages filled in from the file name.
+fn page_source<'a>(
+    path: &Path,
+    contents: &'a str,
+    options: &RenderOptions,
+) -> (frontmatter::FrontMatter, &'a str) {
+    let (mut front_matter, body) = split_source(path, contents);
+    if options.site_content.is_some() {
+        front_matter.title = front_matter.title.or_else(|| site::page_title(path));
+        front_matter.date = front_matter.date.or_else(|| site::post_date(path));
+    }
+    (front_matter, body)
+}
+
+/// Merges the front matter of all files, each field taken from the first file that sets
+/// it. The logo is resolved against its file's folder.
+fn document_front_matter(sources: &[(PathBuf, String)]) -> frontmatter::FrontMatter {
+    let mut document = frontmatter::FrontMatter::default();
+    for (path, contents) in sources {
+        let (front_matter, _) = split_source(path, contents);
+        let logo = front_matter.logo.map(|logo| {
+            let base = path.parent().unwrap_or(Path::new("."));
+            base.join(logo).to_string_lossy().into_owned()
+        });
+        document.title = document.title.or(front_matter.title);
+        document.subtitle = document.subtitle.or(front_matter.subtitle);
+        document.author = document.author.or(front_matter.author);
+        document.date = document.date.or(front_matter.date);
+        document.subject = document.subject.or(front_matter.subject);
+        if document.keywords.is_empty() {
+            document.keywords = front_matter.keywords;
+        }
+        document.logo = document.logo.or(logo);
+        document.language = document.language.or(front_matter.language);
+    }
+    document
+}
+
+/// Lists every file, by title, chapter title or name, followed by its headings.
+fn document_toc_entries(
+    sources: &[(PathBuf, String)],
+    chapters: &HashMap<PathBuf, &book::Chapter>,
+    options: &RenderOptions,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut counters = Vec::new();
+    for (path, contents) in sources {
+        let (front_matter, body) = page_source(path, contents, options);
+        let file = normalize_path(path);
+        let chapter = chapters.get(&file);
+        for section in chapter.iter().flat_map(|chapter| &chapter.sections) {
+            entries.push(TocEntry {
+                level: 0,
+                text: section.clone(),
+                anchor: Anchor {
+                    file: file.clone(),
+                    slug: None,
+                },
+            });
+        }
+        let chapter = chapter.map(|chapter| chapter.title.clone());
+        entries.push(TocEntry {
+            level: 0,
+            text: front_matter
+                .title
+                .or(chapter)
+                .unwrap_or_else(|| display_name(path, options.notion_export)),
+            anchor: Anchor {
+                file: file.clone(),
+                slug: None,
+            },
+        });
+        entries.extend(toc_entries(body, &file, options.number_headings, &mut counters));
+    }
+    entries
+}
+
+/// A markdown file being rendered, possibly one section at a time.
+struct SourceFile<'a> {
+    contents: &'a str,
+    path: &'a Path,
+    /// Heading counters as they were before the file, for numbering its table of contents.
+    heading_counters: &'a [(u32, u32)],
+}
+
+impl SourceFile<'_> {
+    fn toc_entries(&self, renderer: &Renderer) -> Vec<TocEntry> {
+        toc_entries(
+            self.contents,
+            &renderer.current_file,
+            renderer.options.number_headings,
+            &mut self.heading_counters.to_vec(),
+        )
+    }
+}
+
+/// Collects a file's headings with the text, numbers and slugs they will be rendered
+/// with, so a table of contents can be laid out before the headings themselves.
+/// `counters` are advanced past the file's headings when numbering is on.
+fn toc_entries(
+    contents: &str,
+    file: &Path,
+    number_headings: bool,
+    counters: &mut Vec<(u32, u32)>,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut slug_counts = HashMap::new();
+    let mut current: Option<(u32, String)> = None;
+
+    let parser = Parser::new_ext(contents, MARKDOWN_OPTIONS);
+    for event in scripts::lower(contents, parser.into_offset_iter()) {
+        let Some((level, text)) = current.as_mut() else {
+            if let Event::Start(Tag::Heading { level, .. }) = event {
+                current = Some((level as u32, String::new()));
+            }
+            continue;
+        };
+        match event {
+            Event::Text(fragment) | Event::Code(fragment) | Event::InlineMath(fragment) => {
+                text.push_str(&fragment);
+            }
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::InlineHtml(fragment) => {
+                for token in html::tokenize(&fragment) {
+                    match token {
+                        HtmlToken::Text(fragment) => text.push_str(&fragment),
+                        HtmlToken::LineBreak => text.push('\n'),
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let text = text.trim();
+                let slug = unique_slug(&mut slug_counts, text);
+                let text = if number_headings {
+                    format!("{} {}", heading_number(counters, *level), text)
+                } else {
+                    text.to_string()
+                };
+                entries.push(TocEntry {
+                    level: *level,
+                    text,
+                    anchor: Anchor {
+                        file: file.to_path_buf(),
+                        slug: Some(slug),
+                    },
+                });
+                current = None;
+            }
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+fn render_markdown_content(
+    contents: &str,
+    markdown_path: &Path,
+    renderer: &mut Renderer,
+) -> Result<(), String> {
+    let heading_counters = renderer.heading_counters.clone();
+    let file = SourceFile {
+        contents,
+        path: markdown_path,
+        heading_counters: &heading_counters,
+    };
+
+    for block in columns::split(contents) {
+        match block {
+            columns::Block::Markdown(section) => render_markdown_section(section, &file, renderer)?,
+            columns::Block::Columns { count, body } => {
+                let height = measure_columns(body, count, &file, renderer)?;
+                renderer.begin_columns(count, Some(height / count as f32));
+                render_markdown_section(body, &file, renderer)?;
+                renderer.end_columns();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Total height `body` takes up laid out in a single one of `count` columns, measured
+/// with the renderer's measurer so the real columns can be balanced.
+fn measure_columns(
+    body: &str,
+    count: usize,
+    file: &SourceFile,
+    renderer: &mut Renderer,
+) -> Result<f32, String> {
+    let mut scratch = match renderer.measurer.take() {
+        Some(scratch) => scratch,
+        None => Box::new(Renderer::new(renderer.options.clone(), &renderer.fallback_fonts)?),
+    };
+    scratch.current_file = renderer.current_file.clone();
+    scratch.slug_counts = renderer.slug_counts.clone();
+    scratch.heading_counters = renderer.heading_counters.clone();
+    scratch.hyphenation = renderer.hyphenation;
+    scratch.markdown_files = renderer.markdown_files.clone();
+    scratch.image_files = renderer.image_files.clone();
+    // The images measured are the ones drawn next, so they are decoded once for both.
+    scratch.pictures = std::mem::take(&mut renderer.pictures);
+    scratch.cursor_y = MEASURE_TOP_MM;
+    scratch.begin_columns(count, None);
+    let measured = render_markdown_section(body, file, &mut scratch);
+    scratch.end_columns();
+    let height = MEASURE_TOP_MM - scratch.cursor_y;
+    renderer.pictures = std::mem::take(&mut scratch.pictures);
+    renderer.measurer = Some(scratch);
+    measured.map(|_| height)
+}
+
+fn render_markdown_section(
+    contents: &str,
+    file: &SourceFile,
+    renderer: &mut Renderer,
+) -> Result<(), String> {
+    let markdown_path = file.path;
+    let mut current_spans: Vec<Span> = Vec::new();
+    // Styles to go back to as markdown and inline HTML formatting ends, each with the HTML
+    // tag that opened it, if any.
+    let mut style_stack: Vec<(SpanStyle, Option<InlineTag>)> = Vec::new();
+    let mut style = SpanStyle::default();
+    let mut current_heading: Option<u32> = None;
+    let mut list_items: Vec<ListItem> = Vec::new();
+    let mut list_start: Option<u64> = None;
+    let mut current_list_item: Option<ListItem> = None;
+    let mut in_paragraph = false;
+    let mut in_definition = false;
+    let mut alert: Option<(BlockQuoteKind, Vec<Vec<Span>>)> = None;
+    let mut quote_depth = 0usize;
+    let mut in_code_block = false;
+    let mut code_block = String::new();
+    let mut code_language: Option<String> = None;
+    let mut current_image: Option<String> = None;
+    // Length of the caption text before the current image's alt text.
+    let mut alt_start = 0;
+    let mut table_alignments: Vec<Alignment> = Vec::new();
+    let mut table_header: Vec<String> = Vec::new();
+    let mut table_rows: Vec<Vec<String>> = Vec::new();
+    let mut current_row: Option<Vec<String>> = None;
+    let mut current_cell: Option<String> = None;
+
+    let parser = Parser::new_ext(contents, MARKDOWN_OPTIONS);
+    let mut events = scripts::lower(contents, parser.into_offset_iter()).peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph => {
+                    in_paragraph = true;
+                    current_spans.clear();
+                }
+                Tag::Heading { level, .. } => {
+                    let mapped = match level {
+                        HeadingLevel::H1 => 1,
+                        HeadingLevel::H2 => 2,
+                        HeadingLevel::H3 => 3,
+                        HeadingLevel::H4 => 4,
+                        HeadingLevel::H5 => 5,
+                        HeadingLevel::H6 => 6,
+                    };
+                    current_heading = Some(mapped);
+                    current_spans.clear();
+                }
+                Tag::List(start) => {
+                    list_start = start;
+                    list_items.clear();
+                }
+                Tag::Item => {
+                    current_list_item = Some(ListItem {
+                        spans: Vec::new(),
+                        checked: None,
+                    });
+                }
+                Tag::CodeBlock(kind) => {
+                    in_code_block = true;
+                    code_block.clear();
+                    code_language = match kind {
+                        CodeBlockKind::Fenced(info) => info
+                            .split_whitespace()
+                            .next()
+                            .map(|language| language.to_lowercase()),
+                        CodeBlockKind::Indented => None,
+                    };
+                }
+                Tag::Image { dest_url, .. } => {
+                    current_image = Some(dest_url.to_string());
+                    let caption = inline_target(&mut current_list_item, &mut current_spans);
+                    alt_start = spans_text(caption).len();
+                }
+                Tag::Table(alignments) => {
+                    table_alignments = alignments;
+                    table_header.clear();
+                    table_rows.clear();
+                }
+                Tag::TableHead | Tag::TableRow => {
+                    current_row = Some(Vec::new());
+                }
+                Tag::TableCell => {
+                    current_cell = Some(String::new());
+                }
+                Tag::Strong => {
+                    style_stack.push((style, None));
+                    style.bold = true;
+                }
+                Tag::Emphasis => {
+                    style_stack.push((style, None));
+                    style.italic = true;
+                }
+                Tag::Strikethrough => {
+                    style_stack.push((style, None));
+                    style.strikethrough = true;
+                }
+                Tag::Link { dest_url, .. } => {
+                    style_stack.push((style, None));
+                    style.link = Some(renderer.register_link(&dest_url));
+                }
+                Tag::BlockQuote(Some(kind)) => {
+                    alert = Some((kind, Vec::new()));
+                }
+                Tag::BlockQuote(None) => {
+                    quote_depth += 1;
+                }
+                Tag::DefinitionListTitle => {
+                    current_spans.clear();
+                    style_stack.push((style, None));
+                    style.bold = true;
+                }
+                Tag::DefinitionListDefinition => {
+                    in_definition = true;
+                    current_spans.clear();
+                }
+                Tag::HtmlBlock => {
+                    current_spans.clear();
+                }
+                _ => {}
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Paragraph => {
+                    if in_paragraph && spans_text(&current_spans).trim() == "[TOC]" {
+                        let entries = file.toc_entries(renderer);
+                        renderer.table_of_contents(&entries);
+                    } else if in_paragraph && is_page_break(&spans_text(&current_spans)) {
+                        renderer.page_break();
+                    } else if in_paragraph && !current_spans.is_empty() {
+                        if let Some((_, paragraphs)) = alert.as_mut() {
+                            paragraphs.push(std::mem::take(&mut current_spans));
+                        } else if in_definition {
+                            renderer.definition(&current_spans);
+                        } else if quote_depth > 0 {
+                            renderer.quote(&current_spans, quote_depth);
+                        } else {
+                            renderer.paragraph(&current_spans);
+                        }
+                    }
+                    in_paragraph = false;
+                    current_spans.clear();
+                }
+                TagEnd::Heading(_) => {
+                    if let Some(level) = current_heading.take() {
+                        let text = spans_text(&current_spans);
+                        if level == 1
+                            && renderer.options.start_on_odd_page == Some(OddPageStart::Headings)
+                        {
+                            renderer.odd_page_break();
+                        }
+                        renderer.keep_with_next(level);
+                        renderer.heading_destination(level, text.trim());
+                        renderer.heading(level, text.trim());
+                    }
+                    current_spans.clear();
+                }
+                TagEnd::List(_) => {
+                    if let Some((_, paragraphs)) = alert.as_mut() {
+                        for item in list_items.drain(..) {
+                            let mut spans = Vec::new();
+                            let bullet = format!("{} ", renderer.options.style.bullet);
+                            push_text(&mut spans, &bullet, SpanStyle::default());
+                            for span in item.spans {
+                                push_span(&mut spans, &span.text, span.style);
+                            }
+                            paragraphs.push(spans);
+                        }
+                    } else if !list_items.is_empty() {
+                        renderer.list(&list_items, list_start);
+                    }
+                    list_items.clear();
+                }
+                TagEnd::BlockQuote(Some(_)) => {
+                    if let Some((kind, paragraphs)) = alert.take() {
+                        renderer.alert(kind, &paragraphs);
+                    }
+                }
+                TagEnd::BlockQuote(None) => {
+                    quote_depth = quote_depth.saturating_sub(1);
+                }
+                TagEnd::Item => {
+                    // Empty items are kept, so the items after them keep their numbers.
+                    if let Some(item) = current_list_item.take() {
+                        list_items.push(item);
+                    }
+                }
+                TagEnd::CodeBlock => {
+                    let diagram = match code_language.as_deref() {
+                        Some("mermaid") if in_code_block => mermaid::render(&code_block),
+                        _ => None,
+                    };
+                    if let Some(diagram) = diagram {
+                        let attributes = ImageAttributes::default();
+                        let path = diagram.image_path();
+                        renderer.image(markdown_path, &path, "Diagram", &attributes)?;
+                    } else if in_code_block {
+                        renderer.code_block(&code_block, code_language.as_deref());
+                    }
+                    in_code_block = false;
+                    code_block.clear();
+                }
+                TagEnd::Image => {
+                    if let Some(dest) = current_image.take() {
+                        let mut attributes = ImageAttributes::default();
+                        if let Some(Event::Text(text)) = events.peek_mut() {
+                            if let Some((parsed, rest)) = attributes::parse(text) {
+                                attributes = parsed;
+                                *text = rest.to_string().into();
+                            }
+                        }
+                        let caption = inline_target(&mut current_list_item, &mut current_spans);
+                        let caption = spans_text(caption);
+                        let alt = caption.get(alt_start..).unwrap_or_default();
+                        let animated = renderer.image(markdown_path, &dest, alt, &attributes)?;
+                        // The alt text before this is drawn under the image as its caption.
+                        if animated && renderer.options.mark_animated_images {
+                            let target = inline_target(&mut current_list_item, &mut current_spans);
+                            push_text(target, " (animated)", style);
+                        }
+                    }
+                }
+                TagEnd::TableCell => {
+                    if let (Some(row), Some(cell)) = (current_row.as_mut(), current_cell.take()) {
+                        row.push(cell.trim().to_string());
+                    }
+                }
+                TagEnd::TableHead => {
+                    table_header = current_row.take().unwrap_or_default();
+                }
+                TagEnd::TableRow => {
+                    if let Some(row) = current_row.take() {
+                        table_rows.push(row);
+                    }
+                }
+                TagEnd::Table => {
+                    renderer.table(&table_header, &table_rows, &table_alignments);
+                    table_header.clear();
+                    table_rows.clear();
+                }
+                TagEnd::DefinitionListTitle => {
+                    style = style_stack.pop().map(|(style, _)| style).unwrap_or_default();
+                    if !current_spans.is_empty() {
+                        renderer.definition_term(&current_spans);
+                    }
+                    current_spans.clear();
+                }
+                TagEnd::DefinitionListDefinition => {
+                    if !current_spans.is_empty() {
+                        renderer.definition(&current_spans);
+                    }
+                    in_definition = false;
+                    current_spans.clear();
+                }
+                TagEnd::HtmlBlock => {
+                    if !spans_text(&current_spans).trim().is_empty() {
+                        renderer.paragraph(&current_spans);
+                    }
+                    current_spans.clear();
+                }
+                TagEnd::Strong | TagEnd::Emphasis | TagEnd::Strikethrough | TagEnd::Link => {
+                    style = style_stack.pop().map(|(style, _)| style).unwrap_or_default();
+                }
+                _ => {}
+            },
+            Event::Text(text) => {
+                if in_code_block {
+                    code_block.push_str(&text);
+                } else if let Some(cell) = current_cell.as_mut() {
+                    cell.push_str(&text);
+                } else if current_heading.is_some() {
+                    push_span(&mut current_spans, &text, style);
+                } else {
+                    for piece in wikilink::segments(&text) {
+                        let text = match piece {
+                            wikilink::Piece::Text(text) => text,
+                            wikilink::Piece::Embed(link) if is_image(Path::new(link.note)) => {
+                                if current_list_item.is_none()
+                                    && !spans_text(&current_spans).trim().is_empty()
+                                {
+                                    renderer.paragraph(&current_spans);
+                                    current_spans.clear();
+                                }
+                                let dest = match renderer.resolve_attachment(link.note) {
+                                    Some(path) => path.to_string_lossy().to_string(),
+                                    None => link.note.to_string(),
+                                };
+                                renderer.image(
+                                    markdown_path,
+                                    &dest,
+                                    &link.label,
+                                    &ImageAttributes::default(),
+                                )?;
+                                continue;
+                            }
+                            wikilink::Piece::Link(link) | wikilink::Piece::Embed(link) => {
+                                let link_style = match renderer.register_wiki_link(&link) {
+                                    Some(index) => SpanStyle {
+                                        link: Some(index),
+                                        ..style
+                                    },
+                                    None => SpanStyle {
+                                        italic: true,
+                                        ..style
+                                    },
+                                };
+                                let target =
+                                    inline_target(&mut current_list_item, &mut current_spans);
+                                push_text(target, &link.label, link_style);
+                                continue;
+                            }
+                        };
+                        let target = inline_target(&mut current_list_item, &mut current_spans);
+                        for (piece, dest) in autolink::segments(text) {
+                            let style = match dest {
+                                Some(dest) if style.link.is_none() => SpanStyle {
+                                    link: Some(renderer.register_link(&dest)),
+                                    ..style
+                                },
+                                _ => style,
+                            };
+                            push_text(target, piece, style);
+                        }
+                    }
+                }
+            }
+            Event::Code(text) => {
+                let code_style = SpanStyle {
+                    code: true,
+                    ..style
+                };
+                if let Some(cell) = current_cell.as_mut() {
+                    cell.push_str(&text);
+                } else if let Some(item) = current_list_item.as_mut() {
+                    push_span(&mut item.spans, &text, code_style);
+                } else {
+                    push_span(&mut current_spans, &text, code_style);
+                }
+            }
+            Event::InlineMath(source) => {
+                if let Some(cell) = current_cell.as_mut() {
+                    cell.push_str(&source);
+                } else if current_heading.is_some() {
+                    push_span(&mut current_spans, &source, style);
+                } else {
+                    let spans = math::typeset(&source, style);
+                    let target = match current_list_item.as_mut() {
+                        Some(item) => &mut item.spans,
+                        None => &mut current_spans,
+                    };
+                    for span in spans {
+                        push_span(target, &span.text, span.style);
+                    }
+                }
+            }
+            Event::DisplayMath(source) => {
+                if let Some(cell) = current_cell.as_mut() {
+                    cell.push_str(&source);
+                } else {
+                    if !current_spans.is_empty() {
+                        renderer.paragraph(&current_spans);
+                        current_spans.clear();
+                    }
+                    renderer.display_math(&math::typeset(&source, SpanStyle::default()));
+                }
+            }
+            Event::SoftBreak => {
+                if in_code_block {
+                    code_block.push('\n');
+                } else if let Some(item) = current_list_item.as_mut() {
+                    push_span(&mut item.spans, " ", style);
+                } else {
+                    push_span(&mut current_spans, " ", style);
+                }
+            }
+            Event::HardBreak => {
+                if in_code_block {
+                    code_block.push('\n');
+                } else if let Some(item) = current_list_item.as_mut() {
+                    push_span(&mut item.spans, "\n", style);
+                } else {
+                    push_span(&mut current_spans, "\n", style);
+                }
+            }
+            Event::Html(fragment) if fragment.trim().eq_ignore_ascii_case("<!-- toc -->") => {
+                let entries = file.toc_entries(renderer);
+                renderer.table_of_contents(&entries);
+            }
+            Event::Html(fragment) if is_page_break(&fragment) => {
+                renderer.page_break();
+            }
+            Event::Html(fragment) | Event::InlineHtml(fragment) => {
+                for token in html::tokenize(&fragment) {
+                    match token {
+                        HtmlToken::Text(text) => {
+                            if let Some(cell) = current_cell.as_mut() {
+                                cell.push_str(&text);
+                            } else if let Some(item) = current_list_item.as_mut() {
+                                push_text(&mut item.spans, &text, style);
+                            } else if current_heading.is_some() {
+                                push_span(&mut current_spans, &text, style);
+                            } else {
+                                push_text(&mut current_spans, &text, style);
+                            }
+                        }
+                        HtmlToken::LineBreak => {
+                            if let Some(cell) = current_cell.as_mut() {
+                                cell.push(' ');
+                            } else if let Some(item) = current_list_item.as_mut() {
+                                push_span(&mut item.spans, "\n", style);
+                            } else {
+                                push_span(&mut current_spans, "\n", style);
+                            }
+                        }
+                        HtmlToken::Open(tag) => {
+                            style_stack.push((style, Some(tag)));
+                            match tag {
+                                InlineTag::Bold => style.bold = true,
+                                InlineTag::Italic => style.italic = true,
+                                InlineTag::Strikethrough => style.strikethrough = true,
+                                InlineTag::Superscript => style.script = Script::Super,
+                                InlineTag::Subscript => style.script = Script::Sub,
+                            }
+                        }
+                        // A closing tag without its opening tag, such as a stray `</b>`,
+                        // leaves the formatting around it alone.
+                        HtmlToken::Close(tag) => {
+                            let opened = style_stack.last().and_then(|(_, opened)| *opened);
+                            if opened == Some(tag) {
+                                style = style_stack.pop().map_or(style, |(style, _)| style);
+                            }
+                        }
+                        HtmlToken::Image { src } => {
+                            if current_cell.is_some() {
+                                continue;
+                            }
+                            if current_list_item.is_none()
+                                && !spans_text(&current_spans).trim().is_empty()
+                            {
+                                renderer.paragraph(&current_spans);
+                                current_spans.clear();
+                            }
+                            let attributes = ImageAttributes::default();
+                            renderer.image(markdown_path, &src, "", &attributes)?;
+                        }
+                    }
+                }
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(item) = current_list_item.as_mut() {
+                    item.checked = Some(checked);
+                }
+            }
+            Event::Rule => {
+                renderer.cursor_y -= Renderer::pt_to_mm(8.0);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Bounds on how much of an input is scanned, so dropping something like a home folder
+//! fails quickly with what was found so far instead of walking all of it.
+
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Document;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanLimits {
+    /// Deepest folder level searched below each input folder; a folder further down stops
+    /// the scan, rather than its files being left out unseen.
+    pub max_depth: usize,
+    /// Most markdown files collected.
+    pub max_markdown_files: usize,
+    /// Most bytes of markdown files and images collected, together.
+    pub max_total_bytes: u64,
+}
+
+impl Default for ScanLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            max_markdown_files: 5_000,
+            max_total_bytes: 1 << 30,
+        }
+    }
+}
+
+impl ScanLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_depth == 0 || self.max_markdown_files == 0 || self.max_total_bytes == 0 {
+            return Err("Scan limits must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What a scan has collected so far, checked against its limits as files are added.
+pub struct Tally {
+    limits: ScanLimits,
+    markdown_files: usize,
+    image_files: usize,
+    bytes: u64,
+}
+
+impl Tally {
+    pub fn new(limits: ScanLimits) -> Self {
+        Self {
+            limits,
+            markdown_files: 0,
+            image_files: 0,
+            bytes: 0,
+        }
+    }
+
+    pub fn add_markdown(&mut self, bytes: u64) -> Result<(), TooLarge> {
+        self.markdown_files += 1;
+        self.bytes += bytes;
+        self.check()
+    }
+
+    pub fn add_image(&mut self, bytes: u64) -> Result<(), TooLarge> {
+        self.image_files += 1;
+        self.bytes += bytes;
+        self.check()
+    }
+
+    /// The error for a folder found at the deepest level searched.
+    pub fn too_deep(&self) -> TooLarge {
+        self.stop(Limit::Depth, self.limits.max_depth as u64)
+    }
+
+    fn check(&self) -> Result<(), TooLarge> {
+        if self.markdown_files > self.limits.max_markdown_files {
+            Err(self.stop(Limit::MarkdownFiles, self.limits.max_markdown_files as u64))
+        } else if self.bytes > self.limits.max_total_bytes {
+            Err(self.stop(Limit::TotalBytes, self.limits.max_total_bytes))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn stop(&self, limit: Limit, max: u64) -> TooLarge {
+        TooLarge {
+            limit,
+            max,
+            markdown_files: self.markdown_files,
+            image_files: self.image_files,
+            bytes: self.bytes,
+        }
+    }
+}
+
+/// Checks the files of `input` together against `limits`, for inputs scanned one at a
+/// time and then combined.
+pub fn check(input: &Document, limits: ScanLimits) -> Result<(), TooLarge> {
+    let size = |path: &String| fs::metadata(path).map_or(0, |metadata| metadata.len());
+    let mut tally = Tally::new(limits);
+    for file in &input.markdown_files {
+        tally.add_markdown(size(file))?;
+    }
+    for file in &input.image_files {
+        tally.add_image(size(file))?;
+    }
+    Ok(())
+}
+
+/// The limit a scan passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Depth,
+    MarkdownFiles,
+    TotalBytes,
+}
+
+/// A scan stopped at a limit, with the counts it reached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TooLarge {
+    pub limit: Limit,
+    /// The value of `limit`: a number of folder levels, markdown files or bytes.
+    pub max: u64,
+    pub markdown_files: usize,
+    pub image_files: usize,
+    pub bytes: u64,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.limit {
+            Limit::Depth => format!("folders nested more than {} levels deep", self.max),
+            Limit::MarkdownFiles => format!("more than {} markdown files", self.max),
+            Limit::TotalBytes => format!("more than {} MB", self.max >> 20),
+        };
+        write!(
+            f,
+            "Input too large: {limit}. Stopped after {} markdown files and {} images totalling {} MB",
+            self.markdown_files,
+            self.image_files,
+            self.bytes >> 20
+        )
+    }
+}
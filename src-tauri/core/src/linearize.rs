@@ -0,0 +1,578 @@
+//! Linearized ("fast web view") output, laid out as in Annex F of the PDF specification.
+//!
+//! The catalog and everything the first page needs come first, behind a cross-reference
+//! table of their own, so a viewer fetching the file over HTTP can show the first page
+//! before the rest has arrived. The hint tables after the catalog tell it where every
+//! later page and the objects pages share start, so it can request them by byte range.
+//! Objects are renumbered: the rest of the file takes the low numbers and the first-page
+//! section the high ones, so each cross-reference table is a single run.
+
+use std::collections::{HashMap, HashSet};
+
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+
+/// Width the offsets written before they are known are padded to.
+const OFFSET_WIDTH: usize = 10;
+
+/// Objects grouped into the parts of the file they are written in.
+struct Layout {
+    catalog: ObjectId,
+    /// The first page's page object and everything it needs.
+    first_page: Vec<ObjectId>,
+    /// Each later page's page object and the objects only that page needs.
+    pages: Vec<Vec<ObjectId>>,
+    /// Objects more than one later page needs that the first page does not.
+    shared: Vec<ObjectId>,
+    /// For each later page, the objects it needs from the first page or the shared part.
+    shared_references: Vec<Vec<ObjectId>>,
+    /// Everything else: the page tree, outline, metadata and so on.
+    other: Vec<ObjectId>,
+}
+
+/// Serializes `doc` as a linearized file.
+pub fn write(doc: &Document) -> Result<Vec<u8>, String> {
+    let layout = Layout::new(doc)?;
+
+    // The rest of the file takes numbers from 1; the first-page section follows with the
+    // linearization dictionary, the catalog, the first page and the hint stream.
+    let mut numbers: HashMap<ObjectId, ObjectId> = HashMap::new();
+    let mut next = 1;
+    let rest = layout
+        .pages
+        .iter()
+        .flatten()
+        .chain(&layout.shared)
+        .chain(&layout.other);
+    for &id in rest {
+        numbers.insert(id, (next, 0));
+        next += 1;
+    }
+    let first_number = next;
+    next += 1;
+    for &id in std::iter::once(&layout.catalog).chain(&layout.first_page) {
+        numbers.insert(id, (next, 0));
+        next += 1;
+    }
+    let hint_number = next;
+    let size = hint_number + 1;
+
+    let body = |id: &ObjectId| -> Result<Vec<u8>, String> {
+        let object = doc
+            .objects
+            .get(id)
+            .ok_or("Missing object in the document")?;
+        Ok(indirect_object(numbers[id].0, object, &numbers))
+    };
+    let bodies = |ids: &[ObjectId]| ids.iter().map(body).collect::<Result<Vec<_>, _>>();
+    let catalog = body(&layout.catalog)?;
+    let first_page = bodies(&layout.first_page)?;
+    let pages = layout
+        .pages
+        .iter()
+        .map(|ids| bodies(ids))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shared = bodies(&layout.shared)?;
+    let other = bodies(&layout.other)?;
+
+    let mut trailer = Dictionary::new();
+    trailer.set("Size", Object::Integer(size.into()));
+    for key in [b"Root".as_slice(), b"Info", b"ID"] {
+        if let Ok(value) = doc.trailer.get(key) {
+            trailer.set(key, value.clone());
+        }
+    }
+
+    // Everything up to the catalog has a fixed size, with the offsets in it padded.
+    let mut header = format!("%PDF-{}\n%", doc.version).into_bytes();
+    header.extend_from_slice(&[0xE2, 0xE3, 0xCF, 0xD3, b'\n']);
+    let linearization_len = Linearization::default().object(first_number).len();
+    let first_xref_start = header.len() + linearization_len;
+    let entries = vec![0; (size - first_number) as usize];
+    let catalog_start =
+        first_xref_start + first_xref(first_number, &entries, &trailer, 0, &numbers).len();
+    let hint_start = catalog_start + catalog.len();
+
+    // Hint tables give offsets as if the hint stream were not there.
+    let mut offsets: HashMap<u32, usize> = HashMap::new();
+    let mut end = hint_start;
+    let sections = std::iter::once((&layout.first_page, &first_page))
+        .chain(layout.pages.iter().zip(&pages))
+        .chain([(&layout.shared, &shared), (&layout.other, &other)]);
+    for (ids, bodies) in sections {
+        for (id, body) in ids.iter().zip(bodies) {
+            offsets.insert(numbers[id].0, end);
+            end += body.len();
+        }
+    }
+    let hints = hint_tables(&layout, &numbers, &offsets, &first_page, &pages, &shared);
+    let mut hint_dict = Dictionary::new();
+    hint_dict.set("S", Object::Integer(hints.shared_table_offset as i64));
+    let hint_object = stream_object(hint_number, &hint_dict, &hints.data, &numbers);
+    let hint_len = hint_object.len();
+    for offset in offsets.values_mut() {
+        *offset += hint_len;
+    }
+    offsets.insert(numbers[&layout.catalog].0, catalog_start);
+    offsets.insert(hint_number, hint_start);
+
+    let main_xref_start = end + hint_len;
+    let main_xref_prefix = format!("xref\n0 {first_number}\n");
+    let mut main_xref = main_xref_prefix.clone().into_bytes();
+    main_xref.extend_from_slice(b"0000000000 65535 f\r\n");
+    for number in 1..first_number {
+        main_xref.extend_from_slice(format!("{:010} 00000 n\r\n", offsets[&number]).as_bytes());
+    }
+    main_xref.extend_from_slice(format!("trailer\n<</Size {first_number}>>\n").as_bytes());
+    main_xref.extend_from_slice(format!("startxref\n{first_xref_start}\n%%EOF\n").as_bytes());
+
+    let linearization = Linearization {
+        file_len: main_xref_start + main_xref.len(),
+        hint_start,
+        hint_len,
+        first_page: numbers[&layout.first_page[0]].0,
+        first_page_end: hint_start + hint_len + first_page.iter().map(Vec::len).sum::<usize>(),
+        pages: layout.pages.len() + 1,
+        main_xref_entries: main_xref_start + main_xref_prefix.len() - 1,
+    };
+    let entries: Vec<usize> = (first_number..size)
+        .map(|number| match number {
+            number if number == first_number => header.len(),
+            number => offsets[&number],
+        })
+        .collect();
+
+    let mut output = header;
+    output.extend(linearization.object(first_number));
+    output.extend(first_xref(
+        first_number,
+        &entries,
+        &trailer,
+        main_xref_start,
+        &numbers,
+    ));
+    output.extend(catalog);
+    output.extend(hint_object);
+    let bodies = first_page
+        .into_iter()
+        .chain(pages.into_iter().flatten())
+        .chain(shared)
+        .chain(other);
+    for body in bodies {
+        output.extend(body);
+    }
+    output.extend(main_xref);
+    Ok(output)
+}
+
+impl Layout {
+    fn new(doc: &Document) -> Result<Self, String> {
+        let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+        let (&first_id, later_ids) = page_ids
+            .split_first()
+            .ok_or("Cannot linearize a document without pages")?;
+        let catalog = doc
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .map_err(|err| err.to_string())?;
+
+        let first_page = reachable(doc, first_id);
+        let in_first_page: HashSet<ObjectId> = first_page.iter().copied().collect();
+        let needs: Vec<Vec<ObjectId>> = later_ids.iter().map(|&id| reachable(doc, id)).collect();
+        let mut users: HashMap<ObjectId, usize> = HashMap::new();
+        for &id in needs.iter().flatten() {
+            *users.entry(id).or_default() += 1;
+        }
+        let is_shared = |id: &ObjectId| !in_first_page.contains(id) && users[id] > 1;
+
+        let pages: Vec<Vec<ObjectId>> = needs
+            .iter()
+            .map(|ids| {
+                let own = |id: &&ObjectId| !in_first_page.contains(id) && users[id] == 1;
+                ids.iter().filter(own).copied().collect()
+            })
+            .collect();
+        let mut shared = Vec::new();
+        let mut placed: HashSet<ObjectId> = in_first_page.clone();
+        for id in needs.iter().flatten() {
+            if is_shared(id) && placed.insert(*id) {
+                shared.push(*id);
+            }
+        }
+        let shared_references = needs
+            .iter()
+            .map(|ids| {
+                let borrowed = |id: &&ObjectId| in_first_page.contains(id) || is_shared(id);
+                ids.iter().filter(borrowed).copied().collect()
+            })
+            .collect();
+        placed.insert(catalog);
+        placed.extend(pages.iter().flatten());
+        let other = doc
+            .objects
+            .keys()
+            .filter(|id| !placed.contains(id))
+            .copied()
+            .collect();
+
+        Ok(Self {
+            catalog,
+            first_page,
+            pages,
+            shared,
+            shared_references,
+            other,
+        })
+    }
+}
+
+/// The page object `page_id` followed by every object it needs, without following
+/// references to other pages or up the page tree.
+fn reachable(doc: &Document, page_id: ObjectId) -> Vec<ObjectId> {
+    let mut found = vec![page_id];
+    let mut seen: HashSet<ObjectId> = HashSet::from([page_id]);
+    let mut index = 0;
+    while let Some(id) = found.get(index) {
+        let mut references = Vec::new();
+        if let Some(object) = doc.objects.get(id) {
+            collect_references(object, &mut references);
+        }
+        for id in references {
+            let is_page_tree = doc
+                .get_dictionary(id)
+                .is_ok_and(|dict| dict.type_is(b"Page") || dict.type_is(b"Pages"));
+            if !is_page_tree && doc.objects.contains_key(&id) && seen.insert(id) {
+                found.push(id);
+            }
+        }
+        index += 1;
+    }
+    found
+}
+
+fn collect_references(object: &Object, references: &mut Vec<ObjectId>) {
+    match object {
+        Object::Reference(id) => references.push(*id),
+        Object::Array(items) => {
+            for item in items {
+                collect_references(item, references);
+            }
+        }
+        Object::Dictionary(dict) => {
+            for (_, value) in dict {
+                collect_references(value, references);
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in &stream.dict {
+                collect_references(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The linearization parameter dictionary, the first object in the file.
+#[derive(Default)]
+struct Linearization {
+    file_len: usize,
+    hint_start: usize,
+    hint_len: usize,
+    /// Object number of the first page's page object.
+    first_page: u32,
+    /// Offset just past the first page's objects.
+    first_page_end: usize,
+    pages: usize,
+    /// Offset of the white space before the main cross-reference table's first entry.
+    main_xref_entries: usize,
+}
+
+impl Linearization {
+    fn object(&self, number: u32) -> Vec<u8> {
+        let pad = |value: usize| format!("{value:>OFFSET_WIDTH$}");
+        format!(
+            "{number} 0 obj\n<</Linearized 1/L {}/H[{} {}]/O {}/E {}/N {}/T {}>>\nendobj\n",
+            pad(self.file_len),
+            pad(self.hint_start),
+            pad(self.hint_len),
+            pad(self.first_page as usize),
+            pad(self.first_page_end),
+            pad(self.pages),
+            pad(self.main_xref_entries),
+        )
+        .into_bytes()
+    }
+}
+
+/// The first-page cross-reference table, for the objects numbered from `first_number`,
+/// and the trailer pointing back at the main table.
+fn first_xref(
+    first_number: u32,
+    entries: &[usize],
+    trailer: &Dictionary,
+    main_xref_start: usize,
+    numbers: &HashMap<ObjectId, ObjectId>,
+) -> Vec<u8> {
+    let mut bytes = format!("xref\n{first_number} {}\n", entries.len()).into_bytes();
+    for offset in entries {
+        bytes.extend_from_slice(format!("{offset:010} 00000 n\r\n").as_bytes());
+    }
+    bytes.extend_from_slice(b"trailer\n<<");
+    for (key, value) in trailer {
+        write_name(&mut bytes, key);
+        bytes.push(b' ');
+        write_object(&mut bytes, value, numbers);
+    }
+    bytes.extend_from_slice(format!("/Prev {main_xref_start:>OFFSET_WIDTH$}>>\n").as_bytes());
+    bytes.extend_from_slice(b"startxref\n0\n%%EOF\n");
+    bytes
+}
+
+struct HintTables {
+    data: Vec<u8>,
+    /// Where the shared object hint table starts in `data`.
+    shared_table_offset: usize,
+}
+
+/// The page offset hint table followed by the shared object hint table, as in tables F.3
+/// to F.6 of the specification.
+fn hint_tables(
+    layout: &Layout,
+    numbers: &HashMap<ObjectId, ObjectId>,
+    offsets: &HashMap<u32, usize>,
+    first_page: &[Vec<u8>],
+    pages: &[Vec<Vec<u8>>],
+    shared: &[Vec<u8>],
+) -> HintTables {
+    // Shared objects are identified by position: the first page's objects, then the
+    // shared part, each object a group of its own.
+    let identifiers: HashMap<ObjectId, usize> = layout
+        .first_page
+        .iter()
+        .chain(&layout.shared)
+        .enumerate()
+        .map(|(index, id)| (*id, index))
+        .collect();
+    let length = |bodies: &[Vec<u8>]| bodies.iter().map(Vec::len).sum::<usize>();
+    let object_counts: Vec<usize> = std::iter::once(first_page.len())
+        .chain(pages.iter().map(Vec::len))
+        .collect();
+    let lengths: Vec<usize> = std::iter::once(length(first_page))
+        .chain(pages.iter().map(|bodies| length(bodies)))
+        .collect();
+    let references: Vec<Vec<usize>> = std::iter::once(Vec::new())
+        .chain(
+            layout
+                .shared_references
+                .iter()
+                .map(|ids| ids.iter().map(|id| identifiers[id]).collect()),
+        )
+        .collect();
+
+    let least_objects = object_counts.iter().copied().min().unwrap_or(0);
+    let least_length = lengths.iter().copied().min().unwrap_or(0);
+    let object_bits = bits(
+        object_counts
+            .iter()
+            .map(|count| count - least_objects)
+            .max(),
+    );
+    let length_bits = bits(lengths.iter().map(|length| length - least_length).max());
+    let reference_bits = bits(references.iter().map(Vec::len).max());
+    let identifier_bits = bits(identifiers.len().checked_sub(1));
+
+    let mut table = BitWriter::default();
+    table.write(least_objects, 32);
+    table.write(offsets[&numbers[&layout.first_page[0]].0], 32);
+    table.write(object_bits, 16);
+    table.write(least_length, 32);
+    table.write(length_bits, 16);
+    // Content streams are described as the whole page, as most writers do.
+    table.write(0, 32);
+    table.write(0, 16);
+    table.write(least_length, 32);
+    table.write(length_bits, 16);
+    table.write(reference_bits, 16);
+    table.write(identifier_bits, 16);
+    // Shared objects are not placed within pages: no numerator bits, denominator one.
+    table.write(0, 16);
+    table.write(1, 16);
+    table.write_all(
+        object_counts.iter().map(|count| count - least_objects),
+        object_bits,
+    );
+    table.write_all(
+        lengths.iter().map(|length| length - least_length),
+        length_bits,
+    );
+    table.write_all(references.iter().map(Vec::len), reference_bits);
+    table.write_all(references.iter().flatten().copied(), identifier_bits);
+    // Numerators and content stream offsets take no bits.
+    table.write_all(
+        lengths.iter().map(|length| length - least_length),
+        length_bits,
+    );
+    let shared_table_offset = table.bytes.len();
+
+    let group_lengths: Vec<usize> = first_page.iter().chain(shared).map(Vec::len).collect();
+    let least_group = group_lengths.iter().copied().min().unwrap_or(0);
+    let group_bits = bits(
+        group_lengths
+            .iter()
+            .map(|length| length - least_group)
+            .max(),
+    );
+    let (first_shared, first_shared_offset) = match layout.shared.first() {
+        Some(id) => (numbers[id].0 as usize, offsets[&numbers[id].0]),
+        None => (0, 0),
+    };
+    table.write(first_shared, 32);
+    table.write(first_shared_offset, 32);
+    table.write(layout.first_page.len(), 32);
+    table.write(group_lengths.len(), 32);
+    table.write(0, 16);
+    table.write(least_group, 32);
+    table.write(group_bits, 16);
+    table.write_all(
+        group_lengths.iter().map(|length| length - least_group),
+        group_bits,
+    );
+    // No group carries an MD5 signature.
+    table.write_all(group_lengths.iter().map(|_| 0), 1);
+
+    HintTables {
+        data: table.bytes,
+        shared_table_offset,
+    }
+}
+
+/// Bits needed to write `value`; none for zero or no value.
+fn bits(value: Option<usize>) -> usize {
+    value.map_or(0, |value| (usize::BITS - value.leading_zeros()) as usize)
+}
+
+/// Writes big-endian bit fields.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Bits of the last byte already used; zero when it is full.
+    used: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: usize, bits: usize) {
+        for shift in (0..bits).rev() {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> shift) & 1 == 1 {
+                if let Some(byte) = self.bytes.last_mut() {
+                    *byte |= 0x80 >> self.used;
+                }
+            }
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    /// Writes one item for every page or group, starting the next item on a fresh byte.
+    fn write_all(&mut self, values: impl Iterator<Item = usize>, bits: usize) {
+        for value in values {
+            self.write(value, bits);
+        }
+        self.used = 0;
+    }
+}
+
+fn indirect_object(number: u32, object: &Object, numbers: &HashMap<ObjectId, ObjectId>) -> Vec<u8> {
+    if let Object::Stream(stream) = object {
+        return stream_object(number, &stream.dict, &stream.content, numbers);
+    }
+    let mut bytes = format!("{number} 0 obj\n").into_bytes();
+    write_object(&mut bytes, object, numbers);
+    bytes.extend_from_slice(b"\nendobj\n");
+    bytes
+}
+
+fn stream_object(
+    number: u32,
+    dict: &Dictionary,
+    content: &[u8],
+    numbers: &HashMap<ObjectId, ObjectId>,
+) -> Vec<u8> {
+    let mut dict = dict.clone();
+    dict.set("Length", Object::Integer(content.len() as i64));
+    let mut bytes = format!("{number} 0 obj\n").into_bytes();
+    write_object(&mut bytes, &Object::Dictionary(dict), numbers);
+    bytes.extend_from_slice(b"\nstream\n");
+    bytes.extend_from_slice(content);
+    bytes.extend_from_slice(b"\nendstream\nendobj\n");
+    bytes
+}
+
+/// Serializes `object`, pointing its references at the new object numbers.
+fn write_object(bytes: &mut Vec<u8>, object: &Object, numbers: &HashMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Null => bytes.extend_from_slice(b"null"),
+        Object::Boolean(value) => bytes.extend_from_slice(value.to_string().as_bytes()),
+        Object::Integer(value) => bytes.extend_from_slice(value.to_string().as_bytes()),
+        Object::Real(value) => bytes.extend_from_slice(value.to_string().as_bytes()),
+        Object::Name(name) => write_name(bytes, name),
+        Object::String(text, StringFormat::Literal) => {
+            bytes.push(b'(');
+            for &byte in text {
+                match byte {
+                    // A backslash before a raw end of line continues the string without it.
+                    b'\r' => bytes.extend_from_slice(b"\\r"),
+                    b'(' | b')' | b'\\' => bytes.extend_from_slice(&[b'\\', byte]),
+                    _ => bytes.push(byte),
+                }
+            }
+            bytes.push(b')');
+        }
+        Object::String(text, StringFormat::Hexadecimal) => {
+            bytes.push(b'<');
+            for byte in text {
+                bytes.extend_from_slice(format!("{byte:02X}").as_bytes());
+            }
+            bytes.push(b'>');
+        }
+        Object::Array(items) => {
+            bytes.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    bytes.push(b' ');
+                }
+                write_object(bytes, item, numbers);
+            }
+            bytes.push(b']');
+        }
+        Object::Dictionary(dict) => {
+            bytes.extend_from_slice(b"<<");
+            for (key, value) in dict {
+                write_name(bytes, key);
+                bytes.push(b' ');
+                write_object(bytes, value, numbers);
+            }
+            bytes.extend_from_slice(b">>");
+        }
+        Object::Stream(stream) => {
+            write_object(bytes, &Object::Dictionary(stream.dict.clone()), numbers);
+        }
+        Object::Reference(id) => match numbers.get(id) {
+            Some((number, _)) => bytes.extend_from_slice(format!("{number} 0 R").as_bytes()),
+            // A reference to a missing object means null.
+            None => bytes.extend_from_slice(b"null"),
+        },
+    }
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &[u8]) {
+    bytes.push(b'/');
+    for &byte in name {
+        if b"()<>[]{}/%#".contains(&byte) || !(b'!'..=b'~').contains(&byte) {
+            bytes.extend_from_slice(format!("#{byte:02X}").as_bytes());
+        } else {
+            bytes.push(byte);
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Typesetting for a practical subset of LaTeX math using the builtin fonts.
+//!
+//! Variables are set in Helvetica Oblique, numbers and punctuation upright, and Greek
+//! letters and operators in the Symbol font. Superscripts and subscripts map onto
+//! [`Script`] so the renderer can shrink and raise them like any other span.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{push_span, Script, Span, SpanStyle};
+
+/// Symbol-font codes for Greek letters, written as the Latin-1 characters whose
+/// WinAnsi byte matches the glyph position in the font's builtin encoding.
+const GREEK: &[(&str, char)] = &[
+    ("alpha", 'a'),
+    ("beta", 'b'),
+    ("gamma", 'g'),
+    ("delta", 'd'),
+    ("epsilon", 'e'),
+    ("varepsilon", 'e'),
+    ("zeta", 'z'),
+    ("eta", 'h'),
+    ("theta", 'q'),
+    ("vartheta", 'J'),
+    ("iota", 'i'),
+    ("kappa", 'k'),
+    ("lambda", 'l'),
+    ("mu", 'm'),
+    ("nu", 'n'),
+    ("xi", 'x'),
+    ("omicron", 'o'),
+    ("pi", 'p'),
+    ("rho", 'r'),
+    ("sigma", 's'),
+    ("tau", 't'),
+    ("upsilon", 'u'),
+    ("phi", 'f'),
+    ("varphi", 'j'),
+    ("chi", 'c'),
+    ("psi", 'y'),
+    ("omega", 'w'),
+    ("Gamma", 'G'),
+    ("Delta", 'D'),
+    ("Theta", 'Q'),
+    ("Lambda", 'L'),
+    ("Xi", 'X'),
+    ("Pi", 'P'),
+    ("Sigma", 'S'),
+    ("Upsilon", '\u{A1}'),
+    ("Phi", 'F'),
+    ("Psi", 'Y'),
+    ("Omega", 'W'),
+];
+
+/// Symbol-font operators; `true` marks relations and binary operators that get
+/// surrounding space.
+const OPERATORS: &[(&str, char, bool)] = &[
+    ("times", '\u{B4}', true),
+    ("cdot", '\u{D7}', true),
+    ("pm", '\u{B1}', true),
+    ("div", '\u{B8}', true),
+    ("leq", '\u{A3}', true),
+    ("le", '\u{A3}', true),
+    ("geq", '\u{B3}', true),
+    ("ge", '\u{B3}', true),
+    ("neq", '\u{B9}', true),
+    ("ne", '\u{B9}', true),
+    ("approx", '\u{BB}', true),
+    ("equiv", '\u{BA}', true),
+    ("propto", '\u{B5}', true),
+    ("to", '\u{AE}', true),
+    ("rightarrow", '\u{AE}', true),
+    ("leftarrow", '\u{AC}', true),
+    ("Rightarrow", '\u{DE}', true),
+    ("Leftarrow", '\u{DC}', true),
+    ("Leftrightarrow", '\u{DB}', true),
+    ("in", '\u{CE}', true),
+    ("notin", '\u{CF}', true),
+    ("subset", '\u{CC}', true),
+    ("subseteq", '\u{CD}', true),
+    ("supset", '\u{C9}', true),
+    ("cap", '\u{C7}', true),
+    ("cup", '\u{C8}', true),
+    ("wedge", '\u{D9}', true),
+    ("vee", '\u{DA}', true),
+    ("infty", '\u{A5}', false),
+    ("partial", '\u{B6}', false),
+    ("nabla", '\u{D1}', false),
+    ("forall", '\u{22}', false),
+    ("exists", '\u{24}', false),
+    ("emptyset", '\u{C6}', false),
+    ("sum", '\u{E5}', false),
+    ("prod", '\u{D5}', false),
+    ("int", '\u{F2}', false),
+    ("ldots", '\u{BC}', false),
+    ("cdots", '\u{BC}', false),
+    ("dots", '\u{BC}', false),
+    ("prime", '\u{A2}', false),
+    ("circ", '\u{B0}', false),
+];
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh",
+    "tanh", "log", "ln", "lg", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "deg",
+    "dim", "ker", "arg",
+];
+
+const SYMBOL_MINUS: char = '-';
+const SYMBOL_RADICAL: char = '\u{D6}';
+
+/// Maps Symbol-font codes back to the characters they show, for fonts that use Unicode.
+pub fn symbol_to_unicode(text: &str) -> String {
+    text.chars()
+        .map(|code| match code {
+            'a' => 'α',
+            'b' => 'β',
+            'g' => 'γ',
+            'd' => 'δ',
+            'e' => 'ε',
+            'z' => 'ζ',
+            'h' => 'η',
+            'q' => 'θ',
+            'J' => 'ϑ',
+            'i' => 'ι',
+            'k' => 'κ',
+            'l' => 'λ',
+            'm' => 'μ',
+            'n' => 'ν',
+            'x' => 'ξ',
+            'o' => 'ο',
+            'p' => 'π',
+            'r' => 'ρ',
+            's' => 'σ',
+            't' => 'τ',
+            'u' => 'υ',
+            'f' => 'φ',
+            'j' => 'ϕ',
+            'c' => 'χ',
+            'y' => 'ψ',
+            'w' => 'ω',
+            'G' => 'Γ',
+            'D' => 'Δ',
+            'Q' => 'Θ',
+            'L' => 'Λ',
+            'X' => 'Ξ',
+            'P' => 'Π',
+            'S' => 'Σ',
+            '\u{A1}' => 'ϒ',
+            'F' => 'Φ',
+            'Y' => 'Ψ',
+            'W' => 'Ω',
+            '\u{B4}' => '×',
+            '\u{D7}' => '⋅',
+            '\u{B8}' => '÷',
+            '\u{A3}' => '≤',
+            '\u{B3}' => '≥',
+            '\u{B9}' => '≠',
+            '\u{BB}' => '≈',
+            '\u{BA}' => '≡',
+            '\u{B5}' => '∝',
+            '\u{AE}' => '→',
+            '\u{AC}' => '←',
+            '\u{DE}' => '⇒',
+            '\u{DC}' => '⇐',
+            '\u{DB}' => '⇔',
+            '\u{CE}' => '∈',
+            '\u{CF}' => '∉',
+            '\u{CC}' => '⊂',
+            '\u{CD}' => '⊆',
+            '\u{C9}' => '⊃',
+            '\u{C7}' => '∩',
+            '\u{C8}' => '∪',
+            '\u{D9}' => '∧',
+            '\u{DA}' => '∨',
+            '\u{A5}' => '∞',
+            '\u{B6}' => '∂',
+            '\u{D1}' => '∇',
+            '\u{22}' => '∀',
+            '\u{24}' => '∃',
+            '\u{C6}' => '∅',
+            '\u{E5}' => '∑',
+            '\u{D5}' => '∏',
+            '\u{F2}' => '∫',
+            '\u{BC}' => '…',
+            '\u{A2}' => '′',
+            SYMBOL_MINUS => '−',
+            SYMBOL_RADICAL => '√',
+            other => other,
+        })
+        .collect()
+}
+
+/// Converts a LaTeX math expression into styled spans based on `base`.
+pub fn typeset(source: &str, base: SpanStyle) -> Vec<Span> {
+    let mut parser = MathParser {
+        chars: source.chars().peekable(),
+    };
+    let mut spans = Vec::new();
+    parser.sequence(&mut spans, base, false);
+    spans
+}
+
+struct MathParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl MathParser<'_> {
+    fn sequence(&mut self, spans: &mut Vec<Span>, style: SpanStyle, in_group: bool) {
+        while let Some(&ch) = self.chars.peek() {
+            if ch == '}' {
+                self.chars.next();
+                if in_group {
+                    return;
+                }
+                continue;
+            }
+            self.atom(spans, style);
+        }
+    }
+
+    fn atom(&mut self, spans: &mut Vec<Span>, style: SpanStyle) {
+        let Some(ch) = self.chars.next() else {
+            return;
+        };
+        match ch {
+            '{' => self.sequence(spans, style, true),
+            '^' => self.atom(spans, scripted(style, Script::Super)),
+            '_' => self.atom(spans, scripted(style, Script::Sub)),
+            '\\' => self.command(spans, style),
+            '\'' => push_span(spans, "\u{A2}", symbol(style)),
+            '-' => {
+                let spaced = !spans.is_empty() && style.script == Script::Normal;
+                self.operator(spans, SYMBOL_MINUS, symbol(style), spaced);
+            }
+            '+' | '=' | '<' | '>' => {
+                let spaced = style.script == Script::Normal;
+                self.operator(spans, ch, upright(style), spaced);
+            }
+            ch if ch.is_whitespace() => {}
+            ch if ch.is_alphabetic() => {
+                push_span(spans, ch.encode_utf8(&mut [0; 4]), italic(style));
+            }
+            ch => push_span(spans, ch.encode_utf8(&mut [0; 4]), upright(style)),
+        }
+    }
+
+    fn operator(&mut self, spans: &mut Vec<Span>, ch: char, style: SpanStyle, spaced: bool) {
+        if spaced {
+            push_span(spans, " ", upright(style));
+        }
+        push_span(spans, ch.encode_utf8(&mut [0; 4]), style);
+        if spaced {
+            push_span(spans, " ", upright(style));
+        }
+    }
+
+    fn command(&mut self, spans: &mut Vec<Span>, style: SpanStyle) {
+        let mut name = String::new();
+        while let Some(&ch) = self.chars.peek() {
+            if !ch.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(ch);
+            self.chars.next();
+        }
+        if name.is_empty() {
+            match self.chars.next() {
+                Some(',' | ':' | ';' | ' ') => push_span(spans, " ", upright(style)),
+                Some('!') | None => {}
+                Some(ch) => push_span(spans, ch.encode_utf8(&mut [0; 4]), upright(style)),
+            }
+            return;
+        }
+
+        if let Some((_, code)) = GREEK.iter().find(|(greek, _)| *greek == name) {
+            push_span(spans, code.encode_utf8(&mut [0; 4]), symbol(style));
+            return;
+        }
+        if let Some((_, code, spaced)) = OPERATORS.iter().find(|(operator, _, _)| *operator == name)
+        {
+            let spaced = *spaced && style.script == Script::Normal;
+            self.operator(spans, *code, symbol(style), spaced);
+            return;
+        }
+        if FUNCTIONS.contains(&name.as_str()) {
+            push_span(spans, &name, upright(style));
+            return;
+        }
+
+        match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = self.argument(style);
+                let denominator = self.argument(style);
+                append_operand(spans, numerator, style);
+                push_span(spans, "/", upright(style));
+                append_operand(spans, denominator, style);
+            }
+            "sqrt" => {
+                let radicand = self.argument(style);
+                push_span(spans, SYMBOL_RADICAL.encode_utf8(&mut [0; 4]), symbol(style));
+                append_operand(spans, radicand, style);
+            }
+            "text" | "mathrm" | "textrm" | "operatorname" => {
+                let text = self.raw_argument();
+                push_span(spans, &text, upright(style));
+            }
+            "mathbf" | "textbf" | "boldsymbol" => {
+                let bold = SpanStyle {
+                    bold: true,
+                    ..style
+                };
+                for span in self.argument(bold) {
+                    push_span(spans, &span.text, span.style);
+                }
+            }
+            "quad" => push_span(spans, "  ", upright(style)),
+            "qquad" => push_span(spans, "    ", upright(style)),
+            "left" | "right" | "displaystyle" | "limits" | "nolimits" | "big" | "Big" => {}
+            other => push_span(spans, &format!("\\{}", other), upright(style)),
+        }
+    }
+
+    /// Parses one argument: a braced group or a single atom.
+    fn argument(&mut self, style: SpanStyle) -> Vec<Span> {
+        while self.chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+            self.chars.next();
+        }
+        let mut spans = Vec::new();
+        self.atom(&mut spans, style);
+        spans
+    }
+
+    /// Reads a braced argument verbatim, for text-mode commands.
+    fn raw_argument(&mut self) -> String {
+        while self.chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+            self.chars.next();
+        }
+        if self.chars.peek() != Some(&'{') {
+            return self.chars.next().map(String::from).unwrap_or_default();
+        }
+        self.chars.next();
+        let mut depth = 1;
+        let mut text = String::new();
+        for ch in self.chars.by_ref() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            text.push(ch);
+        }
+        text
+    }
+}
+
+/// Appends a fraction or radical operand, parenthesised when it has more than one glyph.
+fn append_operand(spans: &mut Vec<Span>, operand: Vec<Span>, style: SpanStyle) {
+    let glyphs: usize = operand
+        .iter()
+        .map(|span| span.text.trim().chars().count())
+        .sum();
+    let wrap = glyphs > 1;
+    if wrap {
+        push_span(spans, "(", upright(style));
+    }
+    for span in operand {
+        push_span(spans, &span.text, span.style);
+    }
+    if wrap {
+        push_span(spans, ")", upright(style));
+    }
+}
+
+fn scripted(style: SpanStyle, script: Script) -> SpanStyle {
+    SpanStyle { script, ..style }
+}
+
+fn italic(style: SpanStyle) -> SpanStyle {
+    SpanStyle {
+        italic: true,
+        symbol: false,
+        ..style
+    }
+}
+
+fn upright(style: SpanStyle) -> SpanStyle {
+    SpanStyle {
+        italic: false,
+        symbol: false,
+        ..style
+    }
+}
+
+fn symbol(style: SpanStyle) -> SpanStyle {
+    SpanStyle {
+        italic: false,
+        symbol: true,
+        ..style
+    }
+}
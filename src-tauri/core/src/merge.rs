@@ -0,0 +1,136 @@
+//! Pages of other PDFs joined to the rendered document, such as a ready-made cover or
+//! legal boilerplate, and PDFs merged into one.
+//!
+//! The pages keep their content, annotations and links to websites. The bookmarks, named
+//! destinations and forms of the documents they come from are left out.
+
+use std::fs;
+use std::path::Path;
+
+use printpdf::lopdf::{Document, Object, ObjectId};
+
+/// Attributes a page takes from the page tree above it when it does not set them itself.
+const INHERITED: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// The PDF at `path`, decrypted when it only has an owner password.
+pub fn load(path: &Path) -> Result<Document, String> {
+    let bytes = fs::read(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let mut doc = Document::load_mem(&bytes)
+        .map_err(|err| format!("{} is not a readable PDF: {err}", path.display()))?;
+    if doc.is_encrypted() {
+        doc.decrypt("")
+            .map_err(|_| format!("{} is protected by a password", path.display()))?;
+        doc.trailer.remove(b"Encrypt");
+    }
+    if doc.get_pages().is_empty() {
+        return Err(format!("{} has no pages", path.display()));
+    }
+    Ok(doc)
+}
+
+/// Places the pages of `before` ahead of the pages of `doc` and the pages of `after`
+/// behind them, each document's pages in order.
+pub fn join_pages(
+    doc: &mut Document,
+    before: &[Document],
+    after: &[Document],
+) -> Result<(), String> {
+    if before.is_empty() && after.is_empty() {
+        return Ok(());
+    }
+    let root = doc
+        .catalog()
+        .and_then(|catalog| catalog.get(b"Pages"))
+        .and_then(Object::as_reference)
+        .map_err(|err| err.to_string())?;
+
+    let leading: Vec<Object> = before
+        .iter()
+        .map(|other| take_pages(doc, other.clone(), root))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    let trailing: Vec<Object> = after
+        .iter()
+        .map(|other| take_pages(doc, other.clone(), root))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+
+    let pages = doc
+        .get_dictionary_mut(root)
+        .map_err(|err| err.to_string())?;
+    let kids = pages
+        .get(b"Kids")
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let count = pages
+        .get(b"Count")
+        .and_then(Object::as_i64)
+        .unwrap_or(kids.len() as i64);
+    let added = (leading.len() + trailing.len()) as i64;
+    pages.set("Kids", [leading, kids, trailing].concat());
+    pages.set("Count", count + added);
+    // The catalogs and page trees of the other documents are no longer referenced.
+    doc.prune_objects();
+    Ok(())
+}
+
+/// The PDFs `documents` as one, the pages of each after those of the one before.
+pub fn merge(mut documents: Vec<Document>) -> Result<Vec<u8>, String> {
+    if documents.is_empty() {
+        return Err("No PDFs to merge".to_string());
+    }
+    let mut doc = documents.remove(0);
+    join_pages(&mut doc, &[], &documents)?;
+    doc.compress();
+    let mut output = Vec::new();
+    doc.save_to(&mut output).map_err(|err| err.to_string())?;
+    Ok(output)
+}
+
+/// Moves the objects of `other` into `doc` and returns references to its pages, each now
+/// a direct child of the page tree node `root`.
+fn take_pages(
+    doc: &mut Document,
+    mut other: Document,
+    root: ObjectId,
+) -> Result<Vec<Object>, String> {
+    other.renumber_objects_with(doc.max_id + 1);
+    let page_ids: Vec<ObjectId> = other.get_pages().into_values().collect();
+    for &page_id in &page_ids {
+        let inherited = inherited_attributes(&other, page_id);
+        let page = other
+            .get_dictionary_mut(page_id)
+            .map_err(|err| err.to_string())?;
+        for (key, value) in inherited {
+            page.set(key, value);
+        }
+        page.set("Parent", Object::Reference(root));
+    }
+    doc.max_id = doc.max_id.max(other.max_id);
+    doc.objects.extend(other.objects);
+    Ok(page_ids.into_iter().map(Object::Reference).collect())
+}
+
+/// The attributes the page `page_id` inherits from the page tree and does not set itself.
+fn inherited_attributes(doc: &Document, page_id: ObjectId) -> Vec<(Vec<u8>, Object)> {
+    let Ok(page) = doc.get_dictionary(page_id) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(Vec<u8>, Object)> = Vec::new();
+    let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+    // A malformed tree may loop back on itself, and no real tree is this deep.
+    for _ in 0..64 {
+        let Some(node) = parent.and_then(|id| doc.get_dictionary(id).ok()) else {
+            break;
+        };
+        for key in INHERITED {
+            let set = page.has(key) || found.iter().any(|(name, _)| name == key);
+            if let (false, Ok(value)) = (set, node.get(key)) {
+                found.push((key.to_vec(), value.clone()));
+            }
+        }
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+    found
+}
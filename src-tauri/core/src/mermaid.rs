@@ -0,0 +1,41 @@
+//! Renders ```mermaid fences to PNG through the mermaid CLI (`mmdc`).
+//!
+//! The CLI is an optional external tool; when it is missing or fails, callers fall back
+//! to printing the diagram source as a regular code block.
+
+use std::fs;
+use std::process::{Command, Stdio};
+
+use tempfile::TempDir;
+
+/// A rendered diagram. The image lives in a temporary directory that is removed on drop.
+pub struct Diagram {
+    dir: TempDir,
+}
+
+impl Diagram {
+    pub fn image_path(&self) -> String {
+        self.dir.path().join("diagram.png").to_string_lossy().to_string()
+    }
+}
+
+pub fn render(source: &str) -> Option<Diagram> {
+    let dir = tempfile::tempdir().ok()?;
+    let input = dir.path().join("diagram.mmd");
+    let output = dir.path().join("diagram.png");
+    fs::write(&input, source).ok()?;
+
+    let status = Command::new("mmdc")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .args(["--backgroundColor", "white", "--quiet"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+
+    (status.success() && output.exists()).then_some(Diagram { dir })
+}
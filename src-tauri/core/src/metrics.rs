@@ -0,0 +1,121 @@
+//! Advance widths of the builtin Helvetica and Times faces, in thousandths of an em.
+//!
+//! The oblique faces share the widths of their upright counterparts, so only the
+//! regular and bold tables are needed; Times italics are close enough to the upright
+//! widths to wrap with them. Characters outside printable ASCII fall back
+//! to an average lowercase width. Courier is monospaced at 600 units per glyph, and
+//! the Symbol font is covered for the glyphs the math typesetter emits.
+
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' '../
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0..?
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @..O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P.._
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // `..o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // p..~
+];
+
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, // ' '../
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, // 0..?
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778, // @..O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, // P.._
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, // `..o
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584, // p..~
+];
+
+const TIMES: [u16; 95] = [
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, // ' '../
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444, // 0..?
+    921, 722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722, // @..O
+    556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611, 333, 278, 333, 469, 500, // P.._
+    333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500, // `..o
+    500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541, // p..~
+];
+
+const TIMES_BOLD: [u16; 95] = [
+    250, 333, 555, 500, 500, 1000, 833, 278, 333, 333, 500, 570, 250, 333, 250, 278, // ' '../
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, // 0..?
+    930, 722, 667, 722, 722, 667, 611, 778, 778, 389, 500, 778, 667, 944, 722, 778, // @..O
+    611, 778, 722, 556, 667, 722, 722, 1000, 722, 722, 667, 333, 278, 333, 581, 500, // P.._
+    333, 500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556, 278, 833, 556, 500, // `..o
+    556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394, 220, 394, 520, // p..~
+];
+
+const FALLBACK_WIDTH: u16 = 556;
+const SERIF_FALLBACK_WIDTH: u16 = 500;
+const COURIER_WIDTH: f32 = 600.0;
+
+pub fn char_width(ch: char, bold: bool) -> u16 {
+    let table = if bold { &HELVETICA_BOLD } else { &HELVETICA };
+    match ch as usize {
+        code @ 32..=126 => table[code - 32],
+        _ => FALLBACK_WIDTH,
+    }
+}
+
+/// Width of `text` set in Helvetica at `font_size`, in points.
+pub fn text_width_pt(text: &str, bold: bool, font_size: f32) -> f32 {
+    let units: u32 = text.chars().map(|ch| u32::from(char_width(ch, bold))).sum();
+    units as f32 * font_size / 1000.0
+}
+
+/// Width of `text` set in Times at `font_size`, in points.
+pub fn serif_text_width_pt(text: &str, bold: bool, font_size: f32) -> f32 {
+    let table = if bold { &TIMES_BOLD } else { &TIMES };
+    let units: u32 = text
+        .chars()
+        .map(|ch| match ch as usize {
+            code @ 32..=126 => u32::from(table[code - 32]),
+            _ => u32::from(SERIF_FALLBACK_WIDTH),
+        })
+        .sum();
+    units as f32 * font_size / 1000.0
+}
+
+/// Width of `text` set in Courier at `font_size`, in points.
+pub fn mono_text_width_pt(text: &str, font_size: f32) -> f32 {
+    text.chars().count() as f32 * COURIER_WIDTH * font_size / 1000.0
+}
+
+/// Advance width of a glyph in the builtin Symbol font, addressed by its code.
+pub fn symbol_char_width(ch: char) -> u16 {
+    match ch {
+        ' ' | '\u{D7}' => 250,
+        '\u{A2}' => 247,
+        '(' | ')' => 333,
+        'i' => 329,
+        'g' => 411,
+        'e' | 't' => 439,
+        '\u{B0}' => 400,
+        'x' => 493,
+        'd' | 'z' | '\u{B6}' => 494,
+        'f' | 'n' | 'q' => 521,
+        'm' | 'u' => 576,
+        'S' => 592,
+        'h' | 's' | 'j' | 'G' | '\u{D9}' | '\u{DA}' => 603,
+        'D' => 612,
+        '\u{A1}' => 620,
+        'a' | 'J' => 631,
+        'X' => 645,
+        'w' | 'y' | 'L' => 686,
+        'Q' => 741,
+        'F' => 763,
+        'P' | 'W' | '\u{C7}' | '\u{C8}' => 768,
+        'Y' => 795,
+        '"' | '\u{A5}' | '\u{B5}' | '\u{C9}' | '\u{CC}' | '\u{CD}' | '\u{CE}' | '\u{CF}'
+        | '\u{D1}' | '\u{E5}' => 713,
+        '\u{C6}' | '\u{D5}' => 823,
+        '\u{AC}' | '\u{AE}' | '\u{DC}' | '\u{DE}' => 987,
+        '\u{BC}' => 1000,
+        '\u{DB}' => 1042,
+        '\u{F2}' => 274,
+        _ => 549,
+    }
+}
+
+/// Width of `text` set in Symbol at `font_size`, in points.
+pub fn symbol_text_width_pt(text: &str, font_size: f32) -> f32 {
+    let units: u32 = text.chars().map(|ch| u32::from(symbol_char_width(ch))).sum();
+    units as f32 * font_size / 1000.0
+}
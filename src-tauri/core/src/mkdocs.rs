@@ -0,0 +1,243 @@
+//! MkDocs sites: the pages the `nav` of a `mkdocs.yml` lists, in site order.
+//!
+//! Pages are in the folder `docs_dir` names, `docs` unless set. A nav entry is a page,
+//! `- Title: page.md` or a bare `- page.md` titled by its first heading, or a section,
+//! `- Title:` over a list of entries, which nests them. Sections have no page of their
+//! own, so their titles are carried by the first page in them. Links to other sites are
+//! left out. Only the block-style YAML MkDocs documents for the nav is read.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+use crate::book::Chapter;
+
+enum Entry {
+    Page { title: Option<String>, path: String },
+    Section { title: String, entries: Vec<Entry> },
+}
+
+/// The `mkdocs.yml` of the site in the folder `root`, if it holds one.
+pub fn config_path(root: &Path) -> Option<PathBuf> {
+    ["mkdocs.yml", "mkdocs.yaml"]
+        .into_iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The pages listed in the nav of the config at `path`, with their paths resolved against
+/// the docs folder, or `None` when it has no nav. A page listed twice is kept where it
+/// first appears.
+pub fn chapters(path: &Path) -> Result<Option<Vec<Chapter>>, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let root = path.parent().unwrap_or(Path::new("."));
+    let mut docs_dir = "docs".to_string();
+    let mut nav = None;
+    let lines: Vec<&str> = contents.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(value) = line.strip_prefix("docs_dir:") {
+            docs_dir = unquote(strip_comment(value).trim());
+        } else if strip_comment(line).trim_end() == "nav:" {
+            nav = Some(nav_items(&lines[index + 1..]));
+        }
+    }
+    let Some(items) = nav else {
+        return Ok(None);
+    };
+
+    let docs = root.join(docs_dir);
+    let mut chapters = Vec::new();
+    flatten(
+        parse(&items, &mut 0),
+        &docs,
+        0,
+        &mut Vec::new(),
+        &mut chapters,
+    );
+    Ok(Some(chapters))
+}
+
+/// The list items of the nav starting at `lines`, with their indentation, until the next
+/// top-level key.
+fn nav_items(lines: &[&str]) -> Vec<(usize, String)> {
+    let mut items: Vec<(usize, String)> = Vec::new();
+    for line in lines {
+        let text = strip_comment(line);
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        let trimmed = text.trim();
+        if let Some(item) = trimmed.strip_prefix('-') {
+            items.push((indent, item.trim().to_string()));
+        } else if indent == 0 {
+            break;
+        } else if let Some((_, last)) = items.last_mut() {
+            // A value continued on the next line.
+            last.push(' ');
+            last.push_str(trimmed);
+        }
+    }
+    items
+}
+
+/// The entries of the list whose first item is at `index`, up to the next item indented
+/// differently.
+fn parse(items: &[(usize, String)], index: &mut usize) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let Some(&(indent, _)) = items.get(*index) else {
+        return entries;
+    };
+    while let Some((item_indent, text)) = items.get(*index) {
+        if *item_indent != indent {
+            break;
+        }
+        *index += 1;
+        let entry = match key_value(text) {
+            Some((title, value)) if value.is_empty() => {
+                let nested = items.get(*index).is_some_and(|(next, _)| *next > indent);
+                let entries = if nested {
+                    parse(items, index)
+                } else {
+                    Vec::new()
+                };
+                Entry::Section { title, entries }
+            }
+            Some((title, value)) => Entry::Page {
+                title: Some(title),
+                path: value,
+            },
+            None => Entry::Page {
+                title: None,
+                path: unquote(text),
+            },
+        };
+        entries.push(entry);
+    }
+    entries
+}
+
+/// Adds the pages of `entries` to `chapters`, the first one carrying the titles of the
+/// `sections` opened just before it.
+fn flatten(
+    entries: Vec<Entry>,
+    docs: &Path,
+    depth: u32,
+    sections: &mut Vec<String>,
+    chapters: &mut Vec<Chapter>,
+) {
+    for entry in entries {
+        match entry {
+            Entry::Page { title, path } => {
+                if path.contains("://") || path.starts_with("mailto:") {
+                    continue;
+                }
+                let file = path.split('#').next().unwrap_or_default();
+                let path = docs.join(file).to_string_lossy().into_owned();
+                if file.is_empty() || chapters.iter().any(|chapter| chapter.path == path) {
+                    continue;
+                }
+                let title = title
+                    .or_else(|| first_heading(Path::new(&path)))
+                    .unwrap_or_else(|| file_title(file));
+                chapters.push(Chapter {
+                    path,
+                    title,
+                    depth,
+                    sections: std::mem::take(sections),
+                });
+            }
+            Entry::Section { title, entries } => {
+                let open = sections.len();
+                sections.push(title);
+                flatten(entries, docs, depth + 1, sections, chapters);
+                // A section without pages is left out.
+                sections.truncate(open);
+            }
+        }
+    }
+}
+
+/// The key and value of a `key: value` item, unquoted, or `None` for a bare value.
+fn key_value(text: &str) -> Option<(String, String)> {
+    let (key, value) = match text.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let end = text[1..].find(quote)? + 1;
+            let value = text[end + 1..].trim_start().strip_prefix(':')?;
+            (&text[1..end], value)
+        }
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .map(|(index, _)| index)
+                .find(|&index| {
+                    text[index + 1..].is_empty() || text[index + 1..].starts_with(' ')
+                })?;
+            (&text[..colon], &text[colon + 1..])
+        }
+    };
+    Some((key.trim().to_string(), unquote(value.trim())))
+}
+
+fn unquote(value: &str) -> String {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// `line` without a trailing `# comment`, unless it is quoted.
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    match line.find(" #") {
+        Some(index) if !line[..index].contains(['"', '\'']) => &line[..index],
+        _ => line,
+    }
+}
+
+/// The text of the first level-1 heading of the page at `path`.
+fn first_heading(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut title: Option<String> = None;
+    for event in Parser::new(&contents) {
+        match event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) => title = Some(String::new()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(title) = title.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) => {
+                return title.filter(|title| !title.trim().is_empty());
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The title MkDocs gives a page without a heading: its file name, dashes and
+/// underscores as spaces and the first letter capitalised.
+fn file_title(file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let words = stem.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => file.to_string(),
+    }
+}
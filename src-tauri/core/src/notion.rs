@@ -0,0 +1,85 @@
+//! Notion markdown exports, read with `RenderOptions::notion_export`.
+//!
+//! Notion names each page `Title <id>.md`, the id being 32 hex digits, keeps the page's
+//! subpages and images in a folder named like the page, and percent-encodes the relative
+//! links between them. Links and images are decoded before they are resolved, and when a
+//! link does not name an exported file exactly, as when folder names lack the id, files
+//! are matched with ids left out. Displayed file names leave the ids out too.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Hex digits in a Notion page id.
+const ID_LEN: usize = 32;
+
+/// `name` without the page id Notion adds to it, before the extension if it has one.
+pub fn strip_id(name: &str) -> String {
+    if let Some(title) = without_id(name) {
+        return title.to_string();
+    }
+    match name.rfind('.') {
+        Some(dot) => match without_id(&name[..dot]) {
+            Some(title) => format!("{title}{}", &name[dot..]),
+            None => name.to_string(),
+        },
+        None => name.to_string(),
+    }
+}
+
+/// `text` without a trailing ` <id>`, if it has one.
+fn without_id(text: &str) -> Option<&str> {
+    let start = text.len().checked_sub(ID_LEN + 1)?;
+    let (title, id) = text.split_at_checked(start)?;
+    let id = id.strip_prefix(' ')?;
+    let is_id = id.bytes().all(|byte| byte.is_ascii_hexdigit());
+    (is_id && !title.trim().is_empty()).then_some(title)
+}
+
+/// `link` with its percent-encoded bytes decoded, or unchanged when they are not UTF-8.
+pub fn decode(link: &str) -> String {
+    let bytes = link.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| link.to_string())
+}
+
+/// The file of `files` that `path` names once ids are left out of both, compared without
+/// regard to case.
+pub fn find<'a>(path: &Path, files: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    let wanted = key(path);
+    files.iter().find(|file| key(file) == wanted)
+}
+
+/// The components of `path` with `..` resolved, lowercased and without ids.
+fn key(path: &Path) -> Vec<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(name) => {
+                parts.push(strip_id(&name.to_string_lossy()).to_lowercase());
+            }
+            other => parts.push(other.as_os_str().to_string_lossy().to_lowercase()),
+        }
+    }
+    parts
+}
@@ -0,0 +1,43 @@
+//! Obsidian vaults, read with `RenderOptions::obsidian_vault`.
+//!
+//! A vault is the folder holding an `.obsidian` settings folder. Its `app.json` names the
+//! folder new attachments are saved in as `attachmentFolderPath`: `/` for the vault
+//! itself, `./` for the note's folder, `./name` for a folder beside the note, and any
+//! other path for a folder in the vault.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSettings {
+    #[serde(default)]
+    attachment_folder_path: String,
+}
+
+/// The vault the file at `path` is in, if it is in one.
+pub fn vault_root(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .skip(1)
+        .find(|folder| folder.join(".obsidian").is_dir())
+        .map(Path::to_path_buf)
+}
+
+/// The attachments folder the vault at `root` configures, `None` when attachments go in
+/// the vault itself or beside each note.
+pub fn attachments_folder(root: &Path) -> Result<Option<String>, String> {
+    let settings = root.join(".obsidian").join("app.json");
+    if !settings.is_file() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&settings).map_err(|err| err.to_string())?;
+    let settings: AppSettings = serde_json::from_str(&contents)
+        .map_err(|err| format!("Invalid {}: {err}", settings.display()))?;
+    let folder = settings.attachment_folder_path;
+    let folder = folder.strip_prefix("./").unwrap_or(&folder);
+    let folder = folder.trim_matches(['/', '\\']);
+    Ok((!folder.is_empty() && folder != ".").then(|| folder.to_string()))
+}
@@ -0,0 +1,31 @@
+//! Page ranges such as `10-25` or `1-3, 8, 12-`, with pages numbered from 1.
+
+use std::ops::RangeInclusive;
+
+/// The pages and ranges listed in `range`, separated by commas. A range left open at the
+/// end runs to the last page, and one left open at the start from the first.
+pub fn parse(range: &str) -> Result<Vec<RangeInclusive<usize>>, String> {
+    let invalid =
+        || format!("Invalid page range {range}; list pages and ranges such as 1-3, 8, 12-");
+    let mut ranges = Vec::new();
+    for part in range
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (first, last) = part.split_once(['-', '–']).unwrap_or((part, part));
+        let page = |text: &str, open: usize| match text.trim() {
+            "" => Ok(open),
+            text => text.parse::<usize>().map_err(|_| invalid()),
+        };
+        let (first, last) = (page(first, 1)?, page(last, usize::MAX)?);
+        if first == 0 || last < first {
+            return Err(invalid());
+        }
+        ranges.push(first..=last);
+    }
+    if ranges.is_empty() {
+        return Err(invalid());
+    }
+    Ok(ranges)
+}
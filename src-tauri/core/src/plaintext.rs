@@ -0,0 +1,314 @@
+//! Plain-text export of markdown, for pasting into tickets and emails.
+//!
+//! Headings are underlined, `=` under level 1 and `-` under the rest, list items are
+//! marked with `-` or their number, code blocks are indented four spaces and quotes are
+//! prefixed with `>`. Emphasis is dropped, links keep their address in parentheses after
+//! their text, images become `[Image: alt]` and tables are laid out in padded columns.
+//! Paragraphs stay on one line so mail clients and trackers can wrap them.
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+use crate::wikilink;
+
+/// The markdown `markdown` as plain text.
+pub fn to_text(markdown: &str) -> String {
+    let mut writer = Writer::default();
+    for event in Parser::new_ext(markdown, crate::MARKDOWN_OPTIONS) {
+        writer.event(event);
+    }
+    writer.finish()
+}
+
+/// `title` underlined with `=` for level 1 and with `-` below it.
+pub fn heading(title: &str, level: u32) -> String {
+    let mark = if level <= 1 { "=" } else { "-" };
+    format!("{title}\n{}", mark.repeat(title.chars().count().max(1)))
+}
+
+/// Text written ahead of each line of a block, such as a list marker.
+struct Prefix {
+    /// Before the block's first line.
+    first: String,
+    /// Before its other lines.
+    rest: String,
+    used: bool,
+}
+
+impl Prefix {
+    fn new(first: String, rest: String) -> Self {
+        Self {
+            first,
+            rest,
+            used: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+    header_rows: usize,
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// Inline text of the block being read.
+    text: String,
+    prefixes: Vec<Prefix>,
+    /// Next number of each open list, `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    /// Whether a blank line goes before the next block.
+    blank: bool,
+    heading: Option<HeadingLevel>,
+    code: Option<String>,
+    /// Alt text of the image being read.
+    image: Option<String>,
+    /// Address of each open link and where its text starts.
+    links: Vec<(String, usize)>,
+    table: Option<Table>,
+}
+
+impl Writer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some(code) = self.code.as_mut() {
+                    code.push_str(&text);
+                } else if let Some(alt) = self.image.as_mut() {
+                    alt.push_str(&text);
+                } else {
+                    for piece in wikilink::segments(&text) {
+                        match piece {
+                            wikilink::Piece::Text(text) => self.text.push_str(text),
+                            wikilink::Piece::Link(link) | wikilink::Piece::Embed(link) => {
+                                self.text.push_str(&link.label);
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Code(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                match self.image.as_mut() {
+                    Some(alt) => alt.push_str(&text),
+                    None => self.text.push_str(&text),
+                }
+            }
+            Event::SoftBreak => self.text.push(' '),
+            Event::HardBreak => self.text.push('\n'),
+            Event::Rule => {
+                self.flush();
+                self.block("----------");
+            }
+            Event::TaskListMarker(checked) => {
+                self.text.push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            Event::FootnoteReference(label) => self.text.push_str(&format!("[{label}]")),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                self.heading = Some(level);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.prefixes
+                    .push(Prefix::new("> ".to_string(), "> ".to_string()));
+            }
+            Tag::CodeBlock(_) => {
+                self.flush();
+                self.code = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush();
+                if !self.lists.is_empty() {
+                    self.blank = false;
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                let indent = " ".repeat(marker.len());
+                self.prefixes.push(Prefix::new(marker, indent));
+            }
+            Tag::FootnoteDefinition(label) => {
+                self.flush();
+                let marker = format!("[{label}] ");
+                self.prefixes.push(Prefix::new(marker, "    ".to_string()));
+            }
+            Tag::DefinitionListTitle => self.flush(),
+            Tag::DefinitionListDefinition => {
+                self.flush();
+                self.blank = false;
+                self.prefixes
+                    .push(Prefix::new("    ".to_string(), "    ".to_string()));
+            }
+            Tag::Table(_) => {
+                self.flush();
+                self.table = Some(Table::default());
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::Link { dest_url, .. } => {
+                self.links.push((dest_url.to_string(), self.text.len()));
+            }
+            Tag::Image { .. } => self.image = Some(String::new()),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::DefinitionListTitle => self.flush(),
+            TagEnd::Heading(level) => {
+                let title = std::mem::take(&mut self.text);
+                self.heading = None;
+                let level = if level == HeadingLevel::H1 { 1 } else { 2 };
+                self.block(&heading(title.trim(), level));
+            }
+            TagEnd::Item => {
+                self.flush();
+                self.prefixes.pop();
+                // Items follow one another without blank lines.
+                self.blank = false;
+            }
+            TagEnd::BlockQuote(_)
+            | TagEnd::FootnoteDefinition
+            | TagEnd::DefinitionListDefinition => {
+                self.flush();
+                self.prefixes.pop();
+            }
+            TagEnd::CodeBlock => {
+                let code = self.code.take().unwrap_or_default();
+                let indented: Vec<String> = code
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(|line| format!("    {line}"))
+                    .collect();
+                self.block(&indented.join("\n"));
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                self.blank = true;
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.text);
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(cell.trim().replace('\n', " "));
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.block(&layout_table(&table));
+                }
+            }
+            TagEnd::Link => {
+                if let Some((dest, start)) = self.links.pop() {
+                    let label = self.text[start..].trim().to_string();
+                    if !dest.is_empty() && !dest.starts_with('#') && label != dest {
+                        self.text.push_str(&format!(" ({dest})"));
+                    }
+                }
+            }
+            TagEnd::Image => {
+                let alt = self.image.take().unwrap_or_default();
+                match alt.trim() {
+                    "" => self.text.push_str("[Image]"),
+                    alt => self.text.push_str(&format!("[Image: {alt}]")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes the inline text read so far as a block.
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.text);
+        if !text.trim().is_empty() && self.heading.is_none() {
+            self.block(text.trim());
+        }
+    }
+
+    /// Writes `content` with the open prefixes, after a blank line when one is due.
+    fn block(&mut self, content: &str) {
+        if self.blank && !self.out.is_empty() {
+            let blank: String = self
+                .prefixes
+                .iter()
+                .filter(|prefix| prefix.used)
+                .map(|prefix| prefix.rest.as_str())
+                .collect();
+            self.out.push_str(blank.trim_end());
+            self.out.push('\n');
+        }
+        for line in content.lines() {
+            for prefix in &mut self.prefixes {
+                self.out.push_str(if prefix.used {
+                    &prefix.rest
+                } else {
+                    &prefix.first
+                });
+                prefix.used = true;
+            }
+            self.out.push_str(line.trim_end());
+            self.out.push('\n');
+        }
+        self.blank = true;
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        let mut out = self.out.trim_end().to_string();
+        out.push('\n');
+        out
+    }
+}
+
+/// The rows of `table` in columns padded to their widest cell, a rule under the header.
+fn layout_table(table: &Table) -> String {
+    let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in &table.rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
+    }
+    let mut lines = Vec::new();
+    for (index, row) in table.rows.iter().enumerate() {
+        let cells: Vec<String> = (0..columns)
+            .map(|column| {
+                let cell = row.get(column).map_or("", String::as_str);
+                let padding = widths[column] - cell.chars().count();
+                format!("{cell}{}", " ".repeat(padding))
+            })
+            .collect();
+        lines.push(cells.join(" | ").trim_end().to_string());
+        if index + 1 == table.header_rows {
+            let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            lines.push(rule.join("-+-"));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,851 @@
+//! Edits applied to the saved PDF for structures printpdf cannot express while the
+//! document is still being laid out, such as links that point at other pages.
+
+use std::collections::{BTreeMap, HashMap};
+
+use printpdf::lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use sha2::{Digest, Sha256};
+
+use crate::encryption::{self, Encryption};
+use crate::icc;
+use crate::linearize;
+use crate::merge;
+use crate::tagging::{Element, Kid};
+
+/// Name of the graphics state that sets the watermark's opacity on every page.
+pub const WATERMARK_STATE: &str = "Watermark";
+
+/// Everything the renderer wants changed in the saved document.
+#[derive(Clone, Default)]
+pub struct Edits {
+    pub internal_links: Vec<InternalLink>,
+    /// printpdf tags every builtin font with WinAnsiEncoding, which remaps the Symbol
+    /// font's glyphs; the font must use its own builtin encoding instead.
+    pub restore_symbol_encoding: bool,
+    /// Bookmarks in document order; printpdf can only write a flat list.
+    pub outline: Vec<OutlineItem>,
+    /// printpdf writes the info dictionary as raw UTF-8 and has no XMP author field.
+    pub metadata: Option<Metadata>,
+    /// Applies the PDF/A-2b requirements printpdf does not meet on its own.
+    pub pdf_a: bool,
+    /// Applied last, once nothing else will change.
+    pub encryption: Option<Encryption>,
+    /// printpdf cannot add a graphics state with a fill opacity to a page.
+    pub watermark_opacity: Option<f32>,
+    /// printpdf writes soft masks inline with the wrong height.
+    pub soft_masks: Vec<SoftMask>,
+    /// Files embedded in the document; printpdf cannot embed files.
+    pub attachments: Vec<Attachment>,
+    /// Structure elements of a tagged document, the root first; printpdf cannot tag
+    /// content.
+    pub structure: Vec<Element>,
+    /// Natural language of the text, such as `en`.
+    pub language: Option<String>,
+    /// Writes the file linearized; lopdf writes objects in number order.
+    pub linearize: bool,
+    /// Zero-based indices of pages to turn to landscape; printpdf cannot resize a page.
+    pub turned_pages: Vec<usize>,
+    /// Documents whose pages go before the rendered ones, joined once the edits that
+    /// count pages are done.
+    pub pages_before: Vec<Document>,
+    /// Documents whose pages go after the rendered ones.
+    pub pages_after: Vec<Document>,
+    /// Zero-based indices, in order, of rendered pages left out; see `remove_pages`.
+    pub removed_pages: Vec<usize>,
+}
+
+impl Edits {
+    pub fn is_empty(&self) -> bool {
+        self.internal_links.is_empty()
+            && !self.restore_symbol_encoding
+            && self.outline.is_empty()
+            && self.metadata.is_none()
+            && !self.pdf_a
+            && self.encryption.is_none()
+            && self.watermark_opacity.is_none()
+            && self.soft_masks.is_empty()
+            && self.attachments.is_empty()
+            && self.structure.is_empty()
+            && self.language.is_none()
+            && !self.linearize
+            && self.turned_pages.is_empty()
+            && self.pages_before.is_empty()
+            && self.pages_after.is_empty()
+            && self.removed_pages.is_empty()
+    }
+
+    /// Leaves the pages `removed` out of the document, dropping the edits made on them or
+    /// pointing at them and moving the others to the pages' new indices.
+    pub fn remove_pages(&mut self, mut removed: Vec<usize>) {
+        removed.sort_unstable();
+        removed.dedup();
+        let index = |page: usize| match removed.binary_search(&page) {
+            Ok(_) => None,
+            Err(before) => Some(page - before),
+        };
+        self.internal_links.retain_mut(|link| {
+            match (index(link.page), index(link.target_page)) {
+                (Some(page), Some(target_page)) => {
+                    link.page = page;
+                    link.target_page = target_page;
+                    true
+                }
+                _ => false,
+            }
+        });
+        self.outline.retain_mut(|item| {
+            index(item.page).map(|page| item.page = page).is_some()
+        });
+        self.soft_masks.retain_mut(|mask| {
+            index(mask.page).map(|page| mask.page = page).is_some()
+        });
+        self.turned_pages = self.turned_pages.iter().filter_map(|&page| index(page)).collect();
+        for element in &mut self.structure {
+            element.kids.retain_mut(|kid| match kid {
+                Kid::Content { page, .. } => index(*page).map(|index| *page = index).is_some(),
+                Kid::Element(_) => true,
+            });
+        }
+        self.removed_pages = removed;
+    }
+}
+
+/// Document properties written to both the info dictionary and an XMP packet.
+#[derive(Clone)]
+pub struct Metadata {
+    pub title: String,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Vec<String>,
+    /// Application that produced the source document.
+    pub creator: String,
+}
+
+/// One bookmark. Items nest under the closest preceding item with a lower level.
+#[derive(Clone)]
+pub struct OutlineItem {
+    pub level: u32,
+    pub title: String,
+    /// Zero-based index of the page the bookmark opens.
+    pub page: usize,
+    /// Vertical scroll position on that page, in points.
+    pub top: f32,
+}
+
+/// A link annotation whose target page is only known once layout has finished.
+#[derive(Clone)]
+pub struct InternalLink {
+    /// Zero-based index of the page carrying the annotation.
+    pub page: usize,
+    /// Annotation rectangle in points: lower-left x/y, upper-right x/y.
+    pub rect: [f32; 4],
+    /// Zero-based index of the page the link jumps to.
+    pub target_page: usize,
+    /// Vertical scroll position on the target page, in points.
+    pub target_top: f32,
+}
+
+/// Transparency for one drawn image.
+#[derive(Clone)]
+pub struct SoftMask {
+    /// Zero-based index of the page the image is drawn on.
+    pub page: usize,
+    /// Name of the image in the page's XObject resources.
+    pub xobject: String,
+    pub width_px: u32,
+    pub height_px: u32,
+    /// One opacity byte per pixel.
+    pub alpha: Vec<u8>,
+}
+
+/// A file carried inside the PDF, listed in the reader's attachments panel.
+#[derive(Clone)]
+pub struct Attachment {
+    /// File name shown to the reader, possibly with folders separated by `/`.
+    pub name: String,
+    pub mime_type: String,
+    pub contents: Vec<u8>,
+}
+
+pub fn apply(bytes: &[u8], edits: &Edits) -> Result<Vec<u8>, String> {
+    let mut doc = Document::load_mem(bytes).map_err(|err| err.to_string())?;
+    if !edits.removed_pages.is_empty() {
+        // lopdf numbers pages from 1.
+        let numbers: Vec<u32> = edits.removed_pages.iter().map(|&page| page as u32 + 1).collect();
+        doc.delete_pages(&numbers);
+        doc.prune_objects();
+    }
+    turn_pages(&mut doc, &edits.turned_pages)?;
+    add_internal_links(&mut doc, &edits.internal_links)?;
+    if edits.restore_symbol_encoding {
+        restore_symbol_encoding(&mut doc);
+    }
+    if !edits.outline.is_empty() {
+        add_outline(&mut doc, &edits.outline)?;
+    }
+    if let Some(metadata) = &edits.metadata {
+        set_metadata(&mut doc, metadata, edits.pdf_a)?;
+    }
+    if let Some(opacity) = edits.watermark_opacity {
+        add_watermark_state(&mut doc, opacity)?;
+    }
+    add_soft_masks(&mut doc, &edits.soft_masks)?;
+    if !edits.attachments.is_empty() {
+        add_attachments(&mut doc, &edits.attachments)?;
+    }
+    if !edits.structure.is_empty() {
+        add_structure_tree(&mut doc, &edits.structure)?;
+    }
+    if let Some(language) = &edits.language {
+        let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+        catalog.set("Lang", text_string(language));
+    }
+    merge::join_pages(&mut doc, &edits.pages_before, &edits.pages_after)?;
+    // printpdf embeds an image again every time it is drawn.
+    share_images(&mut doc);
+    if edits.pdf_a {
+        make_archival(&mut doc)?;
+    }
+    // printpdf leaves streams uncompressed in debug builds, decoded images included.
+    doc.compress();
+    if let Some(settings) = &edits.encryption {
+        encryption::encrypt(&mut doc, settings)?;
+    }
+
+    if edits.linearize {
+        return linearize::write(&doc);
+    }
+    let mut output = Vec::new();
+    doc.save_to(&mut output).map_err(|err| err.to_string())?;
+    Ok(output)
+}
+
+fn add_internal_links(doc: &mut Document, links: &[InternalLink]) -> Result<(), String> {
+    let pages: Vec<_> = doc.get_pages().into_values().collect();
+
+    for link in links {
+        let (Some(&page_id), Some(&target_id)) = (pages.get(link.page), pages.get(link.target_page))
+        else {
+            continue;
+        };
+
+        let mut annotation = Dictionary::new();
+        annotation.set("Type", Object::Name(b"Annot".to_vec()));
+        annotation.set("Subtype", Object::Name(b"Link".to_vec()));
+        annotation.set(
+            "Rect",
+            Object::Array(link.rect.iter().map(|value| Object::Real(*value)).collect()),
+        );
+        annotation.set(
+            "Border",
+            Object::Array(vec![Object::Integer(0), Object::Integer(0), Object::Integer(0)]),
+        );
+        annotation.set("Dest", destination(target_id, link.target_top));
+        let annotation_id = doc.add_object(annotation);
+
+        let page = doc
+            .get_dictionary_mut(page_id)
+            .map_err(|err| err.to_string())?;
+        match page.get_mut(b"Annots") {
+            Ok(Object::Array(annotations)) => annotations.push(Object::Reference(annotation_id)),
+            _ => page.set("Annots", vec![Object::Reference(annotation_id)]),
+        }
+    }
+
+    Ok(())
+}
+
+fn restore_symbol_encoding(doc: &mut Document) {
+    for object in doc.objects.values_mut() {
+        if let Object::Dictionary(dict) = object {
+            let is_symbol = matches!(dict.get(b"BaseFont"), Ok(Object::Name(name)) if name == b"Symbol");
+            if dict.type_is(b"Font") && is_symbol {
+                dict.remove(b"Encoding");
+            }
+        }
+    }
+}
+
+/// Builds the nested bookmark tree. Top-level items start expanded, deeper ones collapsed.
+fn add_outline(doc: &mut Document, items: &[OutlineItem]) -> Result<(), String> {
+    let pages: Vec<_> = doc.get_pages().into_values().collect();
+    let root_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = items.iter().map(|_| doc.new_object_id()).collect();
+
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(items.len());
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
+    let mut roots: Vec<usize> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        while stack.last().is_some_and(|&open| items[open].level >= item.level) {
+            stack.pop();
+        }
+        let parent = stack.last().copied();
+        match parent {
+            Some(parent) => children[parent].push(index),
+            None => roots.push(index),
+        }
+        parents.push(parent);
+        stack.push(index);
+    }
+
+    let siblings_of = |index: usize| match parents[index] {
+        Some(parent) => &children[parent],
+        None => &roots,
+    };
+    for (index, item) in items.iter().enumerate() {
+        let mut entry = Dictionary::new();
+        entry.set("Title", text_string(&item.title));
+        entry.set(
+            "Parent",
+            Object::Reference(parents[index].map_or(root_id, |parent| ids[parent])),
+        );
+        if let Some(&page_id) = pages.get(item.page) {
+            entry.set("Dest", destination(page_id, item.top));
+        }
+
+        let siblings = siblings_of(index);
+        let position = siblings.iter().position(|&sibling| sibling == index).unwrap_or(0);
+        if position > 0 {
+            entry.set("Prev", Object::Reference(ids[siblings[position - 1]]));
+        }
+        if let Some(&next) = siblings.get(position + 1) {
+            entry.set("Next", Object::Reference(ids[next]));
+        }
+        if let (Some(&first), Some(&last)) = (children[index].first(), children[index].last()) {
+            entry.set("First", Object::Reference(ids[first]));
+            entry.set("Last", Object::Reference(ids[last]));
+            let count = children[index].len() as i64;
+            entry.set("Count", if parents[index].is_none() { count } else { -count });
+        }
+        doc.objects.insert(ids[index], Object::Dictionary(entry));
+    }
+
+    let mut root = Dictionary::new();
+    root.set("Type", Object::Name(b"Outlines".to_vec()));
+    if let (Some(&first), Some(&last)) = (roots.first(), roots.last()) {
+        root.set("First", Object::Reference(ids[first]));
+        root.set("Last", Object::Reference(ids[last]));
+    }
+    let visible: usize = roots.iter().map(|&index| 1 + children[index].len()).sum();
+    root.set("Count", visible as i64);
+    doc.objects.insert(root_id, Object::Dictionary(root));
+
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    catalog.set("Outlines", Object::Reference(root_id));
+    catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+    Ok(())
+}
+
+fn set_metadata(doc: &mut Document, metadata: &Metadata, pdf_a: bool) -> Result<(), String> {
+    let keywords = metadata.keywords.join(", ");
+    let fields = [
+        ("Title", Some(metadata.title.as_str())),
+        ("Author", metadata.author.as_deref()),
+        ("Subject", metadata.subject.as_deref()),
+        ("Keywords", Some(keywords.as_str()).filter(|keywords| !keywords.is_empty())),
+        ("Creator", Some(metadata.creator.as_str())),
+    ];
+    let info_id = match doc.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => *id,
+        _ => {
+            let id = doc.add_object(Dictionary::new());
+            doc.trailer.set("Info", Object::Reference(id));
+            id
+        }
+    };
+    let info = doc
+        .get_dictionary_mut(info_id)
+        .map_err(|err| err.to_string())?;
+    for (key, value) in fields {
+        match value {
+            Some(value) => info.set(key, text_string(value)),
+            None => {
+                info.remove(key.as_bytes());
+            }
+        }
+    }
+    // Identifier and GTS_PDFXVersion only mean something for PDF/X output, and PDF/A
+    // wants every remaining entry mirrored in the XMP packet.
+    info.remove(b"Identifier");
+    info.remove(b"GTS_PDFXVersion");
+    info.remove(b"Trapped");
+    if matches!(info.get(b"Producer"), Ok(Object::String(producer, _)) if producer.is_empty()) {
+        info.remove(b"Producer");
+    }
+    let dates = [b"CreationDate".as_slice(), b"ModDate"].map(|key| match info.get(key) {
+        Ok(Object::String(date, _)) => xmp_date(&String::from_utf8_lossy(date)),
+        _ => None,
+    });
+
+    let mut dict = Dictionary::new();
+    dict.set("Type", Object::Name(b"Metadata".to_vec()));
+    dict.set("Subtype", Object::Name(b"XML".to_vec()));
+    let packet = xmp_packet(metadata, dates, pdf_a);
+    let stream_id = doc.add_object(Stream::new(dict, packet.into_bytes()));
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    catalog.set("Metadata", Object::Reference(stream_id));
+    Ok(())
+}
+
+fn xmp_packet(
+    metadata: &Metadata,
+    [created, modified]: [Option<String>; 2],
+    pdf_a: bool,
+) -> String {
+    let mut properties = format!(
+        "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+        xml_escape(&metadata.title)
+    );
+    if let Some(author) = &metadata.author {
+        properties.push_str(&format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+            xml_escape(author)
+        ));
+    }
+    if let Some(subject) = &metadata.subject {
+        properties.push_str(&format!(
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n",
+            xml_escape(subject)
+        ));
+    }
+    if !metadata.keywords.is_empty() {
+        let items: String = metadata
+            .keywords
+            .iter()
+            .map(|keyword| format!("<rdf:li>{}</rdf:li>", xml_escape(keyword)))
+            .collect();
+        properties.push_str(&format!(
+            "<dc:subject><rdf:Bag>{items}</rdf:Bag></dc:subject>\n<pdf:Keywords>{}</pdf:Keywords>\n",
+            xml_escape(&metadata.keywords.join(", "))
+        ));
+    }
+    properties.push_str(&format!(
+        "<xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+        xml_escape(&metadata.creator)
+    ));
+    if let Some(created) = created {
+        properties.push_str(&format!("<xmp:CreateDate>{created}</xmp:CreateDate>\n"));
+    }
+    if let Some(modified) = modified {
+        properties.push_str(&format!("<xmp:ModifyDate>{modified}</xmp:ModifyDate>\n"));
+    }
+    if pdf_a {
+        properties.push_str("<pdfaid:part>2</pdfaid:part>\n");
+        properties.push_str("<pdfaid:conformance>B</pdfaid:conformance>\n");
+    }
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         <rdf:Description rdf:about=\"\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\" \
+         xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" \
+         xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n\
+         {properties}\
+         </rdf:Description>\n\
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>"
+    )
+}
+
+/// Converts an info dictionary date such as `D:20240501120000+02'00'` to XMP's
+/// `2024-05-01T12:00:00+02:00`.
+fn xmp_date(date: &str) -> Option<String> {
+    let digits = date.strip_prefix("D:")?;
+    let part = |range: std::ops::Range<usize>| {
+        digits
+            .get(range)
+            .filter(|part| part.bytes().all(|byte| byte.is_ascii_digit()))
+    };
+    let (year, month, day) = (part(0..4)?, part(4..6)?, part(6..8)?);
+    let (hour, minute, second) = (part(8..10)?, part(10..12)?, part(12..14)?);
+    let zone = match digits.get(14..) {
+        Some(zone) if zone.starts_with(['+', '-']) => {
+            let zone = zone.replace('\'', "");
+            format!("{}:{}", zone.get(..3)?, zone.get(3..5)?)
+        }
+        _ => "Z".to_string(),
+    };
+    Some(format!("{year}-{month}-{day}T{hour}:{minute}:{second}{zone}"))
+}
+
+/// Adds the graphics state the watermark drawing refers to to every page's resources.
+fn add_watermark_state(doc: &mut Document, opacity: f32) -> Result<(), String> {
+    let mut state = Dictionary::new();
+    state.set("Type", Object::Name(b"ExtGState".to_vec()));
+    state.set("ca", Object::Real(opacity));
+    state.set("CA", Object::Real(opacity));
+    let state_id = doc.add_object(state);
+
+    let pages: Vec<_> = doc.get_pages().into_values().collect();
+    for page_id in pages {
+        let page = doc.get_dictionary(page_id).map_err(|err| err.to_string())?;
+        // printpdf writes each page's resources as a separate object.
+        let Ok(resources_id) = page.get(b"Resources").and_then(Object::as_reference) else {
+            continue;
+        };
+        let resources = doc
+            .get_dictionary_mut(resources_id)
+            .map_err(|err| err.to_string())?;
+        match resources.get_mut(b"ExtGState") {
+            Ok(Object::Dictionary(states)) => {
+                states.set(WATERMARK_STATE, Object::Reference(state_id));
+            }
+            _ => {
+                let mut states = Dictionary::new();
+                states.set(WATERMARK_STATE, Object::Reference(state_id));
+                resources.set("ExtGState", states);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Swaps the width and height of each page's boxes.
+fn turn_pages(doc: &mut Document, indices: &[usize]) -> Result<(), String> {
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for &index in indices {
+        let page_id = *pages
+            .get(index)
+            .ok_or_else(|| format!("Cannot turn missing page {}", index + 1))?;
+        let page = doc.get_dictionary_mut(page_id).map_err(|err| err.to_string())?;
+        for key in [&b"MediaBox"[..], b"TrimBox", b"CropBox"] {
+            if let Ok(Object::Array(rect)) = page.get_mut(key) {
+                if rect.len() == 4 {
+                    rect.swap(2, 3);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Adds each mask as a greyscale image and points the masked image at it.
+fn add_soft_masks(doc: &mut Document, masks: &[SoftMask]) -> Result<(), String> {
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for mask in masks {
+        let page_id = *pages
+            .get(mask.page)
+            .ok_or_else(|| format!("Soft mask on missing page {}", mask.page + 1))?;
+        let page = doc.get_dictionary(page_id).map_err(|err| err.to_string())?;
+        let resources = page
+            .get(b"Resources")
+            .and_then(Object::as_reference)
+            .and_then(|id| doc.get_dictionary(id))
+            .map_err(|err| err.to_string())?;
+        let image_id = resources
+            .get(b"XObject")
+            .and_then(Object::as_dict)
+            .and_then(|xobjects| xobjects.get(mask.xobject.as_bytes()))
+            .and_then(Object::as_reference)
+            .map_err(|err| err.to_string())?;
+
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"XObject".to_vec()));
+        dict.set("Subtype", Object::Name(b"Image".to_vec()));
+        dict.set("Width", Object::Integer(i64::from(mask.width_px)));
+        dict.set("Height", Object::Integer(i64::from(mask.height_px)));
+        dict.set("ColorSpace", Object::Name(b"DeviceGray".to_vec()));
+        dict.set("BitsPerComponent", Object::Integer(8));
+        let mask_id = doc.add_object(Stream::new(dict, mask.alpha.clone()));
+
+        let image = doc
+            .get_object_mut(image_id)
+            .and_then(Object::as_stream_mut)
+            .map_err(|err| err.to_string())?;
+        image.dict.set("SMask", Object::Reference(mask_id));
+    }
+    Ok(())
+}
+
+/// Embeds the files and lists them in the catalog's name tree of embedded files.
+fn add_attachments(doc: &mut Document, attachments: &[Attachment]) -> Result<(), String> {
+    let mut entries = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        let mut params = Dictionary::new();
+        params.set("Size", Object::Integer(attachment.contents.len() as i64));
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"EmbeddedFile".to_vec()));
+        dict.set("Subtype", Object::Name(attachment.mime_type.as_bytes().to_vec()));
+        dict.set("Params", params);
+        let file_id = doc.add_object(Stream::new(dict, attachment.contents.clone()));
+
+        let mut embedded = Dictionary::new();
+        embedded.set("F", Object::Reference(file_id));
+        embedded.set("UF", Object::Reference(file_id));
+        let mut spec = Dictionary::new();
+        spec.set("Type", Object::Name(b"Filespec".to_vec()));
+        spec.set("F", text_string(&attachment.name));
+        spec.set("UF", text_string(&attachment.name));
+        spec.set("EF", embedded);
+        spec.set("AFRelationship", Object::Name(b"Source".to_vec()));
+        let spec_id = doc.add_object(spec);
+        entries.push((text_string(&attachment.name), spec_id));
+    }
+
+    // Name trees must be sorted by the bytes of their keys.
+    entries.sort_by(|(a, _), (b, _)| a.as_str().ok().cmp(&b.as_str().ok()));
+    let mut names = Vec::with_capacity(entries.len() * 2);
+    for (key, spec_id) in entries {
+        names.push(key);
+        names.push(Object::Reference(spec_id));
+    }
+    let mut tree = Dictionary::new();
+    tree.set("Names", names);
+
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    match catalog.get_mut(b"Names") {
+        Ok(Object::Dictionary(name_trees)) => name_trees.set("EmbeddedFiles", tree),
+        _ => {
+            let mut name_trees = Dictionary::new();
+            name_trees.set("EmbeddedFiles", tree);
+            catalog.set("Names", name_trees);
+        }
+    }
+    Ok(())
+}
+
+/// Writes the structure tree and the parent tree that maps each page's marked content
+/// back to its elements, and marks the document as tagged.
+fn add_structure_tree(doc: &mut Document, elements: &[Element]) -> Result<(), String> {
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let root_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = elements.iter().map(|_| doc.new_object_id()).collect();
+    let mut parents = vec![root_id; elements.len()];
+    for (index, element) in elements.iter().enumerate() {
+        for kid in &element.kids {
+            if let Kid::Element(child) = kid {
+                parents[*child] = ids[index];
+            }
+        }
+    }
+
+    // The element owning each marked-content number, page by page.
+    let mut owners: Vec<Vec<Object>> = vec![Vec::new(); pages.len()];
+    for (index, element) in elements.iter().enumerate() {
+        let first_page = element.kids.iter().find_map(|kid| match kid {
+            Kid::Content { page, .. } => Some(*page),
+            Kid::Element(_) => None,
+        });
+        let mut kids = Vec::with_capacity(element.kids.len());
+        for kid in &element.kids {
+            match *kid {
+                Kid::Element(child) => kids.push(Object::Reference(ids[child])),
+                Kid::Content { page, mcid } => {
+                    let Some(&page_id) = pages.get(page) else {
+                        continue;
+                    };
+                    let page_owners = &mut owners[page];
+                    if page_owners.len() <= mcid as usize {
+                        page_owners.resize(mcid as usize + 1, Object::Null);
+                    }
+                    page_owners[mcid as usize] = Object::Reference(ids[index]);
+                    // Content on another page than the element's own needs a full reference.
+                    if Some(page) == first_page {
+                        kids.push(Object::Integer(mcid.into()));
+                    } else {
+                        let mut reference = Dictionary::new();
+                        reference.set("Type", Object::Name(b"MCR".to_vec()));
+                        reference.set("Pg", Object::Reference(page_id));
+                        reference.set("MCID", Object::Integer(mcid.into()));
+                        kids.push(Object::Dictionary(reference));
+                    }
+                }
+            }
+        }
+
+        let mut dict = Dictionary::new();
+        dict.set("Type", Object::Name(b"StructElem".to_vec()));
+        dict.set("S", Object::Name(element.role.as_bytes().to_vec()));
+        dict.set("P", Object::Reference(parents[index]));
+        if let Some(&page_id) = first_page.and_then(|page| pages.get(page)) {
+            dict.set("Pg", Object::Reference(page_id));
+        }
+        if let Some(alt) = &element.alt {
+            dict.set("Alt", text_string(alt));
+        }
+        dict.set("K", kids);
+        doc.objects.insert(ids[index], Object::Dictionary(dict));
+    }
+
+    let mut nums = Vec::with_capacity(pages.len() * 2);
+    for (index, (&page_id, page_owners)) in pages.iter().zip(owners).enumerate() {
+        let owners_id = doc.add_object(Object::Array(page_owners));
+        nums.push(Object::Integer(index as i64));
+        nums.push(Object::Reference(owners_id));
+        let page = doc
+            .get_dictionary_mut(page_id)
+            .map_err(|err| err.to_string())?;
+        page.set("StructParents", Object::Integer(index as i64));
+        // Tab through links and fields in structure order.
+        page.set("Tabs", Object::Name(b"S".to_vec()));
+    }
+    let mut parent_tree = Dictionary::new();
+    parent_tree.set("Nums", nums);
+
+    let mut root = Dictionary::new();
+    root.set("Type", Object::Name(b"StructTreeRoot".to_vec()));
+    root.set("K", Object::Reference(ids[0]));
+    root.set("ParentTree", parent_tree);
+    root.set("ParentTreeNextKey", Object::Integer(pages.len() as i64));
+    doc.objects.insert(root_id, Object::Dictionary(root));
+
+    let mut mark_info = Dictionary::new();
+    mark_info.set("Marked", Object::Boolean(true));
+    let mut preferences = Dictionary::new();
+    preferences.set("DisplayDocTitle", Object::Boolean(true));
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    catalog.set("StructTreeRoot", Object::Reference(root_id));
+    catalog.set("MarkInfo", mark_info);
+    catalog.set("ViewerPreferences", preferences);
+    Ok(())
+}
+
+/// Replaces identical image streams with one shared copy.
+fn share_images(doc: &mut Document) {
+    // Masks are merged in the first pass, which can make the images using them identical.
+    loop {
+        let mut first: HashMap<(String, [u8; 32]), ObjectId> = HashMap::new();
+        let mut replaced: BTreeMap<ObjectId, ObjectId> = BTreeMap::new();
+        for (&id, object) in &doc.objects {
+            let Object::Stream(stream) = object else {
+                continue;
+            };
+            if !matches!(stream.dict.get(b"Subtype"), Ok(Object::Name(name)) if name == b"Image")
+            {
+                continue;
+            }
+            let key = (
+                format!("{:?}", stream.dict),
+                Sha256::digest(&stream.content).into(),
+            );
+            match first.get(&key) {
+                Some(&kept) => {
+                    replaced.insert(id, kept);
+                }
+                None => {
+                    first.insert(key, id);
+                }
+            }
+        }
+        if replaced.is_empty() {
+            return;
+        }
+        for id in replaced.keys() {
+            doc.objects.remove(id);
+        }
+        for object in doc.objects.values_mut() {
+            redirect_references(object, &replaced);
+        }
+    }
+}
+
+fn redirect_references(object: &mut Object, replaced: &BTreeMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if let Some(&kept) = replaced.get(id) {
+                *id = kept;
+            }
+        }
+        Object::Array(items) => {
+            for item in items {
+                redirect_references(item, replaced);
+            }
+        }
+        Object::Dictionary(dict) => {
+            for (_, value) in dict.iter_mut() {
+                redirect_references(value, replaced);
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in stream.dict.iter_mut() {
+                redirect_references(value, replaced);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Fixes what printpdf leaves out of PDF/A-2b: an sRGB output intent, print flags on
+/// annotations, names on optional content configurations, CID-to-glyph maps on
+/// embedded fonts and the binary marker comment after the header.
+fn make_archival(doc: &mut Document) -> Result<(), String> {
+    // The writer prints the version straight after `%PDF-`; these characters become
+    // the required comment of bytes above 127 on the next line.
+    doc.version = "1.7\n%\u{e2}\u{e3}\u{cf}\u{d3}".to_string();
+
+    for object in doc.objects.values_mut() {
+        let Object::Dictionary(dict) = object else {
+            continue;
+        };
+        if dict.type_is(b"Annot") {
+            // Print flag, and none of the hiding ones.
+            dict.set("F", 4);
+        }
+        // printpdf writes each CID font inline in its Type0 font's DescendantFonts.
+        if let Ok(Object::Array(descendants)) = dict.get_mut(b"DescendantFonts") {
+            for descendant in descendants {
+                if let Object::Dictionary(font) = descendant {
+                    if !font.has(b"CIDToGIDMap") {
+                        font.set("CIDToGIDMap", Object::Name(b"Identity".to_vec()));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut profile_dict = Dictionary::new();
+    profile_dict.set("N", 3);
+    let profile_id = doc.add_object(Stream::new(profile_dict, icc::srgb_profile()));
+    let mut intent = Dictionary::new();
+    intent.set("Type", Object::Name(b"OutputIntent".to_vec()));
+    intent.set("S", Object::Name(b"GTS_PDFA1".to_vec()));
+    intent.set("OutputConditionIdentifier", text_string(icc::DESCRIPTION));
+    intent.set("Info", text_string(icc::DESCRIPTION));
+    intent.set("DestOutputProfile", Object::Reference(profile_id));
+
+    let catalog = doc.catalog_mut().map_err(|err| err.to_string())?;
+    catalog.set("OutputIntents", vec![Object::Dictionary(intent)]);
+    if let Ok(Object::Dictionary(properties)) = catalog.get_mut(b"OCProperties") {
+        if let Ok(Object::Dictionary(config)) = properties.get_mut(b"D") {
+            if !config.has(b"Name") {
+                config.set("Name", text_string("Default"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn destination(page_id: ObjectId, top: f32) -> Object {
+    Object::Array(vec![
+        Object::Reference(page_id),
+        Object::Name(b"XYZ".to_vec()),
+        Object::Null,
+        Object::Real(top),
+        Object::Null,
+    ])
+}
+
+/// Encodes a PDF text string: plain bytes for ASCII, UTF-16BE with a byte order mark
+/// for anything else.
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::String(text.as_bytes().to_vec(), StringFormat::Literal);
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
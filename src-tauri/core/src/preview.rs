@@ -0,0 +1,289 @@
+//! Pages of a rendered PDF rasterized to PNG, so the app can show the document before it
+//! is exported, and a live preview kept up to date as its files are edited.
+//!
+//! Rasterizing is done by PDFium, which is loaded when a preview is asked for: from the
+//! folder of the running program, where the app bundles it, or else from the system's
+//! library path. Without it previews fail and everything else works as before.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use image::ImageFormat;
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium, PdfiumLibraryBindings};
+use printpdf::lopdf::{self, Object, ObjectId};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+use crate::{Document, ExistingOutput, RenderOptions};
+
+/// Resolution of the previews, that of a screen at 100% zoom.
+const PREVIEW_DPI: f32 = 96.0;
+const POINTS_PER_INCH: f32 = 72.0;
+
+/// A page of the document as a PNG image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preview {
+    /// Number of the page shown, from 1.
+    pub page: usize,
+    /// Pages in the document, for paging through it.
+    pub page_count: usize,
+    pub png: Vec<u8>,
+}
+
+/// `options` without what keeps a preview from being shown or only matters once the PDF
+/// is saved: encryption, splitting, linearizing and attached sources.
+pub fn previewable(options: RenderOptions) -> RenderOptions {
+    RenderOptions {
+        encryption: None,
+        split: None,
+        linearize: false,
+        attach_sources: false,
+        existing_output: ExistingOutput::Overwrite,
+        ..options
+    }
+}
+
+/// Page `page` of the PDF `bytes`, numbered from 1, as a PNG image.
+pub fn rasterize(bytes: &[u8], page: usize) -> Result<Preview, String> {
+    let mut preview = None;
+    rasterize_pages(bytes, &[page], |page| preview = Some(page))?;
+    preview.ok_or_else(|| format!("Page {page} could not be rasterized"))
+}
+
+/// Passes `on_page` each of the pages `pages` of the PDF `bytes`, numbered from 1, as a
+/// PNG image, in order.
+fn rasterize_pages(
+    bytes: &[u8],
+    pages: &[usize],
+    mut on_page: impl FnMut(Preview),
+) -> Result<(), String> {
+    let pdfium = Pdfium::new(bind()?);
+    let document = pdfium
+        .load_pdf_from_byte_slice(bytes, None)
+        .map_err(|err| err.to_string())?;
+    let page_count = usize::from(document.pages().len());
+    let config = PdfRenderConfig::new().scale_page_by_factor(PREVIEW_DPI / POINTS_PER_INCH);
+    for &page in pages {
+        if page == 0 || page > page_count {
+            return Err(format!(
+                "Page {page} is not in the document, which has {page_count} pages"
+            ));
+        }
+        let pdf_page = document
+            .pages()
+            .get((page - 1) as u16)
+            .map_err(|err| err.to_string())?;
+        let bitmap = pdf_page
+            .render_with_config(&config)
+            .map_err(|err| err.to_string())?;
+        let mut png = Vec::new();
+        bitmap
+            .as_image()
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|err| err.to_string())?;
+        on_page(Preview {
+            page,
+            page_count,
+            png,
+        });
+    }
+    Ok(())
+}
+
+fn bind() -> Result<Box<dyn PdfiumLibraryBindings>, String> {
+    let beside_program = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .map(|dir| Pdfium::pdfium_platform_library_name_at_path(&dir));
+    beside_program
+        .and_then(|path| Pdfium::bind_to_library(path).ok())
+        .map_or_else(Pdfium::bind_to_system_library, Ok)
+        .map_err(|_| {
+            format!(
+                "Previews need the PDFium library, {}, beside the app or installed on the system",
+                Pdfium::pdfium_platform_library_name().to_string_lossy()
+            )
+        })
+}
+
+/// A preview of a document kept up to date as its files are edited, for a split-screen
+/// editor. Each file is read once, and after that only the file edited is read again.
+/// Layout resumes on the page the first edited file begins on, when that file starts a
+/// page of its own, and the pages before it are kept. With a contents page or page
+/// references, which an edit can change, every update lays out the whole document, as
+/// does an edit that changes the page count a header or footer shows. Only the pages that
+/// look different are rasterized again.
+pub struct LivePreview {
+    document: Document,
+    options: RenderOptions,
+    /// Markdown of each file by path, as last read or edited; `None` for drafts left out.
+    sources: HashMap<PathBuf, Option<(String, Vec<u8>)>>,
+    /// Fingerprint of each page of the last layout.
+    pages: Vec<u64>,
+    /// The last layout, for the next to resume from.
+    layout: crate::Layout,
+    /// Unpacked archives and downloads the document's files are in, kept while it is shown.
+    _temp_dirs: Vec<Arc<TempDir>>,
+}
+
+/// Pages of a `LivePreview` that changed with an update.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiveUpdate {
+    pub page_count: usize,
+    /// The first page that looks different from the last layout, from 1; `None` when the
+    /// edit changed no page.
+    pub first_changed: Option<usize>,
+    /// Every page that looks different from the last layout, from `first_changed` on.
+    pub changed: Vec<usize>,
+}
+
+impl LivePreview {
+    /// A preview of `document`, whose files may be in `temp_dirs`, kept until it ends.
+    pub fn new(
+        document: Document,
+        options: RenderOptions,
+        temp_dirs: Vec<Arc<TempDir>>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            document,
+            options: previewable(options),
+            sources: HashMap::new(),
+            pages: Vec::new(),
+            layout: crate::Layout::default(),
+            _temp_dirs: temp_dirs,
+        })
+    }
+
+    /// Lays the document out again after the file `path` was edited to `contents`, or
+    /// changed on disk when `contents` is `None`, and passes `on_page` each page that looks
+    /// different, in order. With no `path`, every file is read again.
+    pub fn update(
+        &mut self,
+        path: Option<&str>,
+        contents: Option<String>,
+        on_page: impl FnMut(Preview),
+    ) -> Result<LiveUpdate, String> {
+        match path {
+            Some(path) => {
+                let path = self.file(path)?;
+                let source = match contents {
+                    Some(contents) => {
+                        crate::prepare_source(&path, contents.into_bytes(), &self.options)?
+                    }
+                    None => crate::load_source(&path, &self.options)?,
+                };
+                self.sources.insert(path, source);
+            }
+            None => self.sources.clear(),
+        }
+
+        let sources = &mut self.sources;
+        let mut read_source = |path: &Path, options: &RenderOptions| {
+            let key = crate::normalize_path(path);
+            if let Some(source) = sources.get(&key) {
+                return Ok(source.clone());
+            }
+            let source = crate::load_source(path, options)?;
+            sources.insert(key, source.clone());
+            Ok(source)
+        };
+        let bytes = crate::render_live(
+            &self.document.markdown_files,
+            &self.document.chapters,
+            &self.document.image_files,
+            self.options.clone(),
+            &mut read_source,
+            &mut self.layout,
+        )?;
+
+        let pages = fingerprints(&bytes)?;
+        let changed: Vec<usize> = pages
+            .iter()
+            .enumerate()
+            .filter(|(index, page)| self.pages.get(*index) != Some(page))
+            .map(|(index, _)| index + 1)
+            .collect();
+        self.pages = pages;
+        rasterize_pages(&bytes, &changed, on_page)?;
+        Ok(LiveUpdate {
+            page_count: self.pages.len(),
+            first_changed: changed.first().copied(),
+            changed,
+        })
+    }
+
+    /// The text of the file `path` as it is on disk, for an editor to start from.
+    pub fn source(&self, path: &str) -> Result<String, String> {
+        let bytes = fs::read(self.file(path)?).map_err(|err| err.to_string())?;
+        Ok(crate::encoding::decode(&bytes).into_owned())
+    }
+
+    /// `path` normalized, when it is one of the markdown files of the document.
+    fn file(&self, path: &str) -> Result<PathBuf, String> {
+        let path = crate::normalize_path(Path::new(path));
+        if self
+            .document
+            .markdown_files
+            .iter()
+            .any(|file| crate::normalize_path(Path::new(file)) == path)
+        {
+            Ok(path)
+        } else {
+            Err(format!("{} is not part of the preview", path.display()))
+        }
+    }
+}
+
+/// A fingerprint of what each page of the PDF `bytes` shows: its drawing and the images
+/// and forms it draws.
+fn fingerprints(bytes: &[u8]) -> Result<Vec<u64>, String> {
+    let doc = lopdf::Document::load_mem(bytes).map_err(|err| err.to_string())?;
+    Ok(doc
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let mut hasher = DefaultHasher::new();
+            doc.get_page_content(page_id)
+                .unwrap_or_default()
+                .hash(&mut hasher);
+            for (name, stream) in page_xobjects(&doc, page_id) {
+                name.hash(&mut hasher);
+                stream.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+        .collect())
+}
+
+/// The names and stream contents of the images and forms in the resources of `page_id`.
+fn page_xobjects(doc: &lopdf::Document, page_id: ObjectId) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let (resources, resource_ids) = doc.get_page_resources(page_id);
+    let dictionaries = resources.into_iter().chain(
+        resource_ids
+            .iter()
+            .filter_map(|id| doc.get_dictionary(*id).ok()),
+    );
+    let mut found = Vec::new();
+    for resources in dictionaries {
+        let xobjects = match resources.get(b"XObject") {
+            Ok(Object::Reference(id)) => doc.get_dictionary(*id).ok(),
+            Ok(Object::Dictionary(dict)) => Some(dict),
+            _ => None,
+        };
+        for (name, object) in xobjects.into_iter().flat_map(|dict| dict.iter()) {
+            let stream = object
+                .as_reference()
+                .and_then(|id| doc.get_object(id))
+                .and_then(Object::as_stream)
+                .map(|stream| stream.content.clone())
+                .unwrap_or_default();
+            found.push((name.clone(), stream));
+        }
+    }
+    found
+}
@@ -0,0 +1,251 @@
+//! Markdown files given as `http://` or `https://` URLs, such as raw GitHub or Gist links.
+//!
+//! The file is downloaded into a temporary folder under its name from the URL, along with
+//! the images it links by relative path, which are fetched from the same site and saved
+//! at the same relative path so the file finds them. Images that cannot be fetched are
+//! skipped with a warning.
+//!
+//! GitHub repository links, optionally to a branch or folder with `/tree/<branch>/<folder>`,
+//! fetch the repository's zip archive instead, which is unpacked like an archive input and
+//! scanned from that folder.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use tempfile::TempDir;
+use url::Url;
+
+use crate::{archive, encoding};
+
+/// Longest a download may wait to connect, or for the server to send more, before it is
+/// given up. A large download that keeps arriving may take as long as it needs.
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest markdown file or image downloaded.
+const MAX_BYTES: u64 = 50 << 20;
+/// Largest repository archive downloaded.
+const MAX_REPOSITORY_BYTES: u64 = 500 << 20;
+/// Most images downloaded for one markdown file.
+const MAX_IMAGES: usize = 500;
+
+pub struct Fetched {
+    pub dir: TempDir,
+    /// Folder to scan, inside `dir`.
+    pub root: PathBuf,
+    /// Images and archive entries left out, with the reason.
+    pub warnings: Vec<String>,
+}
+
+pub fn is_url(input: &str) -> bool {
+    let scheme = input.split_once("://").map(|(scheme, _)| scheme);
+    scheme.is_some_and(|scheme| {
+        scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("http")
+    })
+}
+
+/// Downloads the markdown file at `address` and its relative images, or the GitHub
+/// repository it links, into a new temporary folder.
+pub fn fetch(address: &str) -> Result<Fetched, String> {
+    let url = Url::parse(address).map_err(|err| format!("Invalid URL {address}: {err}"))?;
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(TIMEOUT)
+        .timeout_read(TIMEOUT)
+        .build();
+    if let Some(repository) = Repository::of(&url) {
+        return fetch_repository(&agent, &repository);
+    }
+    let (content_type, markdown) = download(&agent, &url, MAX_BYTES)?;
+    if content_type == "text/html" {
+        return Err(format!(
+            "{address} is a web page, not a markdown file; use the link to the raw file"
+        ));
+    }
+
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    fs::write(dir.path().join(file_name(&url)), &markdown).map_err(|err| err.to_string())?;
+
+    let mut warnings = Vec::new();
+    let links = image_links(&encoding::decode(&markdown));
+    if links.len() > MAX_IMAGES {
+        warnings.push(format!(
+            "Skipped the images of {address} past the first {MAX_IMAGES}"
+        ));
+    }
+    for (link, relative) in links.into_iter().take(MAX_IMAGES) {
+        let fetched = url
+            .join(&link)
+            .map_err(|err| err.to_string())
+            .and_then(|image_url| download(&agent, &image_url, MAX_BYTES))
+            .and_then(|(_, bytes)| {
+                let path = dir.path().join(&relative);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                }
+                fs::write(path, bytes).map_err(|err| err.to_string())
+            });
+        if let Err(err) = fetched {
+            warnings.push(format!("Skipped image {link} of {address}: {err}"));
+        }
+    }
+
+    Ok(Fetched {
+        root: dir.path().to_path_buf(),
+        dir,
+        warnings,
+    })
+}
+
+/// A GitHub repository, at a branch and in a folder when the link names them.
+struct Repository {
+    owner: String,
+    name: String,
+    /// Branch, tag or commit; the default branch when unset.
+    reference: Option<String>,
+    folder: PathBuf,
+}
+
+impl Repository {
+    /// The repository `url` links, if it is a link to a repository or one of its folders.
+    fn of(url: &Url) -> Option<Self> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        if host != "github.com" && host != "www.github.com" {
+            return None;
+        }
+        let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+        let owner = segments.next()?.to_string();
+        let name = segments.next()?;
+        let name = name.strip_suffix(".git").unwrap_or(name).to_string();
+        let reference = match segments.next() {
+            None => None,
+            Some("tree") => Some(segments.next()?.to_string()),
+            // Single files (`/blob/`), issues and the like are not repositories.
+            Some(_) => return None,
+        };
+        let folder = relative_path(&segments.collect::<Vec<_>>().join("/")).unwrap_or_default();
+        Some(Self {
+            owner,
+            name,
+            reference,
+            folder,
+        })
+    }
+}
+
+/// Downloads and unpacks the zip archive of `repository`.
+fn fetch_repository(agent: &ureq::Agent, repository: &Repository) -> Result<Fetched, String> {
+    let label = format!("{}/{}", repository.owner, repository.name);
+    let reference = repository.reference.as_deref().unwrap_or("HEAD");
+    let address = format!("https://github.com/{label}/archive/{reference}.zip");
+    let url = Url::parse(&address).map_err(|err| err.to_string())?;
+    let (_, bytes) = download(agent, &url, MAX_REPOSITORY_BYTES)
+        .map_err(|err| format!("Could not download {label}: {err}"))?;
+
+    let download_dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let archive_path = download_dir.path().join(format!("{}.zip", repository.name));
+    fs::write(&archive_path, bytes).map_err(|err| err.to_string())?;
+    let extracted = archive::extract(&archive_path, archive::Kind::Zip, None)?;
+
+    // GitHub archives hold the repository in a single top-level folder.
+    let top = single_folder(extracted.dir.path()).unwrap_or_else(|| extracted.dir.path().into());
+    let root = top.join(&repository.folder);
+    if !root.is_dir() {
+        return Err(format!(
+            "{} is not a folder in {label}",
+            repository.folder.display()
+        ));
+    }
+    Ok(Fetched {
+        dir: extracted.dir,
+        root,
+        warnings: extracted.warnings,
+    })
+}
+
+/// The only entry of the folder `dir`, when that entry is a folder.
+fn single_folder(dir: &Path) -> Option<PathBuf> {
+    let mut entries = fs::read_dir(dir).ok()?.filter_map(|entry| entry.ok());
+    let entry = entries.next()?;
+    (entries.next().is_none() && entry.path().is_dir()).then(|| entry.path())
+}
+
+/// The body of the response to a GET of `url`, with its content type, failing when it is
+/// over `max_bytes` long.
+fn download(agent: &ureq::Agent, url: &Url, max_bytes: u64) -> Result<(String, Vec<u8>), String> {
+    let response = agent.get(url.as_str()).call().map_err(|err| err.to_string())?;
+    let content_type = response.content_type().to_ascii_lowercase();
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(max_bytes + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| err.to_string())?;
+    if bytes.len() as u64 > max_bytes {
+        return Err(format!("{url} is larger than {} MB", max_bytes >> 20));
+    }
+    Ok((content_type, bytes))
+}
+
+/// Name the file at `url` is saved under: the last segment of its path, with `.md` added
+/// when it has no markdown extension.
+fn file_name(url: &Url) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|segment| !segment.is_empty())
+        .unwrap_or("index");
+    let name: String = segment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let lower = name.to_ascii_lowercase();
+    if lower.ends_with(".md") || lower.ends_with(".markdown") {
+        name
+    } else {
+        format!("{name}.md")
+    }
+}
+
+/// Image links in `markdown` that are relative paths inside the file's folder, each with
+/// the path it is saved at.
+fn image_links(markdown: &str) -> Vec<(String, PathBuf)> {
+    let mut links: Vec<(String, PathBuf)> = Vec::new();
+    for event in Parser::new(markdown) {
+        let Event::Start(Tag::Image { dest_url, .. }) = event else {
+            continue;
+        };
+        let link = dest_url.to_string();
+        if links.iter().any(|(known, _)| *known == link) {
+            continue;
+        }
+        if let Some(relative) = relative_path(&link) {
+            links.push((link, relative));
+        }
+    }
+    links
+}
+
+/// `link` as a path below the markdown file's folder, or `None` for links with a scheme,
+/// absolute paths and paths leading out of the folder.
+fn relative_path(link: &str) -> Option<PathBuf> {
+    let path = link.split(['?', '#']).next().unwrap_or_default();
+    if path.starts_with('/') || path.contains(':') {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => relative.push(part),
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
@@ -0,0 +1,869 @@
+//! reStructuredText files, as found in Sphinx documentation folders, converted to markdown
+//! so they are laid out like the markdown files beside them.
+//!
+//! Sections, paragraphs, bullet, enumerated and definition lists, field lists, block
+//! quotes, literal blocks, grid, simple, list and CSV tables and inline markup are carried
+//! over. Of the directives, code blocks, images, figures, admonitions, version notes and
+//! math are drawn like their markdown counterparts; those that only make sense to Sphinx,
+//! such as `toctree`, are left out with comments and link targets, and the content of any
+//! other directive is kept as it is.
+
+use crate::csv;
+
+/// Directives left out along with their content.
+const DROPPED: [&str; 22] = [
+    "autoattribute",
+    "autoclass",
+    "autodata",
+    "autofunction",
+    "automethod",
+    "automodule",
+    "autosummary",
+    "contents",
+    "currentmodule",
+    "default-role",
+    "highlight",
+    "include",
+    "index",
+    "literalinclude",
+    "meta",
+    "module",
+    "raw",
+    "role",
+    "sectnum",
+    "tabularcolumns",
+    "title",
+    "toctree",
+];
+/// Roles whose text is drawn as code.
+const CODE_ROLES: [&str; 19] = [
+    "attr", "class", "code", "command", "const", "data", "envvar", "exc", "file", "func", "kbd",
+    "literal", "meth", "mod", "obj", "option", "program", "samp", "type",
+];
+/// Fields at the top of a Sphinx document that only set how it is built.
+const METADATA_FIELDS: [&str; 4] = ["orphan", "nocomments", "tocdepth", "nosearch"];
+/// Characters escaped in text so they are not read as markdown.
+const ESCAPED: &str = "\\`*_[]<>&$";
+
+/// The markdown equivalent of the reStructuredText document `rst`.
+pub fn to_markdown(rst: &str) -> String {
+    let rst = rst.strip_prefix('\u{feff}').unwrap_or(rst);
+    let lines: Vec<String> = rst
+        .lines()
+        .map(|line| line.replace('\t', "        ").trim_end().to_string())
+        .collect();
+    let mut markdown = Converter::default().blocks(&lines).join("\n");
+    markdown.push('\n');
+    markdown
+}
+
+#[derive(Default)]
+struct Converter {
+    /// Section adornments in the order they first appear, each a character and whether it
+    /// has an overline. The first is level 1.
+    styles: Vec<(char, bool)>,
+}
+
+impl Converter {
+    /// The markdown lines of the body elements in `lines`.
+    fn blocks(&mut self, lines: &[String]) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut index = 0;
+        // Whether the previous block is an ordered or a bullet list item.
+        let mut list: Option<bool> = None;
+
+        while index < lines.len() {
+            let line = lines[index].as_str();
+            if line.is_empty() {
+                index += 1;
+                continue;
+            }
+            let previous_list = list.take();
+
+            if indent(line) > 0 {
+                let end = block_end(lines, index, 1);
+                let quoted = self.blocks(&dedent(&lines[index..end]));
+                push_block(&mut out, quote(&quoted));
+                index = end;
+                continue;
+            }
+
+            let next = lines.get(index + 1).map(String::as_str);
+            if let (Some(over), Some(title), Some(under)) = (
+                adornment(line),
+                next,
+                lines.get(index + 2).map(String::as_str),
+            ) {
+                if adornment(under) == Some(over) && !title.trim().is_empty() {
+                    let level = self.level(over, true);
+                    push_block(&mut out, [heading(level, title.trim())]);
+                    index += 3;
+                    continue;
+                }
+            }
+            if let Some(under) = next.and_then(adornment) {
+                let length = next.unwrap_or_default().chars().count();
+                if adornment(line).is_none() && (length >= 3 || length >= line.chars().count()) {
+                    let level = self.level(under, false);
+                    push_block(&mut out, [heading(level, line)]);
+                    index += 2;
+                    continue;
+                }
+            }
+            if adornment(line).is_some() && line.len() >= 4 {
+                push_block(&mut out, ["---".to_string()]);
+                index += 1;
+                continue;
+            }
+
+            if line == ".." || line.starts_with(".. ") {
+                let end = block_end(lines, index + 1, 1);
+                let body = dedent(&lines[index + 1..end]);
+                let explicit = self.explicit(line[2..].trim(), &body);
+                push_block(&mut out, explicit);
+                index = end;
+                continue;
+            }
+
+            if let Some((rows, end)) =
+                grid_table(lines, index).or_else(|| simple_table(lines, index))
+            {
+                push_block(&mut out, table(&rows));
+                index = end;
+                continue;
+            }
+
+            if let Some((marker, width)) = list_marker(line) {
+                let end = block_end(lines, index + 1, 1);
+                let mut body = vec![line[width..].to_string()];
+                body.extend(dedent(&lines[index + 1..end]));
+                let item = self.blocks(&body);
+                let ordered = marker != "- ";
+                if previous_list != Some(ordered) {
+                    push_block(&mut out, []);
+                }
+                out.extend(indent_item(&marker, &item));
+                list = Some(ordered);
+                index = end;
+                continue;
+            }
+
+            if let Some((name, value)) = field(line) {
+                let end = block_end(lines, index + 1, 1);
+                if !METADATA_FIELDS.contains(&name.to_ascii_lowercase().as_str()) {
+                    let mut text = vec![value.to_string()];
+                    text.extend(dedent(&lines[index + 1..end]));
+                    let text = inline(text.join("\n").trim());
+                    push_block(&mut out, [format!("**{}:** {text}", escape(name))]);
+                }
+                index = end;
+                continue;
+            }
+
+            // A single line with an indented block right below it is a definition list item.
+            if next.is_some_and(|next| indent(next) > 0) {
+                let end = block_end(lines, index + 1, 1);
+                let definition = self.blocks(&dedent(&lines[index + 1..end]));
+                let term = match line.split_once(" : ") {
+                    Some((term, classifier)) => {
+                        format!("{} *({})*", inline(term), inline(classifier.trim()))
+                    }
+                    None => inline(line),
+                };
+                push_block(&mut out, [term]);
+                out.extend(indent_item(": ", &definition));
+                index = end;
+                continue;
+            }
+
+            let start = index;
+            while index < lines.len() && !lines[index].is_empty() && indent(&lines[index]) == 0 {
+                index += 1;
+            }
+            let mut paragraph = lines[start..index].join("\n");
+            let literal = paragraph.ends_with("::");
+            if literal {
+                paragraph = match paragraph.strip_suffix("::") {
+                    Some(text) if text.is_empty() || text.ends_with(char::is_whitespace) => {
+                        text.trim_end().to_string()
+                    }
+                    Some(text) => format!("{text}:"),
+                    None => paragraph,
+                };
+            }
+            if !paragraph.is_empty() {
+                push_block(&mut out, [inline(&paragraph)]);
+            }
+            if literal {
+                let start = (index..lines.len())
+                    .find(|&line| !lines[line].is_empty())
+                    .filter(|&line| indent(&lines[line]) > 0);
+                if let Some(start) = start {
+                    let end = block_end(lines, start, 1);
+                    push_block(&mut out, fence("", &dedent(&lines[start..end])));
+                    index = end;
+                }
+            }
+        }
+        out
+    }
+
+    /// Heading level of the section adornment `ch`, with an overline when `over` is set.
+    fn level(&mut self, ch: char, over: bool) -> usize {
+        let position = match self.styles.iter().position(|style| *style == (ch, over)) {
+            Some(position) => position,
+            None => {
+                self.styles.push((ch, over));
+                self.styles.len() - 1
+            }
+        };
+        (position + 1).min(6)
+    }
+
+    /// Markdown for the explicit markup block `.. head` followed by the indented `body`:
+    /// a directive, footnote, or a comment or target, which are left out.
+    fn explicit(&mut self, head: &str, body: &[String]) -> Vec<String> {
+        if let Some(rest) = head.strip_prefix('[') {
+            let Some((label, text)) = rest.split_once(']') else {
+                return Vec::new();
+            };
+            let mut lines = vec![text.trim().to_string()];
+            lines.extend(body.iter().cloned());
+            let text = inline(lines.join("\n").trim());
+            return vec![format!("\\[{}\\] {text}", escape(label))];
+        }
+        if head.starts_with(['_', '|']) {
+            return Vec::new();
+        }
+        let Some((name, argument)) = head.split_once("::") else {
+            return Vec::new();
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let argument = argument.trim();
+        if DROPPED.contains(&name.as_str()) {
+            return Vec::new();
+        }
+
+        let options_end = body
+            .iter()
+            .position(|line| field(line).is_none())
+            .unwrap_or(body.len());
+        let option = |name: &str| {
+            body[..options_end]
+                .iter()
+                .filter_map(|line| field(line))
+                .find(|(option, _)| *option == name)
+                .map(|(_, value)| value.trim().to_string())
+        };
+        let content_start = (options_end..body.len())
+            .find(|&line| !body[line].is_empty())
+            .unwrap_or(body.len());
+        let content = &body[content_start..];
+        let mut with_argument = Vec::new();
+        if !argument.is_empty() {
+            with_argument.push(argument.to_string());
+            with_argument.push(String::new());
+        }
+        with_argument.extend(content.iter().cloned());
+
+        match name.as_str() {
+            "code" | "code-block" | "sourcecode" => fence(argument, content),
+            "image" | "figure" => {
+                let alt = escape(&option("alt").unwrap_or_default());
+                let mut lines = vec![format!("![{alt}]({})", destination(argument))];
+                if name == "figure" && !content.is_empty() {
+                    lines.push(String::new());
+                    lines.extend(self.blocks(content));
+                }
+                lines
+            }
+            "note" | "hint" | "seealso" | "tip" | "important" | "warning" | "attention"
+            | "caution" | "danger" | "error" => {
+                let kind = match name.as_str() {
+                    "tip" => "TIP",
+                    "important" => "IMPORTANT",
+                    "warning" | "attention" => "WARNING",
+                    "caution" | "danger" | "error" => "CAUTION",
+                    _ => "NOTE",
+                };
+                let mut lines = vec![format!("[!{kind}]")];
+                lines.extend(self.blocks(&with_argument));
+                quote(&lines)
+            }
+            "admonition" | "topic" | "sidebar" => {
+                let mut lines = vec![format!("**{}**", inline(argument)), String::new()];
+                lines.extend(self.blocks(content));
+                quote(&lines)
+            }
+            "rubric" => vec![format!("**{}**", inline(argument))],
+            "versionadded" | "versionchanged" | "deprecated" | "versionremoved" => {
+                let label = match name.as_str() {
+                    "versionadded" => "New in version",
+                    "versionchanged" => "Changed in version",
+                    "deprecated" => "Deprecated since version",
+                    _ => "Removed in version",
+                };
+                let (version, text) = argument.split_once(' ').unwrap_or((argument, ""));
+                let mut text = vec![text.to_string()];
+                text.extend(content.iter().cloned());
+                let text = inline(text.join("\n").trim());
+                vec![format!("*{label} {}:* {text}", escape(version))]
+            }
+            "math" => {
+                let mut lines = vec!["$$".to_string()];
+                lines.extend(with_argument.into_iter().filter(|line| !line.is_empty()));
+                lines.push("$$".to_string());
+                lines
+            }
+            "list-table" => titled(argument, table(&list_table(content))),
+            "csv-table" => {
+                let mut rows = option("header").into_iter().collect::<Vec<_>>();
+                rows.extend(content.iter().cloned());
+                let markdown = csv::to_markdown(&rows.join("\n"));
+                titled(argument, markdown.lines().map(String::from).collect())
+            }
+            "table" => titled(argument, self.blocks(content)),
+            _ => self.blocks(&with_argument),
+        }
+    }
+}
+
+/// `lines` with a bold `title` paragraph above them, when there is a title.
+fn titled(title: &str, lines: Vec<String>) -> Vec<String> {
+    if title.is_empty() {
+        return lines;
+    }
+    let mut titled = vec![format!("**{}**", inline(title)), String::new()];
+    titled.extend(lines);
+    titled
+}
+
+/// Adds `block` to `out`, after a blank line when `out` has something in it.
+fn push_block(out: &mut Vec<String>, block: impl IntoIterator<Item = String>) {
+    if out.last().is_some_and(|line| !line.is_empty()) {
+        out.push(String::new());
+    }
+    for line in block {
+        out.extend(line.split('\n').map(String::from));
+    }
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Index just past the last line of the block starting at `start` whose lines are blank
+/// or indented by at least `min_indent`.
+fn block_end(lines: &[String], start: usize, min_indent: usize) -> usize {
+    let mut end = start;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        if line.is_empty() {
+            continue;
+        }
+        if indent(line) < min_indent {
+            break;
+        }
+        end = index + 1;
+    }
+    end
+}
+
+/// `lines` with the indentation they share removed.
+fn dedent(lines: &[String]) -> Vec<String> {
+    let shared = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| indent(line))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(shared..).unwrap_or_default().to_string())
+        .collect()
+}
+
+/// The character of the section adornment or transition `line`: a run of one punctuation
+/// character.
+fn adornment(line: &str) -> Option<char> {
+    let first = line.chars().next()?;
+    let is_adornment =
+        first.is_ascii_punctuation() && line.len() >= 2 && line.chars().all(|ch| ch == first);
+    is_adornment.then_some(first)
+}
+
+fn heading(level: usize, title: &str) -> String {
+    format!("{} {}", "#".repeat(level), inline(title))
+}
+
+/// `lines` as a markdown block quote.
+fn quote(lines: &[String]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect()
+}
+
+/// `lines` as a fenced code block.
+fn fence(language: &str, lines: &[String]) -> Vec<String> {
+    let mut fence = "```".to_string();
+    while lines.iter().any(|line| line.contains(fence.as_str())) {
+        fence.push('`');
+    }
+    let mut block = vec![format!("{fence}{language}")];
+    let start = lines
+        .iter()
+        .position(|line| !line.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(start, |end| end + 1);
+    block.extend(lines[start..end].iter().cloned());
+    block.push(fence);
+    block
+}
+
+/// The markdown marker and the width of the reStructuredText marker of the list item
+/// `line`, if it is one.
+fn list_marker(line: &str) -> Option<(String, usize)> {
+    let first = line.chars().next()?;
+    let (marker, marker_len) = if matches!(first, '-' | '*' | '+' | '•') {
+        ("- ".to_string(), first.len_utf8())
+    } else if line.starts_with("#.") || line.starts_with("#)") {
+        ("1. ".to_string(), 2)
+    } else {
+        let inner = line.strip_prefix('(').unwrap_or(line);
+        let digits = inner.len()
+            - inner
+                .trim_start_matches(|ch: char| ch.is_ascii_digit())
+                .len();
+        let close = inner[digits..].chars().next()?;
+        let closes = if line.starts_with('(') {
+            close == ')'
+        } else {
+            matches!(close, '.' | ')')
+        };
+        if digits == 0 || digits > 9 || !closes {
+            return None;
+        }
+        let number = &inner[..digits];
+        (format!("{number}. "), line.len() - inner.len() + digits + 1)
+    };
+    let rest = &line[marker_len..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let width = marker_len + indent(rest);
+    Some((marker, width.min(line.len())))
+}
+
+/// `lines` as a list item or definition, after `marker`.
+fn indent_item(marker: &str, lines: &[String]) -> Vec<String> {
+    let padding = " ".repeat(marker.len());
+    let mut item: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| match (index, line.is_empty()) {
+            (0, _) => format!("{marker}{line}").trim_end().to_string(),
+            (_, true) => String::new(),
+            _ => format!("{padding}{line}"),
+        })
+        .collect();
+    if item.is_empty() {
+        item.push(marker.trim_end().to_string());
+    }
+    item
+}
+
+/// The name and value of the field list item `line`, such as `:param x: The value.`
+fn field(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let end = rest.find(':')?;
+    let value = &rest[end + 1..];
+    let is_field = end > 0 && (value.is_empty() || value.starts_with(' '));
+    is_field.then(|| (&rest[..end], value.trim_start()))
+}
+
+/// Rows of cells of the grid table starting at `lines[start]`, with the index past it.
+fn grid_table(lines: &[String], start: usize) -> Option<(Vec<Vec<String>>, usize)> {
+    let border: Vec<char> = lines[start].chars().collect();
+    let is_border = |line: &[char]| {
+        line.len() > 2 && line[0] == '+' && line.iter().all(|ch| matches!(ch, '+' | '-' | '='))
+    };
+    if !is_border(&border) || *border.last()? != '+' {
+        return None;
+    }
+    let columns: Vec<usize> = border
+        .iter()
+        .enumerate()
+        .filter(|(_, ch)| **ch == '+')
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut cells = vec![Vec::new(); columns.len() - 1];
+    let mut end = start + 1;
+    while let Some(line) = lines.get(end).filter(|line| line.starts_with(['+', '|'])) {
+        let line: Vec<char> = line.chars().collect();
+        end += 1;
+        if is_border(&line) {
+            if cells.iter().any(|cell: &Vec<String>| !cell.is_empty()) {
+                rows.push(
+                    cells
+                        .iter()
+                        .map(|cell| cell_text(&cell.join(" ")))
+                        .collect(),
+                );
+            }
+            cells = vec![Vec::new(); columns.len() - 1];
+            continue;
+        }
+        for (column, bounds) in columns.windows(2).enumerate() {
+            let text: String = line
+                .get(bounds[0] + 1..bounds[1].min(line.len()))
+                .unwrap_or_default()
+                .iter()
+                .collect();
+            let text = text.trim();
+            if !text.is_empty() {
+                cells[column].push(text.to_string());
+            }
+        }
+    }
+    Some((rows, end))
+}
+
+/// Rows of cells of the simple table starting at `lines[start]`, with the index past it.
+fn simple_table(lines: &[String], start: usize) -> Option<(Vec<Vec<String>>, usize)> {
+    let is_border = |line: &str| {
+        line.starts_with('=') && line.contains(' ') && line.chars().all(|ch| ch == '=' || ch == ' ')
+    };
+    if !is_border(&lines[start]) {
+        return None;
+    }
+    let mut columns = Vec::new();
+    let mut previous = ' ';
+    for (index, ch) in lines[start].chars().enumerate() {
+        if ch == '=' && previous == ' ' {
+            columns.push(index);
+        }
+        previous = ch;
+    }
+
+    // A header is separated from the body by a second border, with a third at the end.
+    let borders: Vec<usize> = (start + 1..lines.len())
+        .take_while(|&index| {
+            let line = &lines[index];
+            !line.is_empty() || lines.get(index + 1).is_some_and(|next| !next.is_empty())
+        })
+        .filter(|&index| is_border(&lines[index]))
+        .take(2)
+        .collect();
+    let first_border = *borders.first()?;
+    let body_has_rows = lines
+        .get(first_border + 1)
+        .is_some_and(|line| !line.is_empty());
+    let end = match borders.get(1) {
+        Some(&second) if body_has_rows => second,
+        _ => first_border,
+    };
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for line in &lines[start + 1..end] {
+        if line.is_empty() || is_border(line) {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let cells: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(column, &from)| {
+                let to = columns.get(column + 1).copied().unwrap_or(chars.len());
+                let text: String = chars
+                    .get(from.min(chars.len())..to.min(chars.len()))
+                    .unwrap_or_default()
+                    .iter()
+                    .collect();
+                text.trim().to_string()
+            })
+            .collect();
+        // A row with an empty first column continues the one above it.
+        match rows.last_mut() {
+            Some(row) if cells[0].is_empty() => {
+                for (cell, text) in row.iter_mut().zip(cells) {
+                    if !text.is_empty() {
+                        cell.push(' ');
+                        cell.push_str(&text);
+                    }
+                }
+            }
+            _ => rows.push(cells),
+        }
+    }
+    let rows = rows
+        .into_iter()
+        .map(|row| row.iter().map(|cell| cell_text(cell.trim())).collect())
+        .collect();
+    Some((rows, end + 1))
+}
+
+/// Rows of cells of a `list-table` directive: a bullet list of rows, each a bullet list
+/// of cells.
+fn list_table(content: &[String]) -> Vec<Vec<String>> {
+    let items = |lines: &[String]| -> Vec<Vec<String>> {
+        let mut items: Vec<Vec<String>> = Vec::new();
+        for line in lines {
+            match list_marker(line) {
+                Some((_, width)) if indent(line) == 0 => {
+                    items.push(vec![line[width..].to_string()]);
+                }
+                _ => {
+                    if let Some(item) = items.last_mut() {
+                        item.push(line.clone());
+                    }
+                }
+            }
+        }
+        items.into_iter().map(|item| dedent_rest(&item)).collect()
+    };
+    items(content)
+        .iter()
+        .map(|row| {
+            items(row)
+                .iter()
+                .map(|cell| cell_text(cell.join(" ").trim()))
+                .collect()
+        })
+        .collect()
+}
+
+/// `item` with the indentation of all but its first line removed.
+fn dedent_rest(item: &[String]) -> Vec<String> {
+    let mut lines = vec![item[0].clone()];
+    lines.extend(dedent(&item[1..]));
+    lines
+}
+
+/// The text of a table cell, with its inline markup converted.
+fn cell_text(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    inline(&text).replace('|', "\\|")
+}
+
+/// Markdown table of `rows`, the first of which is the header.
+fn table(rows: &[Vec<String>]) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return Vec::new();
+    }
+    let line = |cells: &[String]| {
+        let mut line = String::from("|");
+        for column in 0..columns {
+            let cell = cells.get(column).map_or("", String::as_str);
+            line.push_str(&format!(" {cell} |"));
+        }
+        line
+    };
+    let mut table = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+    table.extend(rows[1..].iter().map(|row| line(row)));
+    table
+}
+
+/// `text` with the characters markdown would read as markup escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if ESCAPED.contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// `url` as a markdown link destination, wrapped in `<>` when it holds spaces or brackets.
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn code_span(code: &str) -> String {
+    if code.contains('`') {
+        format!("`` {code} ``")
+    } else {
+        format!("`{code}`")
+    }
+}
+
+/// Whether inline markup may start after `previous`.
+fn starts_markup(previous: Option<char>) -> bool {
+    previous.is_none_or(|ch| ch.is_whitespace() || "'\"([{<-/:‘“’«".contains(ch))
+}
+
+/// The title and target of the reference or role text `text`, such as `Title <target>`.
+fn split_target(text: &str) -> (&str, Option<&str>) {
+    match text
+        .strip_suffix('>')
+        .and_then(|text| text.rsplit_once('<'))
+    {
+        Some((title, target)) if !title.trim().is_empty() => (title.trim(), Some(target)),
+        Some((_, target)) => (target, Some(target)),
+        None => (text, None),
+    }
+}
+
+/// Markdown for the role `name` applied to `text`.
+fn role(name: &str, text: &str) -> String {
+    let name = name.rsplit(':').next().unwrap_or(name).to_ascii_lowercase();
+    let (title, _) = split_target(text);
+    match name.as_str() {
+        "math" => format!("${text}$"),
+        "sup" | "superscript" => format!("<sup>{}</sup>", escape(text)),
+        "sub" | "subscript" => format!("<sub>{}</sub>", escape(text)),
+        "strong" => format!("**{}**", escape(text)),
+        "emphasis" | "dfn" => format!("*{}*", escape(text)),
+        "pep" => format!("PEP {}", escape(text)),
+        "rfc" => format!("RFC {}", escape(text)),
+        name if CODE_ROLES.contains(&name) => {
+            let title = title.trim_start_matches('!');
+            let title = match title.strip_prefix('~') {
+                Some(path) => path.rsplit('.').next().unwrap_or(path),
+                None => title,
+            };
+            code_span(title)
+        }
+        _ => escape(title),
+    }
+}
+
+/// `text` with its reStructuredText inline markup turned into markdown.
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let find = |from: usize, pattern: &str| -> Option<usize> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        (from..chars.len()).find(|&index| chars[index..].starts_with(&pattern))
+    };
+    let collect = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let mut out = String::with_capacity(text.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        let ch = chars[index];
+        let previous = index.checked_sub(1).map(|previous| chars[previous]);
+        let next = chars.get(index + 1).copied();
+        let starts = starts_markup(previous) && next.is_some_and(|next| !next.is_whitespace());
+
+        if ch == '\\' {
+            match next {
+                Some(next) if next.is_whitespace() => {}
+                Some(next) if next.is_ascii_punctuation() => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                Some(next) => out.push(next),
+                None => out.push_str("\\\\"),
+            }
+            index += 2;
+            continue;
+        }
+
+        if ch == '`' && next == Some('`') && starts {
+            if let Some(end) = find(index + 2, "``") {
+                out.push_str(&code_span(&collect(index + 2, end)));
+                index = end + 2;
+                continue;
+            }
+        }
+
+        if ch == '`' && starts {
+            if let Some(end) = find(index + 1, "`") {
+                let content = collect(index + 1, end);
+                let underscores = chars[end + 1..].iter().take(2).take_while(|ch| **ch == '_');
+                let underscores = underscores.count();
+                if underscores > 0 {
+                    let (title, target) = split_target(&content);
+                    match target.filter(|target| !target.ends_with('_')) {
+                        Some(target) => {
+                            out.push_str(&format!("[{}]({})", escape(title), destination(target)))
+                        }
+                        None => out.push_str(&escape(title)),
+                    }
+                } else {
+                    out.push_str(&format!("*{}*", escape(&content)));
+                }
+                index = end + 1 + underscores;
+                continue;
+            }
+        }
+
+        if ch == ':' && starts {
+            let name_end = (index + 1..chars.len()).find(|&end| {
+                !(chars[end].is_alphanumeric() || matches!(chars[end], '-' | '_' | '.' | ':'))
+                    || (chars[end] == ':' && chars.get(end + 1) == Some(&'`'))
+            });
+            let is_role = name_end.is_some_and(|end| {
+                end > index + 1 && chars[end] == ':' && chars.get(end + 1) == Some(&'`')
+            });
+            if let (true, Some(name_end)) = (is_role, name_end) {
+                if let Some(end) = find(name_end + 2, "`") {
+                    out.push_str(&role(
+                        &collect(index + 1, name_end),
+                        &collect(name_end + 2, end),
+                    ));
+                    index = end + 1;
+                    continue;
+                }
+            }
+        }
+
+        if ch == '[' && starts {
+            let reference = find(index + 1, "]_").filter(|&end| {
+                chars[index + 1..end]
+                    .iter()
+                    .all(|ch| ch.is_alphanumeric() || matches!(ch, '#' | '*' | '-' | '_'))
+            });
+            if let Some(end) = reference {
+                let label = collect(index + 1, end);
+                if !label.starts_with(['#', '*']) {
+                    out.push_str(&format!("\\[{}\\]", escape(&label)));
+                }
+                index = end + 2;
+                continue;
+            }
+        }
+
+        // `Python_` and `Python__` refer to link targets defined elsewhere.
+        let word_start = chars[..index]
+            .iter()
+            .rposition(|ch| ch.is_whitespace())
+            .map_or(0, |space| space + 1);
+        if ch == '_' && previous.is_some_and(char::is_alphanumeric) && chars[word_start] != '_' {
+            let underscores = if next == Some('_') { 2 } else { 1 };
+            let after = chars.get(index + underscores);
+            if after.is_none_or(|after| after.is_whitespace() || ",.;:!?)".contains(*after)) {
+                index += underscores;
+                continue;
+            }
+            out.push('_');
+            index += 1;
+            continue;
+        }
+
+        let line_start = out.is_empty() || out.ends_with('\n');
+        if "<>[]&$".contains(ch) || (ch == '_' && starts) || (ch == '#' && line_start) {
+            out.push('\\');
+        }
+        out.push(ch);
+        index += 1;
+    }
+    out
+}
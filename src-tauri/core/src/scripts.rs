@@ -0,0 +1,91 @@
+//! Pandoc-style `^superscript^` and `~subscript~` syntax.
+//!
+//! pulldown-cmark has no script extension: it parses single tildes between words as
+//! strikethrough and leaves carets and intraword tildes as text. Both cases are lowered
+//! to the `<sup>`/`<sub>` inline HTML the html module already handles.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd, TextMergeStream};
+
+/// Rewrites script syntax in an offset-annotated event stream. Adjacent text events are
+/// merged on the way, so later stages also see URLs the parser split at `_` or `*`.
+pub fn lower<'a>(
+    contents: &'a str,
+    events: impl Iterator<Item = (Event<'a>, Range<usize>)>,
+) -> impl Iterator<Item = Event<'a>> {
+    // One entry per open strikethrough, `true` when it was written with a single tilde.
+    let mut subscripts: Vec<bool> = Vec::new();
+    let events = events.map(move |(event, range)| match event {
+        Event::Start(Tag::Strikethrough) => {
+            let single = !contents[range].starts_with("~~");
+            subscripts.push(single);
+            if single {
+                Event::InlineHtml("<sub>".into())
+            } else {
+                event
+            }
+        }
+        Event::End(TagEnd::Strikethrough) => {
+            if subscripts.pop().unwrap_or(false) {
+                Event::InlineHtml("</sub>".into())
+            } else {
+                event
+            }
+        }
+        event => event,
+    });
+
+    let mut in_code_block = false;
+    TextMergeStream::new(events).flat_map(move |event| {
+        match &event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) if !in_code_block && text.contains(['^', '~']) => {
+                return scripts(text);
+            }
+            _ => {}
+        }
+        vec![event]
+    })
+}
+
+/// Splits `^text^` and `~text~` runs out of a text event. Like pandoc, the scripted
+/// text may not be empty or contain whitespace; anything else keeps its markers.
+fn scripts(text: &str) -> Vec<Event<'static>> {
+    let mut events = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['^', '~']) {
+        let marker = rest[start..].chars().next().unwrap_or('^');
+        let after = &rest[start + 1..];
+        let scripted = after
+            .find(marker)
+            .map(|end| &after[..end])
+            .filter(|scripted| !scripted.is_empty() && !scripted.contains(char::is_whitespace));
+        match scripted {
+            Some(scripted) => {
+                let tag = if marker == '^' { "sup" } else { "sub" };
+                plain.push_str(&rest[..start]);
+                if !plain.is_empty() {
+                    events.push(Event::Text(CowStr::from(std::mem::take(&mut plain))));
+                }
+                events.push(Event::InlineHtml(CowStr::from(format!("<{tag}>"))));
+                events.push(Event::Text(CowStr::from(scripted.to_string())));
+                events.push(Event::InlineHtml(CowStr::from(format!("</{tag}>"))));
+                rest = &after[scripted.len() + 1..];
+            }
+            None => {
+                plain.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    plain.push_str(rest);
+    if !plain.is_empty() {
+        events.push(Event::Text(CowStr::from(plain)));
+    }
+
+    events
+}
@@ -0,0 +1,406 @@
+//! Static-site sources, read with `RenderOptions::site_content`.
+//!
+//! Hugo shortcodes (`{{< name >}}` and `{{% name %}}`) and Jekyll's Liquid tags
+//! (`{% name %}` and `{{ value }}`) are template code the site generator replaces when it
+//! builds the site. Left in, they print as noise, so they are stripped, and the common ones
+//! are expanded into the markdown they stand for: figures become images, videos and gists
+//! links, `ref` and `link` tags their path, and highlighted code a fenced block. The text
+//! between paired tags is kept, except in comments. Code spans and fenced code blocks are
+//! left alone, and Hugo's escaped `{{</* name */>}}` is printed as `{{< name >}}`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// What becomes of shortcodes and Liquid tags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shortcodes {
+    /// Leaves them in the text.
+    Keep,
+    /// Removes them.
+    Strip,
+    /// Replaces the common ones with markdown and removes the rest.
+    #[default]
+    Expand,
+}
+
+/// Delimiters of Hugo shortcodes, which `/*` and `*/` inside escape.
+const ESCAPES: [(&str, &str); 2] = [("{{<", ">}}"), ("{{%", "%}}")];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Syntax {
+    /// `{{< name >}}` or `{{% name %}}`.
+    Hugo,
+    /// `{% name %}`.
+    LiquidTag,
+    /// `{{ value }}`.
+    LiquidOutput,
+}
+
+#[derive(Debug)]
+struct Tag {
+    syntax: Syntax,
+    /// Name of a Hugo closing tag, `/highlight`, keeps its slash; Liquid's are `end` names.
+    name: String,
+    args: Vec<Arg>,
+}
+
+#[derive(Debug)]
+struct Arg {
+    key: Option<String>,
+    value: String,
+}
+
+impl Tag {
+    /// The argument named `key`, or else the positional one at `position`.
+    fn arg(&self, key: &str, position: usize) -> Option<&str> {
+        self.named(key).or_else(|| self.positional(position))
+    }
+
+    fn named(&self, key: &str) -> Option<&str> {
+        let arg = self
+            .args
+            .iter()
+            .find(|arg| arg.key.as_deref() == Some(key))?;
+        Some(&arg.value)
+    }
+
+    fn positional(&self, position: usize) -> Option<&str> {
+        let arg = self
+            .args
+            .iter()
+            .filter(|arg| arg.key.is_none())
+            .nth(position)?;
+        Some(&arg.value)
+    }
+}
+
+/// `body` with its shortcodes and Liquid tags handled as `mode` says.
+pub fn replace(body: &str, mode: Shortcodes) -> String {
+    if mode == Shortcodes::Keep {
+        return body.to_string();
+    }
+    let mut out = String::with_capacity(body.len());
+    let mut prose = String::new();
+    let mut fence: Option<String> = None;
+    for line in body.split_inclusive('\n') {
+        if let Some(marker) = &fence {
+            out.push_str(line);
+            if line.trim() == marker.as_str() {
+                fence = None;
+            }
+            continue;
+        }
+        match fence_marker(line) {
+            Some(marker) => {
+                replace_tags(&std::mem::take(&mut prose), mode, &mut out);
+                out.push_str(line);
+                fence = Some(marker);
+            }
+            None => prose.push_str(line),
+        }
+    }
+    replace_tags(&prose, mode, &mut out);
+    out
+}
+
+/// The backticks or tildes that open a fenced code block on `line`, if it opens one.
+fn fence_marker(line: &str) -> Option<String> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let line = line.trim();
+    if indent > 3 {
+        return None;
+    }
+    let fence = line.chars().next().filter(|ch| matches!(ch, '`' | '~'))?;
+    let length = line.chars().take_while(|ch| *ch == fence).count();
+    (length >= 3).then(|| fence.to_string().repeat(length))
+}
+
+fn replace_tags(text: &str, mode: Shortcodes, out: &mut String) {
+    let mut rest = text;
+    while let Some(start) = rest.find(['`', '{']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with('`') {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let span = closing_ticks(&rest[ticks..], ticks).map_or(ticks, |end| ticks + end);
+            out.push_str(&rest[..span]);
+            rest = &rest[span..];
+            continue;
+        }
+        let escaped = ESCAPES.iter().find_map(|&(open, close)| {
+            let inner = rest.strip_prefix(open)?.strip_prefix("/*")?;
+            let end = inner.find(&format!("*/{close}"))?;
+            Some((open, close, &inner[..end], &inner[end + 2 + close.len()..]))
+        });
+        if let Some((open, close, inner, after)) = escaped {
+            out.push_str(&format!("{open}{inner}{close}"));
+            rest = after;
+            continue;
+        }
+        let Some((tag, length)) = parse_tag(rest) else {
+            out.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[length..];
+        rest = apply(&tag, rest, mode, out);
+    }
+    out.push_str(rest);
+}
+
+/// Where the run of exactly `ticks` backticks closing a code span starts in `text`, and
+/// its end.
+fn closing_ticks(text: &str, ticks: usize) -> Option<usize> {
+    let mut index = 0;
+    while let Some(start) = text[index..].find('`') {
+        let start = index + start;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == ticks {
+            return Some(start + run);
+        }
+        index = start + run;
+    }
+    None
+}
+
+/// The tag `text` starts with and its length.
+fn parse_tag(text: &str) -> Option<(Tag, usize)> {
+    let (syntax, close, inner_start) = if text.starts_with("{{<") {
+        (Syntax::Hugo, ">}}", 3)
+    } else if text.starts_with("{{%") {
+        (Syntax::Hugo, "%}}", 3)
+    } else if text.starts_with("{%") {
+        (Syntax::LiquidTag, "%}", 2)
+    } else if text.starts_with("{{") {
+        (Syntax::LiquidOutput, "}}", 2)
+    } else {
+        return None;
+    };
+    let end = inner_start + text[inner_start..].find(close)?;
+    let inner = text[inner_start..end].trim();
+    let inner = inner.trim_start_matches('-').trim_end_matches('-');
+    let inner = inner.strip_suffix('/').unwrap_or(inner).trim();
+
+    let tag = if syntax == Syntax::LiquidOutput {
+        Tag {
+            syntax,
+            name: String::new(),
+            args: vec![Arg {
+                key: None,
+                value: inner.to_string(),
+            }],
+        }
+    } else {
+        let (slash, inner) = match inner.strip_prefix('/') {
+            Some(inner) => ("/", inner.trim_start()),
+            None => ("", inner),
+        };
+        let (name, args) = match inner.find(char::is_whitespace) {
+            Some(space) => (&inner[..space], &inner[space..]),
+            None => (inner, ""),
+        };
+        Tag {
+            syntax,
+            name: format!("{slash}{}", name.to_lowercase()),
+            args: parse_args(args),
+        }
+    };
+    Some((tag, end + close.len()))
+}
+
+/// Positional and `key=value` arguments, values quoted with `"`, `'` or backticks or bare.
+fn parse_args(text: &str) -> Vec<Arg> {
+    let mut args = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+        let mut word = String::new();
+        let mut key = None;
+        if matches!(first, '"' | '\'' | '`') {
+            word = quoted(&mut chars);
+        } else {
+            while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != '=') {
+                word.push(ch);
+            }
+            if chars.next_if_eq(&'=').is_some() {
+                key = Some(std::mem::take(&mut word));
+                match chars.peek() {
+                    Some('"' | '\'' | '`') => word = quoted(&mut chars),
+                    _ => {
+                        while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace()) {
+                            word.push(ch);
+                        }
+                    }
+                }
+            }
+        }
+        args.push(Arg { key, value: word });
+    }
+    args
+}
+
+fn quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let quote = chars.next().unwrap_or('"');
+    chars.take_while(|ch| *ch != quote).collect()
+}
+
+/// Writes what `tag` becomes to `out`, returning the text after it and any text it
+/// consumed.
+fn apply<'a>(tag: &Tag, rest: &'a str, mode: Shortcodes, out: &mut String) -> &'a str {
+    let expand = mode == Shortcodes::Expand;
+    match (tag.syntax, tag.name.as_str()) {
+        (Syntax::Hugo | Syntax::LiquidTag, "comment") => {
+            let (_, after) = paired(tag, rest);
+            after
+        }
+        (Syntax::LiquidTag, "raw") => {
+            let (content, after) = paired(tag, rest);
+            out.push_str(content);
+            after
+        }
+        (Syntax::Hugo | Syntax::LiquidTag, "highlight") => {
+            let (content, after) = paired(tag, rest);
+            if expand {
+                let language = tag.arg("lang", 0).unwrap_or_default();
+                let code = content.trim_matches(['\n', '\r']);
+                out.push_str(&format!("```{language}\n{code}\n```"));
+            } else {
+                out.push_str(content);
+            }
+            after
+        }
+        _ if !expand => rest,
+        (Syntax::Hugo, "figure") => {
+            if let Some(src) = tag.arg("src", 0) {
+                let alt = ["alt", "caption", "title"]
+                    .into_iter()
+                    .find_map(|key| tag.named(key))
+                    .unwrap_or_default();
+                out.push_str(&format!("![{alt}]({src})"));
+            }
+            rest
+        }
+        (Syntax::Hugo, "youtube") => {
+            link(
+                tag,
+                "YouTube video",
+                "https://www.youtube.com/watch?v=",
+                out,
+            );
+            rest
+        }
+        (Syntax::Hugo, "vimeo") => {
+            link(tag, "Vimeo video", "https://vimeo.com/", out);
+            rest
+        }
+        (Syntax::Hugo, "instagram") => {
+            link(tag, "Instagram post", "https://www.instagram.com/p/", out);
+            rest
+        }
+        (Syntax::Hugo, "gist") => {
+            if let (Some(user), Some(id)) = (tag.arg("user", 0), tag.arg("id", 1)) {
+                out.push_str(&format!("[Gist](https://gist.github.com/{user}/{id})"));
+            }
+            rest
+        }
+        (Syntax::Hugo, "tweet" | "x") => {
+            if let (Some(user), Some(id)) = (tag.arg("user", 0), tag.arg("id", 1)) {
+                out.push_str(&format!(
+                    "[Post by @{user}](https://x.com/{user}/status/{id})"
+                ));
+            }
+            rest
+        }
+        (Syntax::Hugo, "ref" | "relref") | (Syntax::LiquidTag, "link") => {
+            out.push_str(tag.arg("path", 0).unwrap_or_default());
+            rest
+        }
+        (Syntax::LiquidTag, "post_url") => {
+            if let Some(post) = tag.positional(0) {
+                out.push_str(&format!("{post}.md"));
+            }
+            rest
+        }
+        (Syntax::LiquidOutput, _) => {
+            // `{{ "/assets/a.png" | relative_url }}` prints its path; other values are
+            // site variables without a value here.
+            let value = tag.positional(0).unwrap_or_default();
+            if let Some((literal, filters)) = value.split_once('|') {
+                let literal = literal.trim();
+                let is_url = filters.trim().starts_with("relative_url")
+                    || filters.trim().starts_with("absolute_url");
+                if is_url && literal.len() >= 2 && literal.starts_with(['"', '\'']) {
+                    out.push_str(&literal[1..literal.len() - 1]);
+                }
+            }
+            rest
+        }
+        _ => rest,
+    }
+}
+
+fn link(tag: &Tag, label: &str, url: &str, out: &mut String) {
+    if let Some(id) = tag.arg("id", 0) {
+        out.push_str(&format!("[{label}]({url}{id})"));
+    }
+}
+
+/// The text up to the tag closing `tag` in `rest` and the text after that tag; all of
+/// `rest` when it is never closed.
+fn paired<'a>(tag: &Tag, rest: &'a str) -> (&'a str, &'a str) {
+    let closing = match tag.syntax {
+        Syntax::Hugo => format!("/{}", tag.name),
+        _ => format!("end{}", tag.name),
+    };
+    let opener = if tag.syntax == Syntax::Hugo {
+        "{{"
+    } else {
+        "{%"
+    };
+    let mut index = 0;
+    while let Some(start) = rest[index..].find(opener) {
+        let start = index + start;
+        match parse_tag(&rest[start..]) {
+            Some((found, length)) if found.syntax == tag.syntax && found.name == closing => {
+                return (&rest[..start], &rest[start + length..]);
+            }
+            _ => index = start + opener.len(),
+        }
+    }
+    (rest, "")
+}
+
+/// The date a Jekyll post named `YYYY-MM-DD-title.md` is published on.
+pub fn post_date(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let date = stem.get(..10)?;
+    let is_date = date.char_indices().all(|(index, ch)| match index {
+        4 | 7 => ch == '-',
+        _ => ch.is_ascii_digit(),
+    });
+    (is_date && stem[10..].starts_with('-')).then(|| date.to_string())
+}
+
+/// The title a page without one is given, as Hugo does: its file name, or its folder's
+/// for the `index.md` of a page bundle, with dashes and underscores as spaces, a post's
+/// date left out and the first letter capitalised.
+pub fn page_title(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let name = if matches!(stem, "index" | "_index") {
+        path.parent()?.file_name()?.to_str()?
+    } else if post_date(path).is_some() {
+        &stem[11..]
+    } else {
+        stem
+    };
+    let words = name.replace(['-', '_'], " ");
+    let mut chars = words.trim().chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
@@ -0,0 +1,130 @@
+//! Splitting the rendered document into parts of at most so many pages or about so many
+//! megabytes, for upload targets that reject large files.
+//!
+//! A part's size is estimated from the compressed objects its pages draw on, each counted
+//! once per part, so fonts and images shared by its pages are only counted for the first.
+
+use std::collections::HashSet;
+
+use printpdf::lopdf::{Document, Object, ObjectId};
+
+use crate::SplitOutput;
+
+/// Bytes added to each object for its number, dictionary keys and cross-reference entry.
+const OBJECT_OVERHEAD: usize = 64;
+
+/// The zero-based indices of the pages of the PDF `bytes` that go in each part, leaving
+/// out the pages `removed`.
+pub fn parts(
+    bytes: &[u8],
+    limit: &SplitOutput,
+    removed: &[usize],
+) -> Result<Vec<Vec<usize>>, String> {
+    let mut doc = Document::load_mem(bytes).map_err(|err| err.to_string())?;
+    let max_bytes = limit
+        .max_megabytes
+        .map(|megabytes| (f64::from(megabytes) * 1024.0 * 1024.0) as usize);
+    if max_bytes.is_some() {
+        doc.compress();
+    }
+
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let mut parts: Vec<Vec<usize>> = Vec::new();
+    let mut part: Vec<usize> = Vec::new();
+    let mut part_objects: HashSet<ObjectId> = HashSet::new();
+    let mut part_bytes = 0;
+    for (index, &page_id) in pages.iter().enumerate() {
+        if removed.contains(&index) {
+            continue;
+        }
+        let objects = match max_bytes {
+            Some(_) => page_objects(&doc, page_id),
+            None => Vec::new(),
+        };
+        let added = |part_objects: &HashSet<ObjectId>| -> usize {
+            objects
+                .iter()
+                .filter(|id| !part_objects.contains(id))
+                .map(|&id| object_size(&doc, id))
+                .sum()
+        };
+        let full_pages = limit.max_pages.is_some_and(|max| part.len() >= max);
+        let full_bytes = max_bytes.is_some_and(|max| part_bytes + added(&part_objects) > max);
+        if !part.is_empty() && (full_pages || full_bytes) {
+            parts.push(std::mem::take(&mut part));
+            part_objects.clear();
+            part_bytes = 0;
+        }
+        part_bytes += added(&part_objects);
+        part_objects.extend(objects.iter().copied());
+        part.push(index);
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    Ok(parts)
+}
+
+/// The objects the page `page_id` draws on, found through its references without
+/// following them into the page tree or other pages.
+fn page_objects(doc: &Document, page_id: ObjectId) -> Vec<ObjectId> {
+    let mut found = vec![page_id];
+    let mut seen: HashSet<ObjectId> = HashSet::from([page_id]);
+    let mut index = 0;
+    while let Some(&id) = found.get(index) {
+        index += 1;
+        let Ok(object) = doc.get_object(id) else {
+            continue;
+        };
+        let mut references = Vec::new();
+        collect_references(object, &mut references);
+        for reference in references {
+            let is_page = doc
+                .get_dictionary(reference)
+                .and_then(|dict| dict.get(b"Type"))
+                .and_then(Object::as_name)
+                .is_ok_and(|name| name == b"Page" || name == b"Pages");
+            if !is_page && seen.insert(reference) {
+                found.push(reference);
+            }
+        }
+    }
+    found
+}
+
+fn collect_references(object: &Object, references: &mut Vec<ObjectId>) {
+    match object {
+        Object::Reference(id) => references.push(*id),
+        Object::Array(items) => {
+            for item in items {
+                collect_references(item, references);
+            }
+        }
+        Object::Dictionary(dict) => {
+            for (key, value) in dict.iter() {
+                // A page's parent is the page tree.
+                if key != b"Parent" {
+                    collect_references(value, references);
+                }
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in stream.dict.iter() {
+                collect_references(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Approximate number of bytes the object `id` takes in the file.
+fn object_size(doc: &Document, id: ObjectId) -> usize {
+    let content = match doc.get_object(id) {
+        Ok(Object::Stream(stream)) => stream.content.len() + stream.dict.len() * 16,
+        Ok(Object::Dictionary(dict)) => dict.len() * 16,
+        Ok(Object::Array(items)) => items.len() * 8,
+        Ok(Object::String(bytes, _)) => bytes.len(),
+        _ => 0,
+    };
+    content + OBJECT_OVERHEAD
+}
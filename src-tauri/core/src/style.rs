@@ -0,0 +1,162 @@
+//! Typography, spacing and colours of the rendered document.
+//!
+//! The defaults reproduce the original look. A theme file in TOML or JSON, chosen by its
+//! extension, can override any subset of the fields.
+
+use std::fs;
+use std::path::Path;
+
+use printpdf::{Color, Rgb};
+use serde::{Deserialize, Serialize};
+
+/// Builtin font families body text and headings can be set in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontFamily {
+    #[default]
+    Helvetica,
+    Times,
+}
+
+/// Horizontal placement of paragraph and heading lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches every line but the last of a paragraph to the full width.
+    Justify,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    pub font_family: FontFamily,
+    /// Font sizes in points.
+    pub body_size: f32,
+    /// Sizes of heading levels 1 to 4; deeper levels use the last one.
+    pub heading_sizes: Vec<f32>,
+    pub code_size: f32,
+    pub code_label_size: f32,
+    pub table_size: f32,
+    pub math_size: f32,
+    /// Size of the page header and footer.
+    pub margin_text_size: f32,
+    /// Line height as a multiple of the font size.
+    pub line_spacing: f32,
+    /// Space after paragraphs and other blocks, in points.
+    pub block_spacing: f32,
+    /// Extra space before paragraphs, in points.
+    pub paragraph_spacing_before: f32,
+    /// Space after headings, in points.
+    pub heading_spacing: f32,
+    /// Space between list items and other closely grouped lines, in points.
+    pub item_spacing: f32,
+    /// Marker of unordered list items.
+    pub bullet: String,
+    pub text_align: TextAlign,
+    pub heading_align: TextAlign,
+    /// Font size of block quotes, in points.
+    pub quote_size: f32,
+    /// Indent of each block quote level, in points.
+    pub quote_indent: f32,
+    /// RGB colours, each channel in `0.0..=1.0`.
+    pub text_color: [f32; 3],
+    pub heading_color: [f32; 3],
+    pub code_color: [f32; 3],
+    pub quote_color: [f32; 3],
+    /// Colour of link text and its underline.
+    pub link_color: [f32; 3],
+    /// Highlight behind inline code.
+    pub code_background: [f32; 3],
+    pub code_label_color: [f32; 3],
+    pub line_number_color: [f32; 3],
+    pub table_header_background: [f32; 3],
+    pub table_border_color: [f32; 3],
+    pub underline_links: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            font_family: FontFamily::Helvetica,
+            body_size: 11.0,
+            heading_sizes: vec![24.0, 18.0, 14.0, 12.0],
+            code_size: 9.5,
+            code_label_size: 7.5,
+            table_size: 10.0,
+            math_size: 12.0,
+            margin_text_size: 9.0,
+            line_spacing: 1.25,
+            block_spacing: 6.0,
+            paragraph_spacing_before: 0.0,
+            heading_spacing: 8.0,
+            item_spacing: 2.0,
+            bullet: "•".to_string(),
+            text_align: TextAlign::Left,
+            heading_align: TextAlign::Left,
+            quote_size: 11.0,
+            quote_indent: 0.0,
+            text_color: [0.0, 0.0, 0.0],
+            heading_color: [0.0, 0.0, 0.0],
+            code_color: [0.0, 0.0, 0.0],
+            quote_color: [0.0, 0.0, 0.0],
+            link_color: [0.1, 0.3, 0.8],
+            code_background: [0.92, 0.92, 0.92],
+            code_label_color: [0.45, 0.45, 0.45],
+            line_number_color: [0.55, 0.55, 0.55],
+            table_header_background: [0.9, 0.9, 0.9],
+            table_border_color: [0.6, 0.6, 0.6],
+            underline_links: true,
+        }
+    }
+}
+
+impl Style {
+    /// Reads a theme file; `.toml` files are parsed as TOML and anything else as JSON.
+    pub fn load(path: &Path) -> Result<Style, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let is_toml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        if is_toml {
+            toml::from_str(&contents).map_err(|err| err.to_string())
+        } else {
+            serde_json::from_str(&contents).map_err(|err| err.to_string())
+        }
+    }
+
+    /// Rejects line spacing that would stack lines on top of each other and negative gaps.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.line_spacing.is_finite() || self.line_spacing <= 0.0 {
+            return Err("Line spacing must be more than zero".to_string());
+        }
+        let spacings = [
+            ("Block spacing", self.block_spacing),
+            ("Space before paragraphs", self.paragraph_spacing_before),
+            ("Heading spacing", self.heading_spacing),
+            ("Item spacing", self.item_spacing),
+        ];
+        for (name, spacing) in spacings {
+            if !spacing.is_finite() || spacing < 0.0 {
+                return Err(format!("{name} must be zero or more points"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn heading_size(&self, level: u32) -> f32 {
+        let index = (level.max(1) - 1) as usize;
+        self.heading_sizes
+            .get(index)
+            .or(self.heading_sizes.last())
+            .copied()
+            .unwrap_or(self.body_size)
+    }
+}
+
+pub fn color([r, g, b]: [f32; 3]) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
@@ -0,0 +1,90 @@
+//! Structure tags that let screen readers follow the document: headings, paragraphs,
+//! lists, tables and figures, in reading order.
+//!
+//! Blocks open and close elements while they are drawn. Elements that hold content wrap
+//! everything drawn for them in a marked-content sequence with its own number on the
+//! page, restarted on each page the block continues on; grouping elements such as lists
+//! and table rows only hold other elements. The post-processing turns the elements into
+//! the document's structure tree.
+
+/// Roles whose elements only group other elements.
+const GROUPING_ROLES: &[&str] = &["Document", "L", "LI", "Table", "TR", "TOC"];
+
+/// One node of the structure tree.
+#[derive(Clone)]
+pub struct Element {
+    /// Standard structure type, such as `P`, `H1` or `Figure`.
+    pub role: &'static str,
+    /// Replacement text for figures.
+    pub alt: Option<String>,
+    /// Child elements and marked content, in reading order.
+    pub kids: Vec<Kid>,
+}
+
+#[derive(Clone)]
+pub enum Kid {
+    Element(usize),
+    /// A marked-content sequence, by zero-based page index and marked-content number.
+    Content { page: usize, mcid: u32 },
+}
+
+#[derive(Default)]
+pub struct Tags {
+    /// Every element; the first one opened is the root.
+    pub elements: Vec<Element>,
+    open: Vec<usize>,
+    /// Marked-content numbers handed out on each page.
+    page_mcids: Vec<u32>,
+}
+
+impl Tags {
+    /// Opens an element inside the innermost open one, returning whether its content is
+    /// marked.
+    pub fn open(&mut self, role: &'static str, alt: Option<String>) -> bool {
+        let index = self.elements.len();
+        self.elements.push(Element {
+            role,
+            alt,
+            kids: Vec::new(),
+        });
+        if let Some(&parent) = self.open.last() {
+            self.elements[parent].kids.push(Kid::Element(index));
+        }
+        self.open.push(index);
+        self.is_marking()
+    }
+
+    /// Closes the innermost open element, returning whether its content was marked.
+    pub fn close(&mut self) -> bool {
+        let marking = self.is_marking();
+        self.open.pop();
+        marking
+    }
+
+    /// Whether the innermost open element holds content rather than other elements.
+    pub fn is_marking(&self) -> bool {
+        self.open
+            .last()
+            .is_some_and(|&index| !GROUPING_ROLES.contains(&self.elements[index].role))
+    }
+
+    /// Starts a marked-content sequence for the innermost open element on `page`,
+    /// returning its role and marked-content number.
+    pub fn mark(&mut self, page: usize) -> Option<(&'static str, u32)> {
+        let &index = self.open.last()?;
+        if self.page_mcids.len() <= page {
+            self.page_mcids.resize(page + 1, 0);
+        }
+        let mcid = self.page_mcids[page];
+        self.page_mcids[page] += 1;
+        let element = &mut self.elements[index];
+        element.kids.push(Kid::Content { page, mcid });
+        Some((element.role, mcid))
+    }
+}
+
+/// Heading role for a heading `level` deep, with levels past six tagged `H6`.
+pub fn heading_role(level: u32) -> &'static str {
+    const ROLES: [&str; 6] = ["H1", "H2", "H3", "H4", "H5", "H6"];
+    ROLES[level.clamp(1, 6) as usize - 1]
+}
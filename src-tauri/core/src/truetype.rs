@@ -0,0 +1,58 @@
+//! TrueType fonts embedded in the document for characters the builtin fonts cannot show.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use printpdf::{IndirectFontRef, PdfDocumentReference};
+
+/// An embedded TrueType font together with the data needed to measure its glyphs.
+pub struct EmbeddedFont {
+    pub font: IndirectFontRef,
+    /// Horizontal advance of the glyph of each character the font has, in font units.
+    advances: HashMap<char, u16>,
+    units_per_em: f32,
+}
+
+impl EmbeddedFont {
+    pub fn embed(doc: &PdfDocumentReference, path: &Path) -> Result<EmbeddedFont, String> {
+        let data = fs::read(path).map_err(|err| err.to_string())?;
+        let face = ttf_parser::Face::parse(&data, 0).map_err(|err| err.to_string())?;
+        let mut advances = HashMap::new();
+        let subtables = face.tables().cmap.iter().flat_map(|cmap| cmap.subtables);
+        for subtable in subtables.filter(|subtable| subtable.is_unicode()) {
+            subtable.codepoints(|code_point| {
+                let Some(ch) = char::from_u32(code_point) else {
+                    return;
+                };
+                if let Some(glyph) = face.glyph_index(ch) {
+                    advances.insert(ch, face.glyph_hor_advance(glyph).unwrap_or(0));
+                }
+            });
+        }
+        let units_per_em = f32::from(face.units_per_em());
+        let font = doc
+            .add_external_font(data.as_slice())
+            .map_err(|err| err.to_string())?;
+        Ok(EmbeddedFont {
+            font,
+            advances,
+            units_per_em,
+        })
+    }
+
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.advances.contains_key(&ch)
+    }
+
+    /// Width of `text` in points. Characters the font lacks are skipped, as they are
+    /// when the text is written.
+    pub fn text_width_pt(&self, text: &str, font_size: f32) -> f32 {
+        let units: u32 = text
+            .chars()
+            .filter_map(|ch| self.advances.get(&ch))
+            .map(|&advance| u32::from(advance))
+            .sum();
+        units as f32 * font_size / self.units_per_em
+    }
+}
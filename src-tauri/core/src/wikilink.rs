@@ -0,0 +1,82 @@
+//! Obsidian-style `[[Note]]`, `[[Note#Heading]]` and `[[Note|label]]` links, and
+//! `![[image.png]]` embeds.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikiLink<'a> {
+    /// Note name or path without the `.md` extension; empty for links within the same note.
+    pub note: &'a str,
+    pub heading: Option<&'a str>,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece<'a> {
+    Text(&'a str),
+    Link(WikiLink<'a>),
+    Embed(WikiLink<'a>),
+}
+
+/// Splits `text` into plain runs and wiki links.
+pub fn segments(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let inner_start = start + 2;
+        let Some(length) = rest[inner_start..].find("]]") else {
+            break;
+        };
+        let inner = &rest[inner_start..inner_start + length];
+        let Some(link) = parse(inner) else {
+            if !rest[..inner_start].is_empty() {
+                pieces.push(Piece::Text(&rest[..inner_start]));
+            }
+            rest = &rest[inner_start..];
+            continue;
+        };
+
+        let embed = rest[..start].ends_with('!');
+        let text_end = if embed { start - 1 } else { start };
+        if text_end > 0 {
+            pieces.push(Piece::Text(&rest[..text_end]));
+        }
+        pieces.push(if embed {
+            Piece::Embed(link)
+        } else {
+            Piece::Link(link)
+        });
+        rest = &rest[inner_start + length + 2..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+
+    pieces
+}
+
+fn parse(inner: &str) -> Option<WikiLink<'_>> {
+    if inner.trim().is_empty() || inner.contains(['[', ']', '\n']) {
+        return None;
+    }
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target.trim(), Some(alias.trim())),
+        None => (inner.trim(), None),
+    };
+    let (note, heading) = match target.split_once('#') {
+        Some((note, heading)) => (note.trim(), Some(heading.trim())),
+        None => (target, None),
+    };
+    let note = note.strip_suffix(".md").unwrap_or(note);
+
+    let label = match (alias, heading) {
+        (Some(alias), _) if !alias.is_empty() => alias.to_string(),
+        (_, Some(heading)) if note.is_empty() => heading.to_string(),
+        (_, Some(heading)) => format!("{note} > {heading}"),
+        _ => note.to_string(),
+    };
+    Some(WikiLink {
+        note,
+        heading: heading.filter(|heading| !heading.is_empty()),
+        label,
+    })
+}
@@ -0,0 +1,174 @@
+//! Conversions queued by `convert_to_pdf`, rendered one at a time on a worker thread in
+//! the order they were queued, and kept with their outcome for the app's history.
+
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use markdown2pdf_core::{render_with_progress, ConvertResult, Document, Progress, RenderOptions};
+use serde::Serialize;
+use tauri::Emitter;
+use tempfile::TempDir;
+
+/// Event the worker sends a `JobProgress` with as rendering goes on.
+const PROGRESS_EVENT: &str = "conversion://progress";
+
+#[derive(Default)]
+pub struct Jobs {
+    by_id: BTreeMap<u64, Job>,
+    /// ID of the last job queued; IDs are not reused.
+    last_id: u64,
+    /// Hands queued jobs to the worker, which is started for the first job.
+    worker: Option<Sender<Queued>>,
+}
+
+impl Jobs {
+    pub fn get(&self, id: u64) -> Option<&Job> {
+        self.by_id.get(&id)
+    }
+
+    /// Every job, in the order they were queued.
+    pub fn all(&self) -> Vec<Job> {
+        self.by_id.values().cloned().collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    /// The PDF asked for; the file written may be numbered or split into parts.
+    pub output_path: String,
+    pub status: JobStatus,
+    /// Set once the job is done.
+    pub result: Option<ConvertResult>,
+    /// Why the job failed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct JobProgress {
+    job_id: u64,
+    #[serde(flatten)]
+    progress: Progress,
+}
+
+struct Queued {
+    id: u64,
+    input: Document,
+    output_path: PathBuf,
+    options: RenderOptions,
+    /// Folders of the input's session its files are in, kept until the job has run.
+    temp_dirs: Vec<Arc<TempDir>>,
+}
+
+pub fn lock(jobs: &Mutex<Jobs>) -> Result<MutexGuard<'_, Jobs>, String> {
+    jobs.lock()
+        .map_err(|_| "Failed to lock conversion job state".to_string())
+}
+
+/// Queues rendering `input` into the PDF at `output_path` and returns the job's ID. Progress
+/// is sent to the app's windows through `PROGRESS_EVENT`. The `temp_dirs` the input's
+/// files are in are kept until the job has run.
+pub fn enqueue(
+    jobs: &Arc<Mutex<Jobs>>,
+    app: &tauri::AppHandle,
+    input: Document,
+    output_path: PathBuf,
+    options: RenderOptions,
+    temp_dirs: Vec<Arc<TempDir>>,
+) -> Result<u64, String> {
+    let mut state = lock(jobs)?;
+    state.last_id += 1;
+    let id = state.last_id;
+    let worker = state
+        .worker
+        .get_or_insert_with(|| start_worker(Arc::clone(jobs), app.clone()))
+        .clone();
+    state.by_id.insert(
+        id,
+        Job {
+            id,
+            output_path: output_path.to_string_lossy().into_owned(),
+            status: JobStatus::Queued,
+            result: None,
+            error: None,
+        },
+    );
+    let queued = Queued {
+        id,
+        input,
+        output_path,
+        options,
+        temp_dirs,
+    };
+    if worker.send(queued).is_err() {
+        state.by_id.remove(&id);
+        return Err("The conversion worker has stopped".to_string());
+    }
+    Ok(id)
+}
+
+fn start_worker(jobs: Arc<Mutex<Jobs>>, app: tauri::AppHandle) -> Sender<Queued> {
+    let (sender, receiver) = mpsc::channel::<Queued>();
+    thread::spawn(move || {
+        for queued in receiver {
+            let id = queued.id;
+            update(&jobs, id, |job| job.status = JobStatus::Running);
+            // A bug in the renderer fails its job instead of stopping the jobs after it.
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| run(queued, &app)))
+                .unwrap_or_else(|_| Err("Rendering stopped unexpectedly".to_string()));
+            update(&jobs, id, |job| match outcome {
+                Ok(result) => {
+                    job.status = JobStatus::Done;
+                    job.result = Some(result);
+                }
+                Err(err) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(err);
+                }
+            });
+        }
+    });
+    sender
+}
+
+fn run(queued: Queued, app: &tauri::AppHandle) -> Result<ConvertResult, String> {
+    let Queued {
+        id,
+        input,
+        output_path,
+        options,
+        temp_dirs: _temp_dirs,
+    } = queued;
+    let written = render_with_progress(&input, &output_path, options, |progress| {
+        // A window closed mid-conversion only misses the update.
+        let update = JobProgress {
+            job_id: id,
+            progress,
+        };
+        let _ = app.emit(PROGRESS_EVENT, update);
+    })?;
+    Ok(ConvertResult::new(&written))
+}
+
+fn update(jobs: &Mutex<Jobs>, id: u64, change: impl FnOnce(&mut Job)) {
+    // A poisoned lock means a job panicked while holding it; its status is left as it was.
+    let Ok(mut jobs) = lock(jobs) else {
+        return;
+    };
+    if let Some(job) = jobs.by_id.get_mut(&id) {
+        change(job);
+    }
+}
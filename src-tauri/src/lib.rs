@@ -0,0 +1,497 @@
+//! The desktop app: Tauri commands over the conversion in `markdown2pdf-core`, and the
+//! input sessions and conversion jobs they share.
+
+mod jobs;
+mod settings;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use markdown2pdf_core::{
+    arrange_files, chosen_output_path, convert_each_subfolder, default_output_path, filters,
+    free_output_path, limits, merge_pdf_files, open_in_viewer, preview, render_markdown_pdf,
+    render_plain_text, reveal_in_file_manager, scan_inputs, BatchResult, ConvertResult,
+    Document, ExistingOutput, RenderOptions, ScanError, OUTPUT_FILE_NAME,
+};
+use tauri::{Emitter, Manager};
+use tempfile::TempDir;
+
+/// Event each page of a live preview is sent with as a `preview::Preview` when it changes.
+const PREVIEW_PAGE_EVENT: &str = "preview://page";
+
+#[derive(Default)]
+pub struct AppState {
+    sessions: Arc<Mutex<Sessions>>,
+    /// Passwords of encrypted zip inputs, by input path.
+    archive_passwords: Mutex<HashMap<String, String>>,
+    jobs: Arc<Mutex<jobs::Jobs>>,
+    /// The preview started by `start_live_preview`, if any.
+    live_preview: Arc<Mutex<Option<preview::LivePreview>>>,
+}
+
+/// Inputs collected by `process_input`, by session ID, for `add_inputs` to add to. A
+/// session lasts until the next `process_input` that succeeds, so files can still be added
+/// after a conversion or a failed scan.
+#[derive(Default)]
+struct Sessions {
+    by_id: HashMap<u64, Session>,
+    /// ID of the last session started; IDs are not reused.
+    last_id: u64,
+}
+
+struct Session {
+    input: Document,
+    filters: filters::AssetFilters,
+    limits: limits::ScanLimits,
+    /// Unpacked archives and downloads the input's files are in, shared with the jobs and
+    /// live preview using them so they outlive the session for as long as those need.
+    temp_dirs: Vec<Arc<TempDir>>,
+}
+
+/// Collects `input_paths` into a new session. It runs on a worker thread, as archives and
+/// repositories may take a while to download and unpack.
+#[tauri::command]
+async fn process_input(
+    input_paths: Vec<String>,
+    filters: Option<filters::AssetFilters>,
+    limits: Option<limits::ScanLimits>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<Document, ScanError> {
+    let sessions = Arc::clone(&state.sessions);
+    let passwords = archive_passwords(&state)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let filters = filters.unwrap_or_default();
+        let limits = limits.unwrap_or_default();
+        // Downloads are written next to the user's other downloads.
+        let download_dir = || app.path().download_dir().map_err(|err| err.to_string());
+        let scanned = scan_inputs(input_paths, &filters, &limits, &passwords, download_dir)?;
+
+        // The app works on one input at a time, so starting over ends the earlier sessions,
+        // once the new input has been scanned; a scan that fails leaves them as they were.
+        let mut sessions = lock_sessions(&sessions)?;
+        sessions.by_id.clear();
+        sessions.last_id += 1;
+        let mut input = scanned.input;
+        input.session_id = sessions.last_id;
+        let session = Session {
+            input: input.clone(),
+            filters,
+            limits,
+            temp_dirs: scanned.temp_dirs.into_iter().map(Arc::new).collect(),
+        };
+        sessions.by_id.insert(input.session_id, session);
+        Ok(input)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Adds `input_paths`, which are files, folders, archives or URLs, to the input of the
+/// session `session_id`, collecting them with the filters and limits it was started with.
+/// Files already in the input are not added twice. It runs on a worker thread, like
+/// `process_input`.
+#[tauri::command]
+async fn add_inputs(
+    session_id: u64,
+    input_paths: Vec<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<Document, ScanError> {
+    let sessions = Arc::clone(&state.sessions);
+    let passwords = archive_passwords(&state)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let (filters, limits) = {
+            let sessions = lock_sessions(&sessions)?;
+            let session = sessions
+                .by_id
+                .get(&session_id)
+                .ok_or_else(|| session_ended(session_id))?;
+            (session.filters.clone(), session.limits)
+        };
+
+        let download_dir = || app.path().download_dir().map_err(|err| err.to_string());
+        let scanned = scan_inputs(input_paths, &filters, &limits, &passwords, download_dir)?;
+
+        // The session is looked up again, as it may have ended while the inputs were read.
+        let mut sessions = lock_sessions(&sessions)?;
+        let session = sessions
+            .by_id
+            .get_mut(&session_id)
+            .ok_or_else(|| session_ended(session_id))?;
+        let mut input = session.input.clone();
+        input.extend(scanned.input);
+        limits::check(&input, session.limits)?;
+        session.input = input.clone();
+        session
+            .temp_dirs
+            .extend(scanned.temp_dirs.into_iter().map(Arc::new));
+        Ok(input)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// The temporary folders the files of the session `session_id` are in, for whatever
+/// reads them to hold on to; none once the session has ended.
+fn session_temp_dirs(state: &AppState, session_id: u64) -> Result<Vec<Arc<TempDir>>, String> {
+    let sessions = lock_sessions(&state.sessions)?;
+    Ok(sessions
+        .by_id
+        .get(&session_id)
+        .map(|session| session.temp_dirs.clone())
+        .unwrap_or_default())
+}
+
+fn session_ended(session_id: u64) -> String {
+    format!("Input session {session_id} has ended; scan the input again")
+}
+
+fn lock_sessions(sessions: &Mutex<Sessions>) -> Result<MutexGuard<'_, Sessions>, String> {
+    sessions
+        .lock()
+        .map_err(|_| "Failed to lock session state".to_string())
+}
+
+fn archive_passwords(state: &AppState) -> Result<HashMap<String, String>, String> {
+    let passwords = state
+        .archive_passwords
+        .lock()
+        .map_err(|_| "Failed to lock archive password state".to_string())?;
+    Ok(passwords.clone())
+}
+
+/// Puts the markdown files of `input` in `order` and leaves out the `excluded` ones, for
+/// `convert_to_pdf` to render in that order. Files missing from `order` follow in their
+/// current order.
+#[tauri::command]
+fn arrange_markdown(
+    mut input: Document,
+    order: Vec<String>,
+    excluded: Vec<String>,
+) -> Result<Document, String> {
+    input.markdown_files = arrange_files(&input.markdown_files, &order, &excluded)?;
+    Ok(input)
+}
+
+/// Remembers the password of the encrypted zip at `path` for the next `process_input` or
+/// `add_inputs`.
+#[tauri::command]
+fn set_archive_password(
+    path: String,
+    password: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .archive_passwords
+        .lock()
+        .map_err(|_| "Failed to lock archive password state".to_string())?
+        .insert(path, password);
+    Ok(())
+}
+
+/// Queues rendering `input` into the PDF at `output_path`, or else its default output path,
+/// and returns the job's ID for `job_status`. Jobs run on a worker thread one at a time.
+#[tauri::command]
+fn convert_to_pdf(
+    input: Document,
+    options: Option<RenderOptions>,
+    output_path: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<u64, String> {
+    if input.markdown_files.is_empty() {
+        return Err("No markdown files found".to_string());
+    }
+
+    let output_path = match output_path {
+        Some(path) => chosen_output_path(&path, "pdf")?,
+        None => default_output_path(&input, options.as_ref())?,
+    };
+    let options = options.unwrap_or_default();
+    let temp_dirs = session_temp_dirs(&state, input.session_id)?;
+    jobs::enqueue(&state.jobs, &app, input, output_path, options, temp_dirs)
+}
+
+/// Lays out `input` with `options` and returns page `page` of it, numbered from 1, as a
+/// PNG image, for the app to show before exporting. It runs on a worker thread, as laying
+/// out a large input takes a while.
+#[tauri::command]
+async fn render_preview(
+    input: Document,
+    page: usize,
+    options: Option<RenderOptions>,
+    state: tauri::State<'_, AppState>,
+) -> Result<preview::Preview, String> {
+    let temp_dirs = session_temp_dirs(&state, input.session_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let preview = markdown2pdf_core::render_preview(&input, page, options.unwrap_or_default());
+        drop(temp_dirs);
+        preview
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Starts a live preview of `input`, replacing any before it, and sends every page of it
+/// through `PREVIEW_PAGE_EVENT`. `update_live_preview` then keeps it up to date.
+#[tauri::command]
+async fn start_live_preview(
+    input: Document,
+    options: Option<RenderOptions>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<preview::LiveUpdate, String> {
+    if input.markdown_files.is_empty() {
+        return Err("No markdown files found".to_string());
+    }
+
+    let live_preview = Arc::clone(&state.live_preview);
+    let temp_dirs = session_temp_dirs(&state, input.session_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = options.unwrap_or_default();
+        let mut live = preview::LivePreview::new(input, options, temp_dirs)?;
+        let update = live.update(None, None, |page| send_preview_page(&app, page))?;
+        *lock_live_preview(&live_preview)? = Some(live);
+        Ok(update)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Lays the live preview out again with the file at `path` edited to `contents`, or as it
+/// is on disk when `contents` is missing, and sends the pages that changed through
+/// `PREVIEW_PAGE_EVENT`.
+#[tauri::command]
+async fn update_live_preview(
+    path: String,
+    contents: Option<String>,
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<preview::LiveUpdate, String> {
+    let live_preview = Arc::clone(&state.live_preview);
+    tauri::async_runtime::spawn_blocking(move || {
+        lock_live_preview(&live_preview)?
+            .as_mut()
+            .ok_or_else(|| "No live preview has been started".to_string())?
+            .update(Some(&path), contents, |page| send_preview_page(&app, page))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// The text of the file at `path` of the live preview, for the editor to start from.
+#[tauri::command]
+fn live_preview_source(path: String, state: tauri::State<'_, AppState>) -> Result<String, String> {
+    lock_live_preview(&state.live_preview)?
+        .as_ref()
+        .ok_or_else(|| "No live preview has been started".to_string())?
+        .source(&path)
+}
+
+/// Ends the live preview, if one was started.
+#[tauri::command]
+fn stop_live_preview(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    *lock_live_preview(&state.live_preview)? = None;
+    Ok(())
+}
+
+fn lock_live_preview(
+    live_preview: &Mutex<Option<preview::LivePreview>>,
+) -> Result<MutexGuard<'_, Option<preview::LivePreview>>, String> {
+    live_preview
+        .lock()
+        .map_err(|_| "Failed to lock live preview state".to_string())
+}
+
+fn send_preview_page(app: &tauri::AppHandle, page: preview::Preview) {
+    // A window closed while the preview was laid out only misses the page.
+    let _ = app.emit(PREVIEW_PAGE_EVENT, page);
+}
+
+/// The status of the conversion job `job_id`, with its result once it is done.
+#[tauri::command]
+fn job_status(job_id: u64, state: tauri::State<'_, AppState>) -> Result<jobs::Job, String> {
+    jobs::lock(&state.jobs)?
+        .get(job_id)
+        .cloned()
+        .ok_or_else(|| format!("No conversion job {job_id}"))
+}
+
+/// Every conversion job since the app started, oldest first.
+#[tauri::command]
+fn list_jobs(state: tauri::State<'_, AppState>) -> Result<Vec<jobs::Job>, String> {
+    Ok(jobs::lock(&state.jobs)?.all())
+}
+
+/// Renders each subfolder of `folder` into its own PDF in `output_dir`. It runs on a worker
+/// thread, as rendering a whole batch takes a while.
+#[tauri::command]
+async fn convert_subfolders(
+    folder: String,
+    output_dir: String,
+    filters: Option<filters::AssetFilters>,
+    limits: Option<limits::ScanLimits>,
+    options: Option<RenderOptions>,
+) -> Result<BatchResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        convert_each_subfolder(
+            Path::new(&folder),
+            Path::new(&output_dir),
+            &filters.unwrap_or_default(),
+            &limits.unwrap_or_default(),
+            &options.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Merges the PDFs `inputs`, in order, into the PDF at `output_path`.
+#[tauri::command]
+fn merge_pdfs(inputs: Vec<String>, output_path: String) -> Result<ConvertResult, String> {
+    let output_path = chosen_output_path(&output_path, "pdf")?;
+    let (output_path, written) = free_output_path(output_path, ExistingOutput::Overwrite)?;
+    merge_pdf_files(&inputs, &output_path)?;
+    Ok(ConvertResult::new(&[(output_path, written)]))
+}
+
+/// Writes `input` as plain text to `output_path`, or else to its default output path with
+/// a `.txt` extension.
+#[tauri::command]
+fn convert_to_text(
+    input: Document,
+    options: Option<RenderOptions>,
+    output_path: Option<String>,
+) -> Result<ConvertResult, String> {
+    if input.markdown_files.is_empty() {
+        return Err("No markdown files found".to_string());
+    }
+
+    let output_path = match output_path {
+        Some(path) => chosen_output_path(&path, "txt")?,
+        None => default_output_path(&input, options.as_ref())?.with_extension("txt"),
+    };
+    let options = options.unwrap_or_default();
+    let (output_path, written) = free_output_path(output_path, options.existing_output)?;
+    render_plain_text(&input.markdown_files, &input.chapters, &output_path, &options)?;
+
+    Ok(ConvertResult::new(&[(output_path, written)]))
+}
+
+/// Renders `markdown` pasted or typed into the app on its own, into the PDF at
+/// `output_path` or else the downloads folder. Relative image paths have no folder to be
+/// resolved against, so only absolute ones load.
+#[tauri::command]
+fn convert_text_to_pdf(
+    markdown: String,
+    options: Option<RenderOptions>,
+    output_path: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<ConvertResult, String> {
+    if markdown.trim().is_empty() {
+        return Err("No markdown text provided".to_string());
+    }
+
+    let dir = tempfile::tempdir().map_err(|err| err.to_string())?;
+    let file = dir.path().join("pasted.md");
+    fs::write(&file, markdown).map_err(|err| err.to_string())?;
+    let output_path = match output_path {
+        Some(path) => chosen_output_path(&path, "pdf")?,
+        None => {
+            let output_dir = app.path().download_dir().map_err(|err| err.to_string())?;
+            output_dir.join(OUTPUT_FILE_NAME)
+        }
+    };
+    let options = options.unwrap_or_default();
+    let written = render_markdown_pdf(
+        &[file.to_string_lossy().into_owned()],
+        &[],
+        &[],
+        &output_path,
+        options,
+    )?;
+
+    Ok(ConvertResult::new(&written))
+}
+
+/// Suggests where the PDF of `input` goes, or its export with `extension`, for the save
+/// dialog to start at: the default output path, numbered when `options` say so and it is
+/// taken.
+#[tauri::command]
+fn suggest_output_path(
+    input: Document,
+    options: Option<RenderOptions>,
+    extension: Option<String>,
+) -> Result<String, String> {
+    let mut path = default_output_path(&input, options.as_ref())?;
+    if let Some(extension) = extension {
+        path.set_extension(extension);
+    }
+    let path = match options.map(|options| options.existing_output) {
+        Some(ExistingOutput::Increment) => free_output_path(path, ExistingOutput::Increment)?.0,
+        _ => path,
+    };
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Opens the export at `path` in the system's default viewer.
+#[tauri::command]
+fn open_output(path: String) -> Result<(), String> {
+    open_in_viewer(Path::new(&path))
+}
+
+/// Shows the export at `path` selected in the system's file manager.
+#[tauri::command]
+fn reveal_in_folder(path: String) -> Result<(), String> {
+    reveal_in_file_manager(Path::new(&path))
+}
+
+/// The settings saved by `set_settings`, or the defaults before any are.
+#[tauri::command]
+fn get_settings(app: tauri::AppHandle) -> Result<settings::Settings, String> {
+    let dir = app.path().app_data_dir().map_err(|err| err.to_string())?;
+    settings::load(&dir)
+}
+
+/// Saves `settings` for the next run of the app.
+#[tauri::command]
+fn set_settings(settings: settings::Settings, app: tauri::AppHandle) -> Result<(), String> {
+    settings.validate()?;
+    let dir = app.path().app_data_dir().map_err(|err| err.to_string())?;
+    settings::save(&dir, &settings)
+}
+
+/// Starts the desktop app.
+pub fn run() {
+    tauri::Builder::default()
+        .manage(AppState::default())
+        .plugin(tauri_plugin_dialog::init())
+        .invoke_handler(tauri::generate_handler![
+            process_input,
+            add_inputs,
+            arrange_markdown,
+            set_archive_password,
+            suggest_output_path,
+            convert_to_pdf,
+            render_preview,
+            start_live_preview,
+            update_live_preview,
+            live_preview_source,
+            stop_live_preview,
+            job_status,
+            list_jobs,
+            convert_to_text,
+            convert_subfolders,
+            convert_text_to_pdf,
+            merge_pdfs,
+            open_output,
+            reveal_in_folder,
+            get_settings,
+            set_settings
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
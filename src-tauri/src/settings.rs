@@ -0,0 +1,96 @@
+//! Settings kept between runs of the app in `settings.json` in its data folder: the page,
+//! fonts and theme conversions start from, and what happens to exports.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use markdown2pdf_core::style::Style;
+use markdown2pdf_core::{ExistingOutput, Margins, Orientation, PageSize, RenderOptions};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub page_size: PageSize,
+    pub orientation: Orientation,
+    pub margins: Margins,
+    /// Font family, sizes, spacing and colours.
+    pub style: Style,
+    /// TrueType font used for emoji.
+    pub emoji_font: Option<String>,
+    /// TrueType fonts tried in order for characters the builtin fonts lack.
+    pub fallback_fonts: Vec<String>,
+    /// TOML or JSON theme file replacing `style`.
+    pub theme: Option<String>,
+    /// CSS file applied on top of the style or theme.
+    pub stylesheet: Option<String>,
+    /// Template of the PDF's file name, such as `{title}-{date}.pdf`.
+    pub output_name: Option<String>,
+    /// What happens when an export's path already holds a file.
+    pub existing_output: ExistingOutput,
+    /// Opens each export in the system's viewer once it is written.
+    pub open_when_done: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let options = RenderOptions::default();
+        Self {
+            page_size: options.page_size,
+            orientation: options.orientation,
+            margins: options.margins,
+            style: options.style,
+            emoji_font: options.emoji_font,
+            fallback_fonts: options.fallback_fonts,
+            theme: options.theme,
+            stylesheet: options.stylesheet,
+            output_name: options.output_name,
+            // Exports are numbered rather than replacing earlier ones unless asked.
+            existing_output: ExistingOutput::Increment,
+            open_when_done: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Rejects settings no conversion could start from, such as margins wider than the page.
+    pub fn validate(&self) -> Result<(), String> {
+        RenderOptions {
+            page_size: self.page_size,
+            orientation: self.orientation,
+            margins: self.margins,
+            style: self.style.clone(),
+            ..RenderOptions::default()
+        }
+        .validate()
+    }
+}
+
+/// The settings saved in `dir`, or the defaults when none have been saved yet.
+pub fn load(dir: &Path) -> Result<Settings, String> {
+    let path = dir.join(SETTINGS_FILE_NAME);
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let contents = fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&contents)
+        .map_err(|err| format!("Settings in {} could not be read: {err}", path.display()))
+}
+
+/// Saves `settings` in `dir`, creating it when needed. They are written to a temporary
+/// file that then replaces the old one, so a crash midway leaves the old settings whole.
+pub fn save(dir: &Path, settings: &Settings) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    let contents = serde_json::to_string_pretty(settings).map_err(|err| err.to_string())?;
+    let mut file = NamedTempFile::new_in(dir).map_err(|err| err.to_string())?;
+    file.write_all(contents.as_bytes())
+        .and_then(|()| file.as_file().sync_all())
+        .map_err(|err| err.to_string())?;
+    file.persist(dir.join(SETTINGS_FILE_NAME))
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
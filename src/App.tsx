@@ -1,5 +1,12 @@
-import { useCallback, useEffect, useMemo, useState, type KeyboardEvent } from "react";
-import DropZone, { type DropItem } from "./components/DropZone";
+import {
+  useCallback,
+  useEffect,
+  useMemo,
+  useState,
+  type FormEvent,
+  type KeyboardEvent,
+} from "react";
+import DropZone, { isArchive, type DropItem } from "./components/DropZone";
 import type { OpenDialogOptions } from "@tauri-apps/plugin-dialog";
 import appIcon from "./assets/app-icon.png";
 
@@ -16,12 +23,169 @@ type ProcessedInput = {
   markdown_files: string[];
   image_files: string[];
   root: string;
+  warnings: string[];
+  chapters: { path: string; title: string; depth: number; sections: string[] }[];
+  session_id: number;
 };
 
+type OutputWrite = "created" | "overwritten" | "incremented";
+
 type ConvertResult = {
   output_path: string;
+  output_paths: string[];
+  written: OutputWrite;
+  output_writes: OutputWrite[];
+};
+
+type RenderStage = "reading" | "rendering" | "finishing" | "writing";
+
+type ConversionProgress = {
+  job_id: number;
+  file_index: number;
+  file_count: number;
+  percent: number;
+  stage: RenderStage;
+};
+
+type JobStatus = "queued" | "running" | "done" | "failed";
+
+type ConversionJob = {
+  id: number;
+  output_path: string;
+  status: JobStatus;
+  result: ConvertResult | null;
+  error: string | null;
+};
+
+type PagePreview = {
+  page: number;
+  page_count: number;
+  png: number[];
+};
+
+type LiveUpdate = {
+  page_count: number;
+  first_changed: number | null;
+  changed: number[];
 };
 
+type LivePreview = {
+  files: string[];
+  // The file open in the editor and its text.
+  file: string;
+  text: string;
+  // Text of each file edited so far, by path; the files themselves are left as they are.
+  edited: Record<string, string>;
+  page: number;
+  pageCount: number;
+};
+
+type BatchResult = {
+  converted: ConvertResult[];
+  skipped: string[];
+  failed: string[];
+};
+
+type Margins = {
+  top: number;
+  bottom: number;
+  left: number;
+  right: number;
+};
+
+const DEFAULT_MARGINS: Margins = { top: 15, bottom: 15, left: 15, right: 15 };
+
+const MARGIN_SIDES: (keyof Margins)[] = ["top", "bottom", "left", "right"];
+
+type PageSize = "a3" | "a4" | "a5" | "letter" | "legal";
+type Orientation = "portrait" | "landscape";
+type FontFamily = "helvetica" | "times";
+
+const PAGE_SIZES: { value: PageSize; label: string }[] = [
+  { value: "a3", label: "A3" },
+  { value: "a4", label: "A4" },
+  { value: "a5", label: "A5" },
+  { value: "letter", label: "Letter" },
+  { value: "legal", label: "Legal" },
+];
+
+const ORIENTATIONS: { value: Orientation; label: string }[] = [
+  { value: "portrait", label: "Portrait" },
+  { value: "landscape", label: "Landscape" },
+];
+
+const FONT_FAMILIES: { value: FontFamily; label: string }[] = [
+  { value: "helvetica", label: "Helvetica" },
+  { value: "times", label: "Times" },
+];
+
+// The value of the one of `options` a select chose, typed as that option's value.
+const chosenOption = <T extends string>(
+  options: { value: T; label: string }[],
+  chosen: string,
+): T | undefined => options.find((option) => option.value === chosen)?.value;
+
+// The saved settings every conversion starts from, besides those with controls of their own.
+type PageSettings = {
+  page_size: PageSize;
+  orientation: Orientation;
+  // Font family, sizes, spacing and colours; the app only changes the family.
+  style: { font_family: FontFamily } & Record<string, unknown>;
+  emoji_font: string | null;
+  fallback_fonts: string[];
+  theme: string | null;
+  stylesheet: string | null;
+};
+
+type Settings = PageSettings & {
+  margins: Margins;
+  output_name: string | null;
+  existing_output: "ask" | "overwrite" | "increment";
+  open_when_done: boolean;
+};
+
+const DEFAULT_PAGE_SETTINGS: PageSettings = {
+  page_size: "a4",
+  orientation: "portrait",
+  style: { font_family: "helvetica" },
+  emoji_font: null,
+  fallback_fonts: [],
+  theme: null,
+  stylesheet: null,
+};
+
+type AssetFilters = {
+  include: string;
+  exclude: string;
+};
+
+const FILTER_FIELDS: { key: keyof AssetFilters; placeholder: string }[] = [
+  { key: "include", placeholder: "docs/**/*.md" },
+  { key: "exclude", placeholder: "node_modules/**, CHANGELOG.md" },
+];
+
+type ScanToggles = {
+  skip_hidden: boolean;
+  include_text: boolean;
+  include_html: boolean;
+  include_csv: boolean;
+  include_rst: boolean;
+};
+
+const SCAN_TOGGLES: { key: keyof ScanToggles; label: string }[] = [
+  { key: "skip_hidden", label: "Skip hidden files" },
+  { key: "include_text", label: "Include .txt files" },
+  { key: "include_html", label: "Include .html files" },
+  { key: "include_csv", label: "Include .csv files" },
+  { key: "include_rst", label: "Include .rst files" },
+];
+
+const splitPatterns = (value: string): string[] =>
+  value
+    .split(",")
+    .map((pattern) => pattern.trim())
+    .filter((pattern) => pattern.length > 0);
+
 type InvokeArgs = Record<string, unknown>;
 
 const isTauriRuntime = () =>
@@ -35,6 +199,50 @@ const callTauri = async <TResult,>(command: string, args: InvokeArgs) => {
   return invoke<TResult>(command, args);
 };
 
+// Sent by the conversion jobs convert_to_pdf queues as rendering goes on.
+const PROGRESS_EVENT = "conversion://progress";
+const JOB_POLL_MS = 400;
+// How long changes to the settings have to settle before they are saved.
+const SETTINGS_SAVE_DELAY_MS = 500;
+// Sent by the live preview with each page that changed.
+const PREVIEW_PAGE_EVENT = "preview://page";
+// How long typing has to pause before the live preview is laid out again.
+const LIVE_PREVIEW_DELAY_MS = 300;
+
+const JOB_STATUS_LABELS: Record<JobStatus, string> = {
+  queued: "Queued",
+  running: "Rendering",
+  done: "Done",
+  failed: "Failed",
+};
+
+const isActiveJob = (job: ConversionJob) => job.status === "queued" || job.status === "running";
+
+const fileName = (path: string) => path.split(/[\\/]/).pop() ?? path;
+
+const pngUrl = (png: number[]) =>
+  URL.createObjectURL(new Blob([new Uint8Array(png)], { type: "image/png" }));
+
+const STAGE_LABELS: Record<RenderStage, string> = {
+  reading: "Reading",
+  rendering: "Rendering",
+  finishing: "Numbering pages",
+  writing: "Writing PDF",
+};
+
+const formatProgress = (progress: ConversionProgress) =>
+  progress.stage === "reading" || progress.stage === "rendering"
+    ? `${STAGE_LABELS[progress.stage]} file ${progress.file_index + 1} of ${progress.file_count}`
+    : STAGE_LABELS[progress.stage];
+
+const listenTauri = async <TPayload,>(event: string, handler: (payload: TPayload) => void) => {
+  if (!isTauriRuntime()) {
+    throw new Error("Tauri runtime not available. Open this inside the Tauri app.");
+  }
+  const { listen } = await import("@tauri-apps/api/event");
+  return listen<TPayload>(event, (message) => handler(message.payload));
+};
+
 const openDialog = async (options: OpenDialogOptions): Promise<string[] | null> => {
   if (!isTauriRuntime()) {
     throw new Error("Tauri runtime not available. Open this inside the Tauri app.");
@@ -47,6 +255,27 @@ const openDialog = async (options: OpenDialogOptions): Promise<string[] | null>
   return result ? [result] : null;
 };
 
+const OUTPUT_FILE_NAME = "markdown_export.pdf";
+
+type ExportFormat = "pdf" | "txt";
+
+const EXPORT_FILTERS: Record<ExportFormat, { name: string; extensions: string[] }> = {
+  pdf: { name: "PDF", extensions: ["pdf"] },
+  txt: { name: "Text", extensions: ["txt"] },
+};
+
+// Asks where to save the export, starting at `defaultPath`; `null` when the user cancels.
+const saveExportDialog = async (
+  defaultPath: string,
+  format: ExportFormat = "pdf"
+): Promise<string | null> => {
+  if (!isTauriRuntime()) {
+    throw new Error("Tauri runtime not available. Open this inside the Tauri app.");
+  }
+  const { save } = await import("@tauri-apps/plugin-dialog");
+  return save({ defaultPath, filters: [EXPORT_FILTERS[format]] });
+};
+
 const getErrorMessage = (error: unknown): string => {
   if (error instanceof Error) {
     return error.message;
@@ -63,24 +292,121 @@ const getErrorMessage = (error: unknown): string => {
   return "Unexpected error.";
 };
 
+// Errors from `process_input` for an encrypted zip, ending with the archive path.
+const LOCKED_ARCHIVE_PATTERN = /^(?:Password required|Wrong password) for (.+)$/;
+
+const formatWarnings = (warnings: string[]): string =>
+  warnings.map((warning) => ` ${warning}.`).join("");
+
+const formatWritten = (result: ConvertResult): string =>
+  (result.output_paths.length > 1 ? ` Split into ${result.output_paths.length} parts.` : "") +
+  (result.written === "overwritten" ? " The earlier file was replaced." : "");
+
+// Opens the export in the default viewer; a note for the status message when it fails.
+const openExport = async (path: string): Promise<string> => {
+  try {
+    await callTauri<void>("open_output", { path });
+    return "";
+  } catch (error) {
+    return ` ${getErrorMessage(error)}.`;
+  }
+};
+
 export default function App() {
   const [items, setItems] = useState<DropItem[]>([]);
   const [state, setState] = useState<ProcessState>("idle");
   const [message, setMessage] = useState<string>(
-    "Drop a markdown file, directory, or zip to begin."
+    "Drop a markdown file, directory, or archive to begin."
   );
   const [outputPath, setOutputPath] = useState<string | null>(null);
+  // Every file of the last export, when it was split into parts.
+  const [outputParts, setOutputParts] = useState<string[]>([]);
+  const [jobs, setJobs] = useState<ConversionJob[]>([]);
+  const [preview, setPreview] = useState<{ page: number; pageCount: number; url: string } | null>(
+    null
+  );
+  const [livePreview, setLivePreview] = useState<LivePreview | null>(null);
+  // Blob URL of each page image of the live preview, by page number.
+  const [livePages, setLivePages] = useState<Record<number, string>>({});
+  // Edits waiting for typing to pause before they are sent to the live preview, by path.
+  const [liveEdits, setLiveEdits] = useState<Record<string, string>>({});
+  const [jobProgress, setJobProgress] = useState<Record<number, ConversionProgress>>({});
   const [processedInput, setProcessedInput] = useState<ProcessedInput | null>(null);
   const [manualOrderEnabled, setManualOrderEnabled] = useState(false);
   const [orderedMarkdown, setOrderedMarkdown] = useState<string[]>([]);
   const [omittedMarkdown, setOmittedMarkdown] = useState<string[]>([]);
   const [isBooting, setIsBooting] = useState(true);
+  const [margins, setMargins] = useState<Margins>(DEFAULT_MARGINS);
+  const [pageSettings, setPageSettings] = useState<PageSettings>(DEFAULT_PAGE_SETTINGS);
+  // Set once the saved settings are in place, so the defaults never overwrite them.
+  const [settingsLoaded, setSettingsLoaded] = useState(false);
+  const [filters, setFilters] = useState<AssetFilters>({ include: "", exclude: "" });
+  const [scanToggles, setScanToggles] = useState<ScanToggles>({
+    skip_hidden: true,
+    include_text: false,
+    include_html: false,
+    include_csv: false,
+    include_rst: false,
+  });
+  const [pastedMarkdown, setPastedMarkdown] = useState("");
+  const [lockedArchive, setLockedArchive] = useState<string | null>(null);
+  const [archivePassword, setArchivePassword] = useState("");
+  const [keepQueue, setKeepQueue] = useState(false);
+  const [notionExport, setNotionExport] = useState(false);
+  const [obsidianVault, setObsidianVault] = useState(false);
+  const [siteContent, setSiteContent] = useState(false);
+  const [outputName, setOutputName] = useState("");
+  const [numberExports, setNumberExports] = useState(true);
+  const [openWhenDone, setOpenWhenDone] = useState(false);
+  const [pdfsBefore, setPdfsBefore] = useState<string[]>([]);
+  const [pdfsAfter, setPdfsAfter] = useState<string[]>([]);
+  const [pageRange, setPageRange] = useState("");
+  const [sectionTitles, setSectionTitles] = useState("");
+  const [splitPages, setSplitPages] = useState("");
+  const [splitMegabytes, setSplitMegabytes] = useState("");
+  const [perFolder, setPerFolder] = useState(false);
 
   useEffect(() => {
     const timeout = window.setTimeout(() => setIsBooting(false), 900);
     return () => window.clearTimeout(timeout);
   }, []);
 
+  useEffect(() => {
+    if (!isTauriRuntime()) {
+      return;
+    }
+    callTauri<Settings>("get_settings", {})
+      .then(({ margins, output_name, existing_output, open_when_done, ...saved }) => {
+        setPageSettings(saved);
+        setMargins(margins);
+        setOutputName(output_name ?? "");
+        setNumberExports(existing_output === "increment");
+        setOpenWhenDone(open_when_done);
+        setSettingsLoaded(true);
+      })
+      .catch((error) => setMessage(`Settings could not be loaded: ${getErrorMessage(error)}`));
+  }, []);
+
+  // Saves the settings once they stop changing, for the next time the app starts.
+  useEffect(() => {
+    if (!settingsLoaded) {
+      return;
+    }
+    const settings: Settings = {
+      ...pageSettings,
+      margins,
+      output_name: outputName.trim() || null,
+      existing_output: numberExports ? "increment" : "overwrite",
+      open_when_done: openWhenDone,
+    };
+    const timeout = window.setTimeout(() => {
+      // Margins that do not fit the page are reported when converting; they are saved
+      // once fixed.
+      callTauri("set_settings", { settings }).catch(() => undefined);
+    }, SETTINGS_SAVE_DELAY_MS);
+    return () => window.clearTimeout(timeout);
+  }, [margins, numberExports, openWhenDone, outputName, pageSettings, settingsLoaded]);
+
   const statusTone = useMemo(() => {
     switch (state) {
       case "processing":
@@ -96,7 +422,56 @@ export default function App() {
 
   const omittedSet = useMemo(() => new Set(omittedMarkdown), [omittedMarkdown]);
 
-  const handleDropItems = useCallback((nextItems: DropItem[]) => {
+  const handleProcessError = useCallback((error: unknown) => {
+    const detail = getErrorMessage(error);
+    setState("error");
+    setMessage(detail);
+    setLockedArchive(LOCKED_ARCHIVE_PATTERN.exec(detail)?.[1] ?? null);
+  }, []);
+
+  // Files already scanned stay collected; only the added items are scanned.
+  const handleAddItems = useCallback(
+    async (nextItems: DropItem[]) => {
+      const queued = new Set(items.map((item) => item.path));
+      const added = nextItems.filter((item) => !queued.has(item.path));
+      if (added.length === 0) {
+        setMessage("Those items are already queued.");
+        return;
+      }
+      setItems((current) => [...current, ...added]);
+      setOutputPath(null);
+      if (!processedInput) {
+        setState("idle");
+        setMessage("Items added. Ready to convert.");
+        return;
+      }
+
+      setState("processing");
+      setMessage("Scanning the added items.");
+      try {
+        const merged = await callTauri<ProcessedInput>("add_inputs", {
+          sessionId: processedInput.session_id,
+          inputPaths: added.map((item) => item.path),
+        });
+        setProcessedInput(merged);
+        if (manualOrderEnabled) {
+          setOrderedMarkdown((current) => [
+            ...current,
+            ...merged.markdown_files.filter((path) => !current.includes(path)),
+          ]);
+        }
+        setState("idle");
+        const warnings = formatWarnings(merged.warnings.slice(processedInput.warnings.length));
+        setMessage(`Items added. Ready to convert.${warnings}`);
+      } catch (error) {
+        setItems((current) => current.filter((item) => !added.includes(item)));
+        handleProcessError(error);
+      }
+    },
+    [handleProcessError, items, manualOrderEnabled, processedInput]
+  );
+
+  const handleReplaceItems = useCallback((nextItems: DropItem[]) => {
     setItems(nextItems);
     setState("idle");
     setOutputPath(null);
@@ -105,8 +480,20 @@ export default function App() {
     setManualOrderEnabled(false);
     setOrderedMarkdown([]);
     setOmittedMarkdown([]);
+    setLockedArchive(null);
   }, []);
 
+  const handleDropItems = useCallback(
+    (nextItems: DropItem[]) => {
+      if (keepQueue && items.length > 0) {
+        void handleAddItems(nextItems);
+      } else {
+        handleReplaceItems(nextItems);
+      }
+    },
+    [handleAddItems, handleReplaceItems, items.length, keepQueue]
+  );
+
   const handleDropError = useCallback((nextMessage: string) => {
     setState("error");
     setMessage(nextMessage);
@@ -118,7 +505,10 @@ export default function App() {
         multiple: true,
         filters: [
           { name: "Markdown", extensions: ["md", "markdown"] },
-          { name: "Zip", extensions: ["zip"] },
+          { name: "HTML", extensions: ["html", "htm"] },
+          { name: "CSV", extensions: ["csv"] },
+          { name: "reStructuredText", extensions: ["rst"] },
+          { name: "Archive", extensions: ["zip", "tar", "gz", "tgz", "7z"] },
         ],
       });
 
@@ -128,7 +518,7 @@ export default function App() {
 
       const nextItems = paths.map((path) => {
         const lower = path.toLowerCase();
-        const kind: DropItem["kind"] = lower.endsWith(".zip") ? "zip" : "file";
+        const kind: DropItem["kind"] = isArchive(lower) ? "archive" : "file";
         return { name: path.split("/").pop() ?? path, path, kind };
       });
       handleDropItems(nextItems);
@@ -158,18 +548,54 @@ export default function App() {
     }
   }, [handleDropError, handleDropItems]);
 
+  const handleUrl = useCallback(
+    (url: string) => {
+      const name = url.split(/[?#]/)[0].split("/").filter(Boolean).pop() ?? url;
+      handleDropItems([{ name, path: url, kind: "url" }]);
+    },
+    [handleDropItems]
+  );
+
   const ensureProcessedInput = useCallback(async (): Promise<ProcessedInput> => {
     const [firstItem] = items;
     if (!firstItem) {
-      throw new Error("Add at least one markdown file, directory, or zip.");
+      throw new Error("Add at least one markdown file, directory, or archive.");
     }
     if (processedInput) {
       return processedInput;
     }
     return callTauri<ProcessedInput>("process_input", {
       inputPaths: items.map((item) => item.path),
+      filters: {
+        include: splitPatterns(filters.include),
+        exclude: splitPatterns(filters.exclude),
+        ...scanToggles,
+      },
     });
-  }, [items, processedInput]);
+  }, [filters, items, processedInput, scanToggles]);
+
+  const handleUnlockArchive = useCallback(
+    async (event: FormEvent<HTMLFormElement>) => {
+      event.preventDefault();
+      if (!lockedArchive) {
+        return;
+      }
+      try {
+        await callTauri<void>("set_archive_password", {
+          path: lockedArchive,
+          password: archivePassword,
+        });
+        setLockedArchive(null);
+        setArchivePassword("");
+        setState("idle");
+        setMessage("Password saved. Convert again to unpack the archive.");
+      } catch (error) {
+        setState("error");
+        setMessage(getErrorMessage(error));
+      }
+    },
+    [archivePassword, lockedArchive]
+  );
 
   const handleManualOrderToggle = useCallback(async () => {
     if (!manualOrderEnabled) {
@@ -182,10 +608,10 @@ export default function App() {
         setOmittedMarkdown([]);
         setManualOrderEnabled(true);
         setState("idle");
-        setMessage("Manual ordering enabled. Arrange markdown files before converting.");
+        const warnings = formatWarnings(processed.warnings);
+        setMessage(`Manual ordering enabled. Arrange markdown files before converting.${warnings}`);
       } catch (error) {
-        setState("error");
-        setMessage(getErrorMessage(error));
+        handleProcessError(error);
       }
       return;
     }
@@ -198,7 +624,7 @@ export default function App() {
     } else {
       setMessage("Manual ordering disabled. Ready to convert.");
     }
-  }, [ensureProcessedInput, manualOrderEnabled, outputPath, state]);
+  }, [ensureProcessedInput, handleProcessError, manualOrderEnabled, outputPath, state]);
 
   const handleMoveMarkdown = useCallback((index: number, delta: number) => {
     setOrderedMarkdown((current) => {
@@ -250,42 +676,537 @@ export default function App() {
     setOmittedMarkdown([]);
   }, []);
 
-  const handleConvert = useCallback(async () => {
+  const handleMarginChange = useCallback((side: keyof Margins, value: string) => {
+    setMargins((current) => ({ ...current, [side]: Number(value) }));
+  }, []);
+
+  // The files have to be collected again when the filters change.
+  const clearCollectedFiles = useCallback(() => {
+    setProcessedInput(null);
+    setManualOrderEnabled(false);
+    setOrderedMarkdown([]);
+    setOmittedMarkdown([]);
+  }, []);
+
+  const handleFilterChange = useCallback(
+    (key: keyof AssetFilters, value: string) => {
+      setFilters((current) => ({ ...current, [key]: value }));
+      clearCollectedFiles();
+    },
+    [clearCollectedFiles]
+  );
+
+  const handleScanToggleChange = useCallback(
+    (key: keyof ScanToggles, value: boolean) => {
+      setScanToggles((current) => ({ ...current, [key]: value }));
+      clearCollectedFiles();
+    },
+    [clearCollectedFiles]
+  );
+
+  const renderOptions = useMemo(
+    () => ({
+      ...pageSettings,
+      margins,
+      notion_export: notionExport,
+      obsidian_vault: obsidianVault,
+      // Drafts are left out and shortcodes expanded.
+      site_content: siteContent ? { skip_drafts: true } : null,
+      output_name: outputName.trim() || null,
+      existing_output: numberExports ? "increment" : "overwrite",
+      pdfs_before: pdfsBefore,
+      pdfs_after: pdfsAfter,
+      page_range: pageRange.trim() || null,
+      sections: sectionTitles
+        .split(",")
+        .map((title) => title.trim())
+        .filter(Boolean),
+      split:
+        splitPages || splitMegabytes
+          ? {
+              max_pages: splitPages ? Number(splitPages) : null,
+              max_megabytes: splitMegabytes ? Number(splitMegabytes) : null,
+            }
+          : null,
+    }),
+    [
+      pageSettings,
+      margins,
+      notionExport,
+      obsidianVault,
+      siteContent,
+      outputName,
+      numberExports,
+      pdfsBefore,
+      pdfsAfter,
+      pageRange,
+      sectionTitles,
+      splitPages,
+      splitMegabytes,
+    ]
+  );
+
+  // The scanned input, and the markdown files of it to render in the order chosen.
+  const prepareInput = useCallback(async () => {
+    const processed = await ensureProcessedInput();
+    const isManualListReady = manualOrderEnabled && orderedMarkdown.length > 0;
+    const input = isManualListReady
+      ? await callTauri<ProcessedInput>("arrange_markdown", {
+          input: processed,
+          order: orderedMarkdown,
+          excluded: omittedMarkdown,
+        })
+      : processed;
+    return { processed, input };
+  }, [ensureProcessedInput, manualOrderEnabled, orderedMarkdown, omittedMarkdown]);
+
+  const handleConvert = useCallback(async (format: ExportFormat) => {
     if (items.length === 0) {
       setState("error");
-      setMessage("Add at least one markdown file, directory, or zip.");
+      setMessage("Add at least one markdown file, directory, or archive.");
+      return;
+    }
+    if (MARGIN_SIDES.some((side) => !Number.isFinite(margins[side]) || margins[side] < 0)) {
+      setState("error");
+      setMessage("Page margins must be zero or more millimetres.");
       return;
     }
 
     setState("processing");
-    setMessage("Scanning files, resolving images, and composing PDF.");
+    setMessage(
+      format === "pdf"
+        ? "Scanning files, resolving images, and composing PDF."
+        : "Scanning files and composing the text export."
+    );
 
     try {
-      const processed = await ensureProcessedInput();
-      const isManualListReady = manualOrderEnabled && orderedMarkdown.length > 0;
-      const filteredMarkdown = isManualListReady
-        ? orderedMarkdown.filter((path) => !omittedMarkdown.includes(path))
-        : processed.markdown_files;
-      if (isManualListReady && filteredMarkdown.length === 0) {
+      const { processed, input } = await prepareInput();
+      const suggestedPath = await callTauri<string>("suggest_output_path", {
+        input,
+        options: renderOptions,
+        extension: format,
+      });
+      const chosenPath = await saveExportDialog(suggestedPath, format);
+      if (!chosenPath) {
+        setState("idle");
+        setMessage("Export cancelled.");
+        return;
+      }
+      // The save dialog has already confirmed replacing a file the user picked.
+      const args = {
+        input,
+        outputPath: chosenPath,
+        options: { ...renderOptions, existing_output: "overwrite" },
+      };
+      if (format === "pdf") {
+        // Rendering goes on in the background; the job poll reports the outcome.
+        const jobId = await callTauri<number>("convert_to_pdf", args);
+        setJobs(await callTauri<ConversionJob[]>("list_jobs", {}));
+        setState("idle");
+        setMessage(`PDF export queued as job ${jobId}.${formatWarnings(processed.warnings)}`);
+        return;
+      }
+      const result = await callTauri<ConvertResult>("convert_to_text", args);
+
+      const opened = openWhenDone ? await openExport(result.output_path) : "";
+      setOutputPath(result.output_path);
+      setOutputParts(result.output_paths);
+      setState("success");
+      setMessage(
+        `Text exported successfully.${formatWritten(result)}${formatWarnings(processed.warnings)}${opened}`
+      );
+    } catch (error) {
+      handleProcessError(error);
+    }
+  }, [handleProcessError, items.length, margins, openWhenDone, prepareInput, renderOptions]);
+
+  // Lays out the document as it would be exported and shows page `page` of it.
+  const handlePreview = useCallback(
+    async (page: number) => {
+      if (items.length === 0) {
         setState("error");
-        setMessage("Select at least one markdown file to convert.");
+        setMessage("Add at least one markdown file, directory, or archive.");
         return;
       }
-      const input = isManualListReady
-        ? { ...processed, markdown_files: filteredMarkdown }
-        : processed;
+      setState("processing");
+      setMessage(`Laying out the document to preview page ${page}.`);
+      try {
+        const { input } = await prepareInput();
+        const result = await callTauri<PagePreview>("render_preview", {
+          input,
+          page,
+          options: renderOptions,
+        });
+        const url = pngUrl(result.png);
+        setPreview((current) => {
+          if (current) {
+            URL.revokeObjectURL(current.url);
+          }
+          return { page: result.page, pageCount: result.page_count, url };
+        });
+        setState("idle");
+        setMessage(`Previewing page ${result.page} of ${result.page_count}.`);
+      } catch (error) {
+        handleProcessError(error);
+      }
+    },
+    [handleProcessError, items.length, prepareInput, renderOptions]
+  );
+
+  const handleClosePreview = useCallback(() => {
+    setPreview((current) => {
+      if (current) {
+        URL.revokeObjectURL(current.url);
+      }
+      return null;
+    });
+  }, []);
+
+  // Lays out the document for the split-screen editor, with its first file open.
+  const handleStartLivePreview = useCallback(async () => {
+    if (items.length === 0) {
+      setState("error");
+      setMessage("Add at least one markdown file, directory, or archive.");
+      return;
+    }
+    setState("processing");
+    setMessage("Laying out the document for the live preview.");
+    setLivePages((current) => {
+      Object.values(current).forEach((url) => URL.revokeObjectURL(url));
+      return {};
+    });
+    try {
+      const { input } = await prepareInput();
+      const update = await callTauri<LiveUpdate>("start_live_preview", {
+        input,
+        options: renderOptions,
+      });
+      const file = input.markdown_files[0];
+      const text = await callTauri<string>("live_preview_source", { path: file });
+      setLiveEdits({});
+      setLivePreview({
+        files: input.markdown_files,
+        file,
+        text,
+        edited: {},
+        page: 1,
+        pageCount: update.page_count,
+      });
+      setState("idle");
+      setMessage(`Live preview of ${update.page_count} pages. Edits are previewed, not saved.`);
+    } catch (error) {
+      handleProcessError(error);
+    }
+  }, [handleProcessError, items.length, prepareInput, renderOptions]);
+
+  const handleLiveFileChange = useCallback(
+    async (file: string) => {
+      try {
+        const text =
+          livePreview?.edited[file] ??
+          (await callTauri<string>("live_preview_source", { path: file }));
+        setLivePreview((current) => (current ? { ...current, file, text } : current));
+      } catch (error) {
+        handleProcessError(error);
+      }
+    },
+    [handleProcessError, livePreview]
+  );
 
-      const result = await callTauri<ConvertResult>("convert_to_pdf", { input });
+  const handleLiveEdit = useCallback((file: string, text: string) => {
+    setLivePreview((current) =>
+      current ? { ...current, text, edited: { ...current.edited, [file]: text } } : current
+    );
+    setLiveEdits((current) => ({ ...current, [file]: text }));
+  }, []);
 
+  const handleStopLivePreview = useCallback(async () => {
+    setLiveEdits({});
+    setLivePreview(null);
+    setLivePages((current) => {
+      Object.values(current).forEach((url) => URL.revokeObjectURL(url));
+      return {};
+    });
+    try {
+      await callTauri("stop_live_preview", {});
+    } catch (error) {
+      handleProcessError(error);
+    }
+  }, [handleProcessError]);
+
+  // Sends the edits to the live preview once typing pauses; the pages that changed arrive
+  // through PREVIEW_PAGE_EVENT.
+  useEffect(() => {
+    const edits = Object.entries(liveEdits);
+    if (edits.length === 0) {
+      return;
+    }
+    const timeout = window.setTimeout(async () => {
+      try {
+        let update: LiveUpdate | null = null;
+        for (const [path, contents] of edits) {
+          update = await callTauri<LiveUpdate>("update_live_preview", { path, contents });
+        }
+        // Edits made meanwhile stay pending.
+        setLiveEdits((current) =>
+          Object.fromEntries(
+            Object.entries(current).filter(([path, text]) => liveEdits[path] !== text)
+          )
+        );
+        if (!update) {
+          return;
+        }
+        const pageCount = update.page_count;
+        setLivePreview((current) =>
+          current
+            ? {
+                ...current,
+                page: Math.min(current.page, pageCount),
+                pageCount,
+              }
+            : current
+        );
+        setLivePages((current) => {
+          const pages = { ...current };
+          for (const page of Object.keys(pages).map(Number)) {
+            if (page > pageCount) {
+              URL.revokeObjectURL(pages[page]);
+              delete pages[page];
+            }
+          }
+          return pages;
+        });
+      } catch (error) {
+        setMessage(`Live preview failed: ${getErrorMessage(error)}`);
+      }
+    }, LIVE_PREVIEW_DELAY_MS);
+    return () => window.clearTimeout(timeout);
+  }, [liveEdits]);
+
+  useEffect(() => {
+    if (!isTauriRuntime()) {
+      return;
+    }
+    const stopListening = listenTauri<PagePreview>(PREVIEW_PAGE_EVENT, (preview) => {
+      const url = pngUrl(preview.png);
+      setLivePages((current) => {
+        const previous = current[preview.page];
+        if (previous) {
+          URL.revokeObjectURL(previous);
+        }
+        return { ...current, [preview.page]: url };
+      });
+    });
+    return () => {
+      void stopListening.then((stop) => stop());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!isTauriRuntime()) {
+      return;
+    }
+    const stopListening = listenTauri<ConversionProgress>(PROGRESS_EVENT, (progress) =>
+      setJobProgress((current) => ({ ...current, [progress.job_id]: progress }))
+    );
+    return () => {
+      void stopListening.then((stop) => stop());
+    };
+  }, []);
+
+  // Polls the queued exports until they finish, reporting each as it does.
+  useEffect(() => {
+    if (!jobs.some(isActiveJob)) {
+      return;
+    }
+    const timeout = window.setTimeout(async () => {
+      try {
+        const latest = await callTauri<ConversionJob[]>("list_jobs", {});
+        const finished = latest.filter(
+          (job) =>
+            !isActiveJob(job) && jobs.some((known) => known.id === job.id && isActiveJob(known))
+        );
+        setJobs(latest);
+        for (const job of finished) {
+          if (job.result) {
+            const opened = openWhenDone ? await openExport(job.result.output_path) : "";
+            setOutputPath(job.result.output_path);
+            setOutputParts(job.result.output_paths);
+            // A scan or dialog under way keeps its own status.
+            if (state !== "processing") {
+              setState("success");
+              setMessage(`PDF exported successfully.${formatWritten(job.result)}${opened}`);
+            }
+          } else if (state !== "processing") {
+            setState("error");
+            setMessage(`Export ${job.id} failed: ${job.error ?? "unknown error"}`);
+          }
+        }
+      } catch (error) {
+        handleProcessError(error);
+      }
+    }, JOB_POLL_MS);
+    return () => window.clearTimeout(timeout);
+  }, [handleProcessError, jobs, openWhenDone, state]);
+
+  // Writes one PDF per subfolder of the single folder queued, into a folder the user picks.
+  const handleConvertFolders = useCallback(async () => {
+    const [folder] = items;
+    if (items.length !== 1 || folder.kind !== "directory") {
+      setState("error");
+      setMessage("One PDF per subfolder needs a single folder in the queue.");
+      return;
+    }
+    if (MARGIN_SIDES.some((side) => !Number.isFinite(margins[side]) || margins[side] < 0)) {
+      setState("error");
+      setMessage("Page margins must be zero or more millimetres.");
+      return;
+    }
+
+    try {
+      const chosen = await openDialog({ directory: true, defaultPath: folder.path });
+      if (!chosen) {
+        setState("idle");
+        setMessage("Export cancelled.");
+        return;
+      }
+      setState("processing");
+      setMessage("Composing one PDF per subfolder.");
+      const result = await callTauri<BatchResult>("convert_subfolders", {
+        folder: folder.path,
+        outputDir: chosen[0],
+        filters: {
+          include: splitPatterns(filters.include),
+          exclude: splitPatterns(filters.exclude),
+          ...scanToggles,
+        },
+        options: renderOptions,
+      });
+      const paths = result.converted.flatMap((converted) => converted.output_paths);
+      setOutputPath(paths[0] ?? null);
+      setOutputParts(paths);
+      const skipped =
+        result.skipped.length > 0
+          ? ` Skipped without markdown: ${result.skipped.join(", ")}.`
+          : "";
+      const failed = result.failed.map((failure) => ` ${failure}.`).join("");
+      setState(result.failed.length > 0 ? "error" : "success");
+      setMessage(`Exported ${result.converted.length} PDFs.${skipped}${failed}`);
+    } catch (error) {
+      handleProcessError(error);
+    }
+  }, [filters, handleProcessError, items, margins, renderOptions, scanToggles]);
+
+  const handleConvertText = useCallback(async () => {
+    if (pastedMarkdown.trim().length === 0) {
+      setState("error");
+      setMessage("Paste some markdown to convert.");
+      return;
+    }
+    if (MARGIN_SIDES.some((side) => !Number.isFinite(margins[side]) || margins[side] < 0)) {
+      setState("error");
+      setMessage("Page margins must be zero or more millimetres.");
+      return;
+    }
+
+    setState("processing");
+    setMessage("Composing PDF from the pasted markdown.");
+
+    try {
+      const { downloadDir, join } = await import("@tauri-apps/api/path");
+      const chosenPath = await saveExportDialog(await join(await downloadDir(), OUTPUT_FILE_NAME));
+      if (!chosenPath) {
+        setState("idle");
+        setMessage("Export cancelled.");
+        return;
+      }
+      const result = await callTauri<ConvertResult>("convert_text_to_pdf", {
+        markdown: pastedMarkdown,
+        outputPath: chosenPath,
+        options: { ...pageSettings, margins, existing_output: "overwrite" },
+      });
+      const opened = openWhenDone ? await openExport(result.output_path) : "";
+      setOutputPath(result.output_path);
+      setOutputParts(result.output_paths);
+      setState("success");
+      setMessage(`PDF exported successfully.${formatWritten(result)}${opened}`);
+    } catch (error) {
+      setState("error");
+      setMessage(getErrorMessage(error));
+    }
+  }, [margins, openWhenDone, pageSettings, pastedMarkdown]);
+
+  // PDFs whose pages go before or after the document, replacing the earlier choice.
+  const handleChoosePdfs = useCallback(
+    async (setPaths: (paths: string[]) => void) => {
+      try {
+        const paths = await openDialog({ multiple: true, filters: [EXPORT_FILTERS.pdf] });
+        if (paths) {
+          setPaths(paths);
+        }
+      } catch (error) {
+        handleProcessError(error);
+      }
+    },
+    [handleProcessError]
+  );
+
+  const handleMergePdfs = useCallback(async () => {
+    try {
+      const inputs = await openDialog({ multiple: true, filters: [EXPORT_FILTERS.pdf] });
+      if (!inputs) {
+        return;
+      }
+      if (inputs.length < 2) {
+        setState("error");
+        setMessage("Choose at least two PDFs to merge.");
+        return;
+      }
+      const { dirname, join } = await import("@tauri-apps/api/path");
+      const chosenPath = await saveExportDialog(await join(await dirname(inputs[0]), "merged.pdf"));
+      if (!chosenPath) {
+        setState("idle");
+        setMessage("Merge cancelled.");
+        return;
+      }
+      setState("processing");
+      setMessage(`Merging ${inputs.length} PDFs.`);
+      const result = await callTauri<ConvertResult>("merge_pdfs", {
+        inputs,
+        outputPath: chosenPath,
+      });
+      const opened = openWhenDone ? await openExport(result.output_path) : "";
       setOutputPath(result.output_path);
+      setOutputParts(result.output_paths);
       setState("success");
-      setMessage("PDF exported successfully.");
+      setMessage(`PDFs merged successfully.${formatWritten(result)}${opened}`);
     } catch (error) {
-      const detail = getErrorMessage(error);
+      handleProcessError(error);
+    }
+  }, [handleProcessError, openWhenDone]);
+
+  const handleOpenOutput = useCallback(async (path: string | null) => {
+    if (!path) {
+      return;
+    }
+    const note = await openExport(path);
+    if (note) {
       setState("error");
-      setMessage(detail);
+      setMessage(note.trim());
     }
-  }, [ensureProcessedInput, items.length, manualOrderEnabled, orderedMarkdown, omittedMarkdown]);
+  }, []);
+
+  const handleRevealOutput = useCallback(async () => {
+    if (!outputPath) {
+      return;
+    }
+    try {
+      await callTauri<void>("reveal_in_folder", { path: outputPath });
+    } catch (error) {
+      setState("error");
+      setMessage(getErrorMessage(error));
+    }
+  }, [outputPath]);
 
   return (
     <div className="min-h-screen bg-ink-950 text-ink-100">
@@ -316,7 +1237,7 @@ export default function App() {
               Markdown to PDF, engineered for messy project folders.
             </h1>
             <p className="mt-4 max-w-2xl text-sm text-ink-200">
-              Drop a folder, markdown file, or zip. The pipeline resolves linked markdown
+              Drop a folder, markdown file, or archive. The pipeline resolves linked markdown
               and images, then exports a single PDF.
             </p>
           </div>
@@ -347,6 +1268,38 @@ export default function App() {
             onError={handleDropError}
             onBrowseFiles={handleBrowseFiles}
             onBrowseFolder={handleBrowseFolder}
+            onUrl={handleUrl}
+          />
+        </section>
+
+        <section
+          className="float-in rounded-3xl border border-ink-800/80 bg-ink-900/70 p-6"
+          style={{ animationDelay: "170ms" }}
+        >
+          <div className="flex items-center justify-between gap-3">
+            <div>
+              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">Paste Markdown</p>
+              <p className="mt-2 text-sm text-ink-200">
+                Convert a snippet without saving it first. The PDF goes to your downloads folder.
+              </p>
+            </div>
+            <button
+              type="button"
+              className="rounded-full bg-ink-100 px-4 py-2 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-950 transition hover:bg-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+              onClick={handleConvertText}
+              disabled={state === "processing" || pastedMarkdown.trim().length === 0}
+            >
+              Convert Text
+            </button>
+          </div>
+          <textarea
+            value={pastedMarkdown}
+            onChange={(event) => setPastedMarkdown(event.target.value)}
+            placeholder="# Notes"
+            aria-label="Markdown text"
+            rows={6}
+            disabled={state === "processing"}
+            className="mt-4 w-full resize-y rounded-2xl border border-ink-700 bg-ink-950/70 px-4 py-3 font-mono text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
           />
         </section>
 
@@ -365,16 +1318,126 @@ export default function App() {
               {STATUS_LABELS[state]}
             </div>
             <p className="text-sm text-ink-200">{message}</p>
+            {lockedArchive ? (
+              <form className="flex flex-wrap items-center gap-3" onSubmit={handleUnlockArchive}>
+                <input
+                  type="password"
+                  value={archivePassword}
+                  onChange={(event) => setArchivePassword(event.target.value)}
+                  placeholder="Archive password"
+                  aria-label="Archive password"
+                  className="w-56 rounded-lg border border-ink-700 bg-ink-900 px-3 py-1 text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                />
+                <button
+                  type="submit"
+                  className="rounded-full bg-ink-100 px-4 py-2 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-950 transition hover:bg-ink-50"
+                >
+                  Unlock
+                </button>
+              </form>
+            ) : null}
             {outputPath ? (
               <div className="rounded-2xl border border-ink-700/60 bg-ink-950/60 p-4 text-xs text-ink-200">
-                <p className="mb-2 uppercase tracking-[0.2em] text-ink-200">Output</p>
-                <p className="break-all text-ink-100">{outputPath}</p>
+                <div className="mb-2 flex items-center justify-between gap-3">
+                  <p className="uppercase tracking-[0.2em] text-ink-200">Output</p>
+                  <div className="flex items-center gap-2">
+                    <button
+                      type="button"
+                      onClick={handleRevealOutput}
+                      className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50"
+                    >
+                      Show in Folder
+                    </button>
+                    <button
+                      type="button"
+                      onClick={() => handleOpenOutput(outputPath)}
+                      className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50"
+                    >
+                      Open
+                    </button>
+                  </div>
+                </div>
+                {outputParts.length > 1 ? (
+                  outputParts.map((path) => (
+                    <p key={path} className="break-all text-ink-100">
+                      {path}
+                    </p>
+                  ))
+                ) : (
+                  <p className="break-all text-ink-100">{outputPath}</p>
+                )}
+              </div>
+            ) : null}
+            {jobs.length > 0 ? (
+              <div className="rounded-2xl border border-ink-700/60 bg-ink-950/60 p-4 text-xs text-ink-200">
+                <p className="mb-2 uppercase tracking-[0.2em] text-ink-200">Exports</p>
+                <div className="space-y-3">
+                  {[...jobs].reverse().map((job) => {
+                    const progress = job.status === "running" ? jobProgress[job.id] : undefined;
+                    const writtenPath = job.result?.output_path ?? null;
+                    return (
+                      <div key={job.id} className="space-y-1">
+                        <div className="flex items-center justify-between gap-3">
+                          <p className="truncate text-ink-100" title={job.output_path}>
+                            {fileName(writtenPath ?? job.output_path)}
+                          </p>
+                          <div className="flex shrink-0 items-center gap-2">
+                            <span className="uppercase tracking-[0.2em]">
+                              {JOB_STATUS_LABELS[job.status]}
+                            </span>
+                            {writtenPath ? (
+                              <button
+                                type="button"
+                                onClick={() => handleOpenOutput(writtenPath)}
+                                className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50"
+                              >
+                                Open
+                              </button>
+                            ) : null}
+                          </div>
+                        </div>
+                        {progress ? (
+                          <>
+                            <div
+                              role="progressbar"
+                              aria-valuemin={0}
+                              aria-valuemax={100}
+                              aria-valuenow={progress.percent}
+                              className="h-1.5 overflow-hidden rounded-full bg-ink-800"
+                            >
+                              <div
+                                className="h-full rounded-full bg-signal-400 transition-[width]"
+                                style={{ width: `${progress.percent}%` }}
+                              />
+                            </div>
+                            <p className="text-ink-300">
+                              {formatProgress(progress)} · {progress.percent}%
+                            </p>
+                          </>
+                        ) : null}
+                        {job.error ? <p className="break-all text-ink-300">{job.error}</p> : null}
+                      </div>
+                    );
+                  })}
+                </div>
               </div>
             ) : null}
           </div>
           <div className="flex h-full flex-col justify-between gap-4">
             <div>
-              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">Queued Items</p>
+              <div className="flex items-center justify-between gap-3">
+                <p className="text-xs uppercase tracking-[0.25em] text-ink-200">Queued Items</p>
+                <label className="flex items-center gap-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                  Add to queue
+                  <input
+                    type="checkbox"
+                    checked={keepQueue}
+                    onChange={(event) => setKeepQueue(event.target.checked)}
+                    disabled={state === "processing"}
+                    className="h-4 w-4 accent-signal-500"
+                  />
+                </label>
+              </div>
               <div className="mt-4 space-y-2 text-sm text-ink-100">
                 {items.length === 0 ? (
                   <p className="text-ink-200">No files loaded yet.</p>
@@ -510,17 +1573,454 @@ export default function App() {
                 </div>
               ) : null}
             </div>
-            <button
-              type="button"
-              className="rounded-full bg-signal-500 px-6 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-950 transition hover:bg-signal-400 disabled:cursor-not-allowed disabled:opacity-40"
-              onClick={handleConvert}
-              disabled={state === "processing"}
-            >
-              Convert to PDF
-            </button>
+            <div className="rounded-2xl border border-ink-800/70 bg-ink-950/60 p-4">
+              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">Page Setup</p>
+              <p className="mt-2 text-sm text-ink-200">
+                Margins in millimetres. Widen them to clear your printer's non-printable area.
+                Kept for the next time the app starts.
+              </p>
+              <div className="mt-4 grid grid-cols-2 gap-3">
+                {MARGIN_SIDES.map((side) => (
+                  <label
+                    key={side}
+                    className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200"
+                  >
+                    {side}
+                    <input
+                      type="number"
+                      min={0}
+                      step={1}
+                      value={margins[side]}
+                      onChange={(event) => handleMarginChange(side, event.target.value)}
+                      disabled={state === "processing"}
+                      className="w-16 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-right text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                    />
+                  </label>
+                ))}
+              </div>
+              <div className="mt-3 grid gap-3">
+                <label className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                  Page size
+                  <select
+                    value={pageSettings.page_size}
+                    onChange={(event) => {
+                      const pageSize = chosenOption(PAGE_SIZES, event.target.value);
+                      if (pageSize) {
+                        setPageSettings({ ...pageSettings, page_size: pageSize });
+                      }
+                    }}
+                    disabled={state === "processing"}
+                    className="w-28 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                  >
+                    {PAGE_SIZES.map(({ value, label }) => (
+                      <option key={value} value={value}>
+                        {label}
+                      </option>
+                    ))}
+                  </select>
+                </label>
+                <label className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                  Orientation
+                  <select
+                    value={pageSettings.orientation}
+                    onChange={(event) => {
+                      const orientation = chosenOption(ORIENTATIONS, event.target.value);
+                      if (orientation) {
+                        setPageSettings({ ...pageSettings, orientation });
+                      }
+                    }}
+                    disabled={state === "processing"}
+                    className="w-28 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                  >
+                    {ORIENTATIONS.map(({ value, label }) => (
+                      <option key={value} value={value}>
+                        {label}
+                      </option>
+                    ))}
+                  </select>
+                </label>
+                <label className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                  Font
+                  <select
+                    value={pageSettings.style.font_family}
+                    onChange={(event) => {
+                      const fontFamily = chosenOption(FONT_FAMILIES, event.target.value);
+                      if (fontFamily) {
+                        setPageSettings({
+                          ...pageSettings,
+                          style: { ...pageSettings.style, font_family: fontFamily },
+                        });
+                      }
+                    }}
+                    disabled={state === "processing"}
+                    className="w-28 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                  >
+                    {FONT_FAMILIES.map(({ value, label }) => (
+                      <option key={value} value={value}>
+                        {label}
+                      </option>
+                    ))}
+                  </select>
+                </label>
+              </div>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Notion export
+                <input
+                  type="checkbox"
+                  checked={notionExport}
+                  onChange={(event) => setNotionExport(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Obsidian vault
+                <input
+                  type="checkbox"
+                  checked={obsidianVault}
+                  onChange={(event) => setObsidianVault(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Hugo / Jekyll site
+                <input
+                  type="checkbox"
+                  checked={siteContent}
+                  onChange={(event) => setSiteContent(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                File name
+                <input
+                  type="text"
+                  value={outputName}
+                  placeholder="{title}-{date}.pdf"
+                  onChange={(event) => setOutputName(event.target.value)}
+                  disabled={state === "processing"}
+                  className="w-48 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Only pages
+                <input
+                  type="text"
+                  value={pageRange}
+                  placeholder="10-25"
+                  onChange={(event) => setPageRange(event.target.value)}
+                  disabled={state === "processing"}
+                  className="w-48 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Only sections
+                <input
+                  type="text"
+                  value={sectionTitles}
+                  placeholder="Setup, Usage"
+                  onChange={(event) => setSectionTitles(event.target.value)}
+                  disabled={state === "processing"}
+                  className="w-48 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                />
+              </label>
+              <div className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Split every
+                <div className="flex items-center gap-2">
+                  <input
+                    type="number"
+                    min={1}
+                    step={1}
+                    value={splitPages}
+                    placeholder="-"
+                    aria-label="Pages per part"
+                    onChange={(event) => setSplitPages(event.target.value)}
+                    disabled={state === "processing"}
+                    className="w-16 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-right text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                  />
+                  pages or
+                  <input
+                    type="number"
+                    min={1}
+                    step={1}
+                    value={splitMegabytes}
+                    placeholder="-"
+                    aria-label="Megabytes per part"
+                    onChange={(event) => setSplitMegabytes(event.target.value)}
+                    disabled={state === "processing"}
+                    className="w-16 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-right text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                  />
+                  MB
+                </div>
+              </div>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Number repeated exports
+                <input
+                  type="checkbox"
+                  checked={numberExports}
+                  onChange={(event) => setNumberExports(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                One PDF per subfolder
+                <input
+                  type="checkbox"
+                  checked={perFolder}
+                  onChange={(event) => setPerFolder(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              <label className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200">
+                Open when done
+                <input
+                  type="checkbox"
+                  checked={openWhenDone}
+                  onChange={(event) => setOpenWhenDone(event.target.checked)}
+                  disabled={state === "processing"}
+                  className="h-4 w-4 accent-signal-500"
+                />
+              </label>
+              {[
+                { label: "Pages before", paths: pdfsBefore, setPaths: setPdfsBefore },
+                { label: "Pages after", paths: pdfsAfter, setPaths: setPdfsAfter },
+              ].map(({ label, paths, setPaths }) => (
+                <div
+                  key={label}
+                  className="mt-3 flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200"
+                >
+                  {label}
+                  <div className="flex min-w-0 items-center gap-2">
+                    <span
+                      className="truncate text-sm normal-case tracking-normal text-ink-100"
+                      title={paths.join("\n")}
+                    >
+                      {paths.length > 0
+                        ? paths.map((path) => path.split(/[/\\]/).pop() ?? path).join(", ")
+                        : "No PDFs"}
+                    </span>
+                    <button
+                      type="button"
+                      onClick={() => handleChoosePdfs(setPaths)}
+                      disabled={state === "processing"}
+                      className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                    >
+                      Choose
+                    </button>
+                    {paths.length > 0 ? (
+                      <button
+                        type="button"
+                        onClick={() => setPaths([])}
+                        disabled={state === "processing"}
+                        className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                      >
+                        Clear
+                      </button>
+                    ) : null}
+                  </div>
+                </div>
+              ))}
+            </div>
+            <div className="rounded-2xl border border-ink-800/70 bg-ink-950/60 p-4">
+              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">File Filters</p>
+              <p className="mt-2 text-sm text-ink-200">
+                Comma-separated globs relative to each folder. Includes pick markdown files;
+                excludes drop any file.
+              </p>
+              <div className="mt-4 space-y-3">
+                {FILTER_FIELDS.map(({ key, placeholder }) => (
+                  <label
+                    key={key}
+                    className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200"
+                  >
+                    {key}
+                    <input
+                      type="text"
+                      value={filters[key]}
+                      placeholder={placeholder}
+                      onChange={(event) => handleFilterChange(key, event.target.value)}
+                      disabled={state === "processing"}
+                      className="w-48 rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm normal-case tracking-normal text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                    />
+                  </label>
+                ))}
+                {SCAN_TOGGLES.map(({ key, label }) => (
+                  <label
+                    key={key}
+                    className="flex items-center justify-between gap-3 rounded-2xl border border-ink-800/70 bg-ink-950/70 px-3 py-2 text-[11px] uppercase tracking-[0.2em] text-ink-200"
+                  >
+                    {label}
+                    <input
+                      type="checkbox"
+                      checked={scanToggles[key]}
+                      onChange={(event) => handleScanToggleChange(key, event.target.checked)}
+                      disabled={state === "processing"}
+                      className="h-4 w-4 accent-signal-500"
+                    />
+                  </label>
+                ))}
+              </div>
+            </div>
+            <div className="flex flex-wrap items-center gap-3">
+              <button
+                type="button"
+                className="rounded-full border border-ink-700/70 bg-ink-900/70 px-5 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                onClick={handleMergePdfs}
+                disabled={state === "processing"}
+              >
+                Merge PDFs
+              </button>
+              <button
+                type="button"
+                className="rounded-full border border-ink-700/70 bg-ink-900/70 px-5 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                onClick={() => handlePreview(preview?.page ?? 1)}
+                disabled={state === "processing"}
+              >
+                Preview
+              </button>
+              <button
+                type="button"
+                className="rounded-full border border-ink-700/70 bg-ink-900/70 px-5 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                onClick={handleStartLivePreview}
+                disabled={state === "processing"}
+              >
+                Live Preview
+              </button>
+              <button
+                type="button"
+                className="rounded-full border border-ink-700/70 bg-ink-900/70 px-5 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                onClick={() => handleConvert("txt")}
+                disabled={state === "processing"}
+              >
+                Export Text
+              </button>
+              <button
+                type="button"
+                className="rounded-full bg-signal-500 px-6 py-3 text-xs font-semibold uppercase tracking-[0.3em] text-ink-950 transition hover:bg-signal-400 disabled:cursor-not-allowed disabled:opacity-40"
+                onClick={() => (perFolder ? handleConvertFolders() : handleConvert("pdf"))}
+                disabled={state === "processing"}
+              >
+                Convert to PDF
+              </button>
+            </div>
           </div>
         </section>
 
+        {preview ? (
+          <section className="rounded-3xl border border-ink-800/80 bg-ink-900/70 p-6">
+            <div className="flex flex-wrap items-center justify-between gap-3">
+              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">
+                Preview · Page {preview.page} of {preview.pageCount}
+              </p>
+              <div className="flex items-center gap-2">
+                <button
+                  type="button"
+                  onClick={() => handlePreview(preview.page - 1)}
+                  disabled={state === "processing" || preview.page <= 1}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                >
+                  Previous
+                </button>
+                <button
+                  type="button"
+                  onClick={() => handlePreview(preview.page + 1)}
+                  disabled={state === "processing" || preview.page >= preview.pageCount}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                >
+                  Next
+                </button>
+                <button
+                  type="button"
+                  onClick={handleClosePreview}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50"
+                >
+                  Close
+                </button>
+              </div>
+            </div>
+            <img
+              src={preview.url}
+              alt={`Page ${preview.page} of the exported PDF`}
+              className="mx-auto mt-4 max-w-full rounded-lg bg-white shadow-lg"
+            />
+          </section>
+        ) : null}
+
+        {livePreview ? (
+          <section className="rounded-3xl border border-ink-800/80 bg-ink-900/70 p-6">
+            <div className="flex flex-wrap items-center justify-between gap-3">
+              <p className="text-xs uppercase tracking-[0.25em] text-ink-200">
+                Live Preview · Page {livePreview.page} of {livePreview.pageCount}
+              </p>
+              <div className="flex items-center gap-2">
+                <button
+                  type="button"
+                  onClick={() =>
+                    setLivePreview({ ...livePreview, page: livePreview.page - 1 })
+                  }
+                  disabled={livePreview.page <= 1}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                >
+                  Previous
+                </button>
+                <button
+                  type="button"
+                  onClick={() =>
+                    setLivePreview({ ...livePreview, page: livePreview.page + 1 })
+                  }
+                  disabled={livePreview.page >= livePreview.pageCount}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50 disabled:cursor-not-allowed disabled:opacity-40"
+                >
+                  Next
+                </button>
+                <button
+                  type="button"
+                  onClick={handleStopLivePreview}
+                  className="rounded-full border border-ink-700/70 bg-ink-900/70 px-3 py-1 text-[11px] font-semibold uppercase tracking-[0.25em] text-ink-100 transition hover:border-ink-400/70 hover:text-ink-50"
+                >
+                  Close
+                </button>
+              </div>
+            </div>
+            <div className="mt-4 grid gap-4 md:grid-cols-2">
+              <div className="flex flex-col gap-3">
+                <select
+                  value={livePreview.file}
+                  onChange={(event) => handleLiveFileChange(event.target.value)}
+                  aria-label="File to edit"
+                  className="rounded-lg border border-ink-700 bg-ink-900 px-2 py-1 text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                >
+                  {livePreview.files.map((file) => (
+                    <option key={file} value={file}>
+                      {fileName(file)}
+                    </option>
+                  ))}
+                </select>
+                <textarea
+                  value={livePreview.text}
+                  onChange={(event) => handleLiveEdit(livePreview.file, event.target.value)}
+                  aria-label="Markdown of the file"
+                  rows={24}
+                  className="w-full flex-1 resize-y rounded-2xl border border-ink-700 bg-ink-950/70 px-4 py-3 font-mono text-sm text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+                />
+              </div>
+              {livePages[livePreview.page] ? (
+                <img
+                  src={livePages[livePreview.page]}
+                  alt={`Page ${livePreview.page} of the live preview`}
+                  className="mx-auto max-w-full rounded-lg bg-white shadow-lg"
+                />
+              ) : (
+                <p className="text-xs text-ink-300">Laying out page {livePreview.page}.</p>
+              )}
+            </div>
+          </section>
+        ) : null}
+
         <section
           className="float-in grid gap-4 rounded-3xl border border-ink-800/70 bg-ink-950/60 p-6 text-xs text-ink-200 md:grid-cols-3"
           style={{ animationDelay: "320ms" }}
@@ -1,9 +1,9 @@
-import { useCallback, useState, type DragEvent } from "react";
+import { useCallback, useState, type DragEvent, type FormEvent } from "react";
 
 export type DropItem = {
   name: string;
   path: string;
-  kind: "file" | "directory" | "zip" | "unknown";
+  kind: "file" | "directory" | "archive" | "url" | "unknown";
 };
 
 export type DropZoneProps = {
@@ -12,15 +12,24 @@ export type DropZoneProps = {
   onError?: (message: string) => void;
   onBrowseFiles?: () => void;
   onBrowseFolder?: () => void;
+  onUrl?: (url: string) => void;
 };
 
+const ARCHIVE_EXTENSIONS = [".zip", ".tar", ".tar.gz", ".tgz", ".7z"];
+
+export const isArchive = (name: string): boolean =>
+  ARCHIVE_EXTENSIONS.some((extension) => name.endsWith(extension));
+
 const classifyItem = (name: string): DropItem["kind"] => {
-  if (name.endsWith(".zip")) {
-    return "zip";
+  if (isArchive(name)) {
+    return "archive";
   }
   if (name.endsWith(".md") || name.endsWith(".markdown")) {
     return "file";
   }
+  if ([".html", ".htm", ".csv", ".rst"].some((extension) => name.endsWith(extension))) {
+    return "file";
+  }
   if (!name.includes(".")) {
     return "directory";
   }
@@ -37,6 +46,8 @@ const readFilePath = (file: File): string | null => {
 
 const resolvePath = (file: File): string => readFilePath(file) ?? file.name;
 
+const isUrl = (value: string): boolean => /^https?:\/\//i.test(value);
+
 const isAbsolutePath = (value: string): boolean => {
   if (value.startsWith("/")) {
     return true;
@@ -53,8 +64,10 @@ export default function DropZone({
   onError,
   onBrowseFiles,
   onBrowseFolder,
+  onUrl,
 }: DropZoneProps) {
   const [isDragging, setIsDragging] = useState(false);
+  const [url, setUrl] = useState("");
 
   const handleFiles = useCallback(
     (fileList: FileList | null) => {
@@ -78,7 +91,7 @@ export default function DropZone({
       const hasAccepted = items.some((item) => item.kind !== "unknown");
 
       if (!hasAccepted) {
-        onError?.("Drop a markdown file, a directory, or a zip archive.");
+        onError?.("Drop a markdown file, a directory, or a zip, tar or 7z archive.");
         return;
       }
 
@@ -100,6 +113,20 @@ export default function DropZone({
     [disabled, handleFiles]
   );
 
+  const handleUrlSubmit = useCallback(
+    (event: FormEvent<HTMLFormElement>) => {
+      event.preventDefault();
+      const trimmed = url.trim();
+      if (!isUrl(trimmed)) {
+        onError?.("Enter an http:// or https:// link to a markdown file.");
+        return;
+      }
+      onUrl?.(trimmed);
+      setUrl("");
+    },
+    [onError, onUrl, url]
+  );
+
   const handleDragOver = useCallback((event: DragEvent<HTMLDivElement>) => {
     event.preventDefault();
     event.stopPropagation();
@@ -130,11 +157,11 @@ export default function DropZone({
           <div>
             <p className="text-xs uppercase tracking-[0.3em] text-ink-200">drop zone</p>
             <h2 className="mt-3 font-display text-3xl text-ink-100">
-              Drag files, directories, or zip archives
+              Drag files, directories, or archives
             </h2>
           </div>
           <div className="rounded-full border border-ink-700 px-4 py-2 text-xs text-ink-200">
-            .md / .markdown / .zip
+            .md / .markdown / .zip / .tar.gz / .7z
           </div>
         </div>
 
@@ -169,6 +196,24 @@ export default function DropZone({
                 Browse Folder
               </button>
             </div>
+            <form className="flex w-full flex-wrap justify-center gap-2" onSubmit={handleUrlSubmit}>
+              <input
+                type="url"
+                value={url}
+                onChange={(event) => setUrl(event.target.value)}
+                placeholder="https://raw.githubusercontent.com/…/README.md"
+                aria-label="Markdown URL"
+                disabled={!onUrl || disabled}
+                className="min-w-0 flex-1 rounded-full border border-ink-700 bg-ink-900 px-4 py-2 text-xs text-ink-100 focus-visible:border-ink-400 focus-visible:outline-none"
+              />
+              <button
+                type="submit"
+                className="rounded-full border border-ink-600 px-4 py-2 text-xs font-semibold text-ink-100 transition hover:border-ink-400 disabled:cursor-not-allowed disabled:opacity-50"
+                disabled={!onUrl || disabled}
+              >
+                Add URL
+              </button>
+            </form>
           </div>
         </div>
       </div>